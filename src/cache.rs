@@ -0,0 +1,104 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for caching the actions of states whose actions are expensive to enumerate.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::mdp::State;
+
+/// How often the action cache of a trainer held the actions of the states they were looked up
+/// for, as returned by [AgentTrainer::action_cache_stats()](crate::AgentTrainer::action_cache_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// The number of lookups of states whose actions were cached.
+    pub hits: u64,
+    /// The number of lookups of states whose actions had to be enumerated.
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Returns the share of lookups whose actions were cached, or `None` if there were none.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            None
+        } else {
+            Some(self.hits as f64 / lookups as f64)
+        }
+    }
+}
+
+/// A cache of the [actions()](State::actions) of up to `capacity` states, which forgets the
+/// least recently used state when it is full.
+pub(crate) struct ActionCache<S: State> {
+    capacity: usize,
+    lru: Mutex<Lru<S>>,
+}
+
+/// The cached actions, with the time every state was last looked up at.
+struct Lru<S: State> {
+    entries: HashMap<S, (Vec<S::A>, u64)>,
+    /// The cached states by the time they were last looked up at, oldest first.
+    order: BTreeMap<u64, S>,
+    time: u64,
+    stats: CacheStats,
+}
+
+impl<S: State> ActionCache<S> {
+    /// Creates an empty cache for the actions of `capacity` states.
+    pub(crate) fn new(capacity: usize) -> ActionCache<S> {
+        ActionCache {
+            capacity,
+            lru: Mutex::new(Lru {
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+                time: 0,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    /// Returns the number of states whose actions are cached at most.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the actions of `state`, from the cache if they are cached.
+    pub(crate) fn actions(&self, state: &S) -> Vec<S::A> {
+        let mut lru = self.lru.lock().unwrap();
+        let Lru {
+            entries,
+            order,
+            time,
+            stats,
+        } = &mut *lru;
+        *time += 1;
+        if let Some((actions, used)) = entries.get_mut(state) {
+            stats.hits += 1;
+            let state = order.remove(used).expect("cached states are ordered");
+            *used = *time;
+            order.insert(*time, state);
+            return actions.clone();
+        }
+
+        stats.misses += 1;
+        let actions = state.actions();
+        if entries.len() >= self.capacity {
+            if let Some((_, oldest)) = order.pop_first() {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(state.clone(), (actions.clone(), *time));
+        order.insert(*time, state.clone());
+        actions
+    }
+
+    /// Returns how often the cache held the actions that were looked up.
+    pub(crate) fn stats(&self) -> CacheStats {
+        self.lru.lock().unwrap().stats
+    }
+}
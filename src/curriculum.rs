@@ -0,0 +1,104 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for training on a sequence of ever harder environments.
+
+use crate::EvalStats;
+
+/// A stage of a [Curriculum]: the environment to train on, as a function returning a fresh
+/// agent for every episode, the largest number of episodes to train on it, and optionally the
+/// mean reward of greedy evaluations at which training advances to the next stage early.
+pub struct Stage<G> {
+    pub(crate) make_agent: Box<dyn FnMut() -> G>,
+    pub(crate) episodes: usize,
+    pub(crate) threshold: Option<Threshold>,
+}
+
+/// When a [Stage] is evaluated, and the mean reward at which it is passed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Threshold {
+    pub(crate) mean_reward: f64,
+    pub(crate) every: usize,
+    pub(crate) rollouts: usize,
+}
+
+impl<G> Stage<G> {
+    /// Creates a stage which trains for `episodes` episodes, each with a fresh agent from
+    /// `make_agent`.
+    pub fn new(episodes: usize, make_agent: impl FnMut() -> G + 'static) -> Stage<G> {
+        Stage {
+            make_agent: Box::new(make_agent),
+            episodes,
+            threshold: None,
+        }
+    }
+
+    /// Evaluates what was learned with `rollouts` greedy episodes after every `every` episodes
+    /// of the stage, and advances to the next stage as soon as their mean total reward is at
+    /// least `mean_reward`. Otherwise, the stage trains for all of its episodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` or `rollouts` is `0`.
+    pub fn with_threshold(mut self, mean_reward: f64, every: usize, rollouts: usize) -> Self {
+        assert!(every > 0, "evaluations must be at least one episode apart");
+        assert!(rollouts > 0, "evaluations must play at least one episode");
+        self.threshold = Some(Threshold {
+            mean_reward,
+            every,
+            rollouts,
+        });
+        self
+    }
+}
+
+/// A sequence of [Stage]s of ever harder environments, which
+/// [AgentTrainer::train_curriculum()](crate::AgentTrainer::train_curriculum) trains through in
+/// order. All stages train the same values, so what was learned in the easier stages carries
+/// over to the harder ones, as far as they share states. The `curriculum` example ramps the size
+/// of a grid up from 3x3 to 8x8.
+pub struct Curriculum<G> {
+    pub(crate) stages: Vec<Stage<G>>,
+}
+
+impl<G> Curriculum<G> {
+    /// Creates a curriculum without stages.
+    pub fn new() -> Curriculum<G> {
+        Curriculum { stages: Vec::new() }
+    }
+
+    /// Adds `stage` after the stages added before.
+    pub fn with_stage(mut self, stage: Stage<G>) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Returns the number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Returns whether there are no stages.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+impl<G> Default for Curriculum<G> {
+    fn default() -> Self {
+        Curriculum::new()
+    }
+}
+
+/// What happened during a stage of a [Curriculum], as returned by
+/// [AgentTrainer::train_curriculum()](crate::AgentTrainer::train_curriculum).
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageReport {
+    /// The number of episodes trained in the stage.
+    pub episodes: usize,
+    /// Whether an evaluation reached the threshold of the stage, which ended it early.
+    pub passed: bool,
+    /// The statistics of the last evaluation of the stage, or `None` if it wasn't evaluated.
+    pub evaluation: Option<EvalStats>,
+}
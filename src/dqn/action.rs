@@ -0,0 +1,123 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the actions of the DQN trainer, which are identified by the index of their output
+//! of the Q-network.
+
+use super::action_index;
+
+/// The actions of a DQN, each of which is identified by an index below `ACTION_SIZE`, which is
+/// the output of the Q-network that estimates its value. The trainer works with the indices
+/// alone: it takes [index()](DiscreteActionSpace::index) of every action it learns from or looks
+/// up the value of, and [from_index()](DiscreteActionSpace::from_index) of the output with the
+/// highest value to pick an action.
+///
+/// Actions which can be encoded as one-hot arrays, that is, which implement both
+/// `Into<[f32; ACTION_SIZE]>` and `From<[f32; ACTION_SIZE]>`, implement this trait already: their
+/// index is that of the highest positive element of their array, and the action of an index is
+/// decoded from the array with a `1` at that index. Actions with more structure, such as those
+/// of board games, are better mapped to indices with a table, which can't lose anything, unlike
+/// an encoding into floats.
+///
+/// # Example
+///
+/// ```
+/// use rurel::dqn::{DQNAgentTrainer, DiscreteActionSpace};
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Position(usize);
+/// #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+/// enum Move {
+///     Left,
+///     Stay,
+///     Right,
+/// }
+///
+/// // The table of all moves: the index of a move is its position in the table
+/// const MOVES: [Move; 3] = [Move::Left, Move::Stay, Move::Right];
+///
+/// impl DiscreteActionSpace<3> for Move {
+///     fn index(&self) -> usize {
+///         MOVES.iter().position(|m| m == self).unwrap()
+///     }
+///     fn from_index(index: usize) -> Move {
+///         MOVES[index]
+///     }
+/// }
+///
+/// impl State for Position {
+///     type A = Move;
+///     fn reward(&self) -> f64 {
+///         if self.0 == 3 { 1.0 } else { 0.0 }
+///     }
+///     fn actions(&self) -> Vec<Move> {
+///         MOVES.to_vec()
+///     }
+/// }
+///
+/// impl From<Position> for [f32; 4] {
+///     fn from(p: Position) -> Self {
+///         std::array::from_fn(|i| if i == p.0 { 1.0 } else { 0.0 })
+///     }
+/// }
+///
+/// struct Walker(Position);
+///
+/// impl Agent<Position> for Walker {
+///     fn current_state(&self) -> &Position {
+///         &self.0
+///     }
+///     fn take_action(&mut self, m: &Move) {
+///         let x = self.0 .0;
+///         self.0 = Position(match m {
+///             Move::Left => x.saturating_sub(1),
+///             Move::Stay => x,
+///             Move::Right => (x + 1).min(3),
+///         });
+///     }
+/// }
+///
+/// let mut trainer = DQNAgentTrainer::<Position, 4, 3, 16>::new(0.9, 1e-3);
+/// for _ in 0..20 {
+///     trainer.train(
+///         &mut Walker(Position(0)),
+///         &mut FixedIterations::new(20),
+///         &RandomExploration,
+///     );
+/// }
+///
+/// // Every output of the network is the value of the move at the same index of the table
+/// let values = trainer.expected_value(&Position(2));
+/// let action_values = trainer.action_values(&Position(2));
+/// for m in MOVES {
+///     assert_eq!(action_values[&m], values[m.index()] as f64);
+/// }
+/// assert!(MOVES.contains(&trainer.best_action(&Position(2)).unwrap()));
+/// ```
+pub trait DiscreteActionSpace<const ACTION_SIZE: usize>: Sized {
+    /// Returns the index of this action, which is below `ACTION_SIZE`. The trainer skips actions
+    /// with a larger index where it learns or looks up values, after panicking in debug builds.
+    fn index(&self) -> usize;
+
+    /// Returns the action with the given index, which is below `ACTION_SIZE`.
+    fn from_index(index: usize) -> Self;
+}
+
+impl<A, const ACTION_SIZE: usize> DiscreteActionSpace<ACTION_SIZE> for A
+where
+    A: Clone + Into<[f32; ACTION_SIZE]> + From<[f32; ACTION_SIZE]>,
+{
+    fn index(&self) -> usize {
+        action_index(&self.clone().into())
+    }
+
+    fn from_index(index: usize) -> A {
+        let mut one_hot = [0.0; ACTION_SIZE];
+        one_hot[index] = 1.0;
+        A::from(one_hot)
+    }
+}
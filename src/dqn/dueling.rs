@@ -0,0 +1,175 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the dueling architecture of the Q-network.
+
+use dfdx::prelude::*;
+
+/// The dueling architecture of the Q-network: two hidden layers of `INNER_SIZE` neurons, each
+/// followed by the activation function `Activation` like in [QNetwork](super::QNetwork), and a
+/// [DuelingHead]. Use it as the architecture of a
+/// [DQNAgentTrainer](super::DQNAgentTrainer), instead of the default
+/// [QNetwork](super::QNetwork):
+///
+/// ```
+/// use rurel::dqn::{DQNAgentTrainer, DuelingQNetwork};
+/// use dfdx::prelude::AutoDevice;
+/// use rurel::mdp::State;
+///
+/// // Unrewarded positions on a line: `Position(x)` is encoded as `[x]`, and `Move(-1)` and
+/// // `Move(1)` one-hot
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Move(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = Move;
+/// #     fn reward(&self) -> f64 {
+/// #         0.0
+/// #     }
+/// #     fn actions(&self) -> Vec<Move> {
+/// #         vec![Move(-1), Move(1)]
+/// #     }
+/// # }
+/// #
+/// # impl From<Position> for [f32; 1] {
+/// #     fn from(p: Position) -> Self {
+/// #         [p.0 as f32]
+/// #     }
+/// # }
+/// # impl From<Move> for [f32; 2] {
+/// #     fn from(m: Move) -> Self {
+/// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+/// #     }
+/// # }
+/// # impl From<[f32; 2]> for Move {
+/// #     fn from(v: [f32; 2]) -> Self {
+/// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+/// #     }
+/// # }
+///
+/// let trainer =
+///     DQNAgentTrainer::<Position, 1, 2, 32, AutoDevice, DuelingQNetwork<1, 2, 32>>::new(0.9, 1e-3);
+/// assert!(trainer.best_action(&Position(0)).is_some());
+/// ```
+///
+/// The saved parameters of the head are named differently from those of the last layer of
+/// [QNetwork](super::QNetwork), so loading a model saved with one architecture into a trainer
+/// with the other fails, instead of silently producing a broken network.
+pub type DuelingQNetwork<
+    const STATE_SIZE: usize,
+    const ACTION_SIZE: usize,
+    const INNER_SIZE: usize,
+    Activation = ReLU,
+> = (
+    (Linear<STATE_SIZE, INNER_SIZE>, Activation),
+    (Linear<INNER_SIZE, INNER_SIZE>, Activation),
+    DuelingHead<INNER_SIZE, ACTION_SIZE>,
+);
+
+/// The output layer of a dueling Q-network, which maps `IN` features to the values of `ACTIONS`
+/// actions. It splits into a stream estimating the value `V` of the state, and a stream estimating
+/// the advantage `A` of every action over the others, and recombines them as
+/// `V + (A - mean(A))`. Because the value of the state is learned separately, it can be learned
+/// from every action, which helps in states where the choice of action barely matters.
+///
+/// This is the architecture used to build a [BuiltDuelingHead]. The advantages are centred, so
+/// the mean of the outputs is the value of the state:
+///
+/// ```
+/// use dfdx::prelude::*;
+/// use rurel::dqn::DuelingHead;
+///
+/// let dev = Cpu::default();
+/// let head = dev.build_module::<DuelingHead<4, 3>, f32>();
+/// let x = dev.tensor([0.5, -1.0, 2.0, 0.25]);
+///
+/// let qs = head.forward(x.clone()).array();
+/// let value = head.value.forward(x).array()[0];
+/// let mean = qs.iter().sum::<f32>() / 3.0;
+/// assert!((mean - value).abs() < 1e-5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuelingHead<const IN: usize, const ACTIONS: usize>;
+
+impl<const IN: usize, const ACTIONS: usize, D: Device<f32>> BuildOnDevice<D, f32>
+    for DuelingHead<IN, ACTIONS>
+{
+    type Built = BuiltDuelingHead<IN, ACTIONS, f32, D>;
+}
+
+#[cfg(feature = "save")]
+impl<const IN: usize, const ACTIONS: usize> super::Activations for DuelingHead<IN, ACTIONS> {}
+
+/// A [DuelingHead], built on a device.
+#[derive(Debug, Clone)]
+pub struct BuiltDuelingHead<const IN: usize, const ACTIONS: usize, E: Dtype, D: Storage<E>> {
+    /// The stream estimating the value of the state.
+    pub value: modules::Linear<IN, 1, E, D>,
+    /// The stream estimating the advantage of every action.
+    pub advantage: modules::Linear<IN, ACTIONS, E, D>,
+}
+
+impl<const IN: usize, const ACTIONS: usize, D: Device<f32>> TensorCollection<f32, D>
+    for BuiltDuelingHead<IN, ACTIONS, f32, D>
+{
+    type To<E2: Dtype, D2: Device<E2>> = BuiltDuelingHead<IN, ACTIONS, E2, D2>;
+
+    fn iter_tensors<V: ModuleVisitor<Self, f32, D>>(
+        visitor: &mut V,
+    ) -> Result<Option<Self::To<V::E2, V::D2>>, V::Err> {
+        visitor.visit_fields(
+            (
+                Self::module("value", |s| &s.value, |s| &mut s.value),
+                Self::module("advantage", |s| &s.advantage, |s| &mut s.advantage),
+            ),
+            |(value, advantage)| BuiltDuelingHead { value, advantage },
+        )
+    }
+}
+
+impl<const IN: usize, const ACTIONS: usize, D: Device<f32>, T> Module<Tensor<Rank1<IN>, f32, D, T>>
+    for BuiltDuelingHead<IN, ACTIONS, f32, D>
+where
+    T: Tape<f32, D> + Merge<T>,
+{
+    type Output = Tensor<Rank1<ACTIONS>, f32, D, T>;
+    type Error = D::Err;
+
+    fn try_forward(&self, x: Tensor<Rank1<IN>, f32, D, T>) -> Result<Self::Output, D::Err> {
+        let value = self.value.try_forward(x.with_empty_tape())?;
+        let advantage = self.advantage.try_forward(x)?;
+        let mean = advantage
+            .with_empty_tape()
+            .try_mean::<Rank0, _>()?
+            .try_broadcast()?;
+        let value = value.try_sum::<Rank0, _>()?.try_broadcast()?;
+        advantage.try_sub(mean)?.try_add(value)
+    }
+}
+
+impl<const IN: usize, const ACTIONS: usize, D: Device<f32>, T>
+    Module<Tensor<(usize, Const<IN>), f32, D, T>> for BuiltDuelingHead<IN, ACTIONS, f32, D>
+where
+    T: Tape<f32, D> + Merge<T>,
+{
+    type Output = Tensor<(usize, Const<ACTIONS>), f32, D, T>;
+    type Error = D::Err;
+
+    fn try_forward(
+        &self,
+        x: Tensor<(usize, Const<IN>), f32, D, T>,
+    ) -> Result<Self::Output, D::Err> {
+        let value = self.value.try_forward(x.with_empty_tape())?;
+        let advantage = self.advantage.try_forward(x)?;
+        let shape = *advantage.shape();
+        let mean = advantage
+            .with_empty_tape()
+            .try_mean::<(usize,), _>()?
+            .try_broadcast_like(&shape)?;
+        let value = value.try_sum::<(usize,), _>()?.try_broadcast_like(&shape)?;
+        advantage.try_sub(mean)?.try_add(value)
+    }
+}
@@ -0,0 +1,129 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for encoding states into the values the Q-network takes.
+
+use crate::RurelError;
+
+/// Encodes a state into the values a Q-network takes, as an alternative to implementing
+/// `From<State> for [f32; N]` directly.
+///
+/// The number of values is [LEN](StateEncoder::LEN), which can be used wherever the size of the
+/// encoding is needed, such as the `STATE_SIZE` of a
+/// [DQNAgentTrainer](crate::dqn::DQNAgentTrainer), so changing the encoding only means changing
+/// `LEN` and [encode()](StateEncoder::encode). The conversion into an array that the trainer needs
+/// is then written with [encode_state()], which checks the length of every encoding:
+///
+/// ```
+/// use rurel::dqn::{encode_state, DQNAgentTrainer, StateEncoder};
+/// use rurel::mdp::State;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Position {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// impl State for Position {
+///     type A = Move;
+///     fn reward(&self) -> f64 {
+///         -(self.x.abs() + self.y.abs()) as f64
+///     }
+///     fn actions(&self) -> Vec<Move> {
+///         vec![Move(0), Move(1)]
+///     }
+/// }
+///
+/// impl StateEncoder for Position {
+///     const LEN: usize = 2;
+///     fn encode(&self) -> Vec<f32> {
+///         vec![self.x as f32, self.y as f32]
+///     }
+/// }
+///
+/// impl From<Position> for [f32; Position::LEN] {
+///     fn from(p: Position) -> Self {
+///         encode_state(&p).unwrap()
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Move(usize);
+///
+/// impl From<Move> for [f32; 2] {
+///     fn from(m: Move) -> Self {
+///         let mut v = [0.0; 2];
+///         v[m.0] = 1.0;
+///         v
+///     }
+/// }
+///
+/// impl From<[f32; 2]> for Move {
+///     fn from(v: [f32; 2]) -> Self {
+///         Move(if v[0] > v[1] { 0 } else { 1 })
+///     }
+/// }
+///
+/// let trainer =
+///     DQNAgentTrainer::<Position, { Position::LEN }, 2, 16>::new_encoded(0.9, 1e-3).unwrap();
+/// let values = trainer.expected_value(&Position { x: 1, y: 2 });
+/// assert!(values.iter().all(|v| v.is_finite()));
+/// ```
+pub trait StateEncoder {
+    /// The number of values every state is encoded into.
+    const LEN: usize;
+
+    /// Encodes this state into [LEN](StateEncoder::LEN) values.
+    fn encode(&self) -> Vec<f32>;
+}
+
+/// Encodes `state` into an array of `N` values, for implementing `From<State> for [f32; N]` with
+/// a [StateEncoder].
+///
+/// Fails with [RurelError::EncodingLength] if `N` isn't [StateEncoder::LEN], or if
+/// [encode()](StateEncoder::encode) returned another number of values.
+///
+/// # Examples
+///
+/// ```
+/// use rurel::dqn::{encode_state, StateEncoder};
+/// use rurel::RurelError;
+///
+/// struct Broken;
+///
+/// impl StateEncoder for Broken {
+///     const LEN: usize = 3;
+///     fn encode(&self) -> Vec<f32> {
+///         vec![0.0; 2]
+///     }
+/// }
+///
+/// let result = encode_state::<_, 3>(&Broken);
+/// assert!(matches!(
+///     result,
+///     Err(RurelError::EncodingLength { expected: 3, found: 2 })
+/// ));
+/// ```
+pub fn encode_state<S: StateEncoder, const N: usize>(state: &S) -> Result<[f32; N], RurelError> {
+    check_len::<S, N>()?;
+    let values = state.encode();
+    values
+        .try_into()
+        .map_err(|values: Vec<f32>| RurelError::EncodingLength {
+            expected: N,
+            found: values.len(),
+        })
+}
+
+/// Fails with [RurelError::EncodingLength] if `S` isn't encoded into `N` values.
+pub(crate) fn check_len<S: StateEncoder, const N: usize>() -> Result<(), RurelError> {
+    if S::LEN == N {
+        Ok(())
+    } else {
+        Err(RurelError::EncodingLength {
+            expected: N,
+            found: S::LEN,
+        })
+    }
+}
@@ -0,0 +1,292 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for ensembles of Q-networks, whose disagreement estimates their uncertainty.
+
+use std::collections::HashMap;
+
+use dfdx::prelude::*;
+
+#[cfg(feature = "save")]
+use super::Activations;
+use super::{checked_index, DQNAgentTrainer, DiscreteActionSpace, QNetwork, QNetworkModel};
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::terminate::TerminationStrategy;
+#[cfg(feature = "save")]
+use crate::RurelError;
+
+/// The value of an action as estimated by a [DQNEnsemble]: the mean of the values of its
+/// members, and the variance of these values around the mean.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnsembleValue {
+    /// The mean of the values of the members.
+    pub mean: f64,
+    /// The variance of the values of the members. It is high where the members disagree, which
+    /// is where they learned little, such as in states they haven't been trained on.
+    pub variance: f64,
+}
+
+/// An ensemble of [DQNAgentTrainer]s, whose networks start from different random weights and
+/// are trained on episodes of their own. The members learn similar values where they have seen
+/// enough of the task, and disagree elsewhere, so the variance of their values estimates how
+/// uncertain the ensemble is, which can be used for risk-aware control or to guide exploration.
+///
+/// # Example
+///
+/// ```
+/// use rurel::dqn::DQNEnsemble;
+/// use rurel::dqn::DQNAgentTrainer;
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::EpsilonGreedy;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// // A `Walker` moves by `Move(-1)` or `Move(1)` between `Position(0)` and `Position(4)`, which
+/// // are one-hot encoded, and is rewarded by how close it is to 2
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Move(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = Move;
+/// #     fn reward(&self) -> f64 {
+/// #         -((self.0 - 2).abs() as f64)
+/// #     }
+/// #     fn actions(&self) -> Vec<Move> {
+/// #         vec![Move(-1), Move(1)]
+/// #     }
+/// # }
+/// #
+/// # impl From<Position> for [f32; 5] {
+/// #     fn from(p: Position) -> Self {
+/// #         let mut v = [0.0; 5];
+/// #         v[p.0 as usize] = 1.0;
+/// #         v
+/// #     }
+/// # }
+/// # impl From<Move> for [f32; 2] {
+/// #     fn from(m: Move) -> Self {
+/// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+/// #     }
+/// # }
+/// # impl From<[f32; 2]> for Move {
+/// #     fn from(v: [f32; 2]) -> Self {
+/// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, m: &Move) {
+/// #         self.0 = Position((self.0 .0 + m.0).clamp(0, 4));
+/// #     }
+/// # }
+///
+/// // Five networks, seeded with 0 to 4
+/// let mut ensemble = DQNEnsemble::<Position, 5, 2, 16>::new(5, 0, |dev| {
+///     DQNAgentTrainer::new_on_device(0.9, 1e-2, dev)
+/// });
+/// for _ in 0..5 {
+///     ensemble.train(
+///         || Walker(Position(0)),
+///         || FixedIterations::new(20),
+///         &EpsilonGreedy::new(0.2),
+///     );
+/// }
+///
+/// let values = ensemble.action_values(&Position(1));
+/// assert_eq!(values.len(), 2);
+/// assert!(values.values().all(|v| v.mean.is_finite() && v.variance >= 0.0));
+/// assert!(ensemble.best_action(&Position(1)).is_some());
+/// ```
+pub struct DQNEnsemble<
+    S,
+    const STATE_SIZE: usize,
+    const ACTION_SIZE: usize,
+    const INNER_SIZE: usize,
+    D = AutoDevice,
+    N = QNetwork<STATE_SIZE, ACTION_SIZE, INNER_SIZE>,
+> where
+    S: State + Into<[f32; STATE_SIZE]>,
+    S::A: DiscreteActionSpace<ACTION_SIZE>,
+    D: Device<f32>,
+    N: BuildOnDevice<D, f32>,
+{
+    members: Vec<DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N>>,
+}
+
+impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize, N>
+    DQNEnsemble<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, AutoDevice, N>
+where
+    S: State + Into<[f32; STATE_SIZE]>,
+    S::A: DiscreteActionSpace<ACTION_SIZE>,
+    N: BuildOnDevice<AutoDevice, f32>,
+    N::Built: QNetworkModel<STATE_SIZE, ACTION_SIZE, AutoDevice>,
+{
+    /// Creates an ensemble of `members` trainers, each made by `make_member` on the default
+    /// device, seeded with `seed`, `seed + 1`, and so on, so that their networks start from
+    /// different weights.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `members` is `0`.
+    pub fn new<F>(members: usize, seed: u64, mut make_member: F) -> Self
+    where
+        F: FnMut(
+            AutoDevice,
+        ) -> DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, AutoDevice, N>,
+    {
+        Self::from_members(
+            (0..members as u64)
+                .map(|i| make_member(AutoDevice::seed_from_u64(seed.wrapping_add(i))))
+                .collect(),
+        )
+    }
+}
+
+impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize, D, N>
+    DQNEnsemble<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N>
+where
+    S: State + Into<[f32; STATE_SIZE]>,
+    S::A: DiscreteActionSpace<ACTION_SIZE>,
+    D: Device<f32> + DeviceBuildExt,
+    D: TensorToArray<Rank1<ACTION_SIZE>, f32, Array = [f32; ACTION_SIZE]>,
+    N: BuildOnDevice<D, f32>,
+    N::Built: QNetworkModel<STATE_SIZE, ACTION_SIZE, D>,
+{
+    /// Creates an ensemble of the given trainers, which should start from different weights,
+    /// such as by being built on devices with different seeds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `members` is empty.
+    pub fn from_members(
+        members: Vec<DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N>>,
+    ) -> Self {
+        assert!(!members.is_empty(), "an ensemble needs at least one member");
+        DQNEnsemble { members }
+    }
+
+    /// Returns the trainers of the ensemble.
+    pub fn members(&self) -> &[DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N>] {
+        &self.members
+    }
+
+    /// Returns the trainers of the ensemble, to configure or train them one by one.
+    pub fn members_mut(
+        &mut self,
+    ) -> &mut [DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N>] {
+        &mut self.members
+    }
+
+    /// Trains every member of the ensemble on an episode of its own, with a fresh agent from
+    /// `make_agent` and a fresh termination strategy from `make_termination`, as in
+    /// [DQNAgentTrainer::train()].
+    pub fn train<G, T>(
+        &mut self,
+        mut make_agent: impl FnMut() -> G,
+        mut make_termination: impl FnMut() -> T,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) where
+        G: Agent<S>,
+        T: TerminationStrategy<S>,
+    {
+        for member in &mut self.members {
+            member.train(
+                &mut make_agent(),
+                &mut make_termination(),
+                exploration_strategy,
+            );
+        }
+    }
+
+    /// Returns the value of every action in `state.actions()`, as the mean and variance of the
+    /// values the members estimate for it.
+    pub fn action_values(&self, state: &S) -> HashMap<S::A, EnsembleValue> {
+        let values: Vec<[f32; ACTION_SIZE]> = self
+            .members
+            .iter()
+            .map(|member| member.expected_value(state))
+            .collect();
+        let n = values.len() as f64;
+        state
+            .actions()
+            .into_iter()
+            .filter_map(|action| {
+                let index = checked_index(&action)?;
+                let mean = values.iter().map(|v| v[index] as f64).sum::<f64>() / n;
+                let variance = values
+                    .iter()
+                    .map(|v| (v[index] as f64 - mean).powi(2))
+                    .sum::<f64>()
+                    / n;
+                Some((action, EnsembleValue { mean, variance }))
+            })
+            .collect()
+    }
+
+    /// Returns the action of `state.actions()` with the highest mean value, or `None` if
+    /// `state` has no actions.
+    pub fn best_action(&self, state: &S) -> Option<S::A> {
+        self.action_values(state)
+            .into_iter()
+            .max_by(|(_, v1), (_, v2)| v1.mean.total_cmp(&v2.mean))
+            .map(|(action, _)| action)
+    }
+
+    /// Saves the networks of all members to the directory at `path`, which is created if it
+    /// doesn't exist, as the files `0.safetensors`, `1.safetensors`, and so on, in the format
+    /// of [DQNAgentTrainer::save()].
+    #[cfg(feature = "save")]
+    pub fn save(&self, path: &str) -> Result<(), RurelError>
+    where
+        N: Activations,
+    {
+        std::fs::create_dir_all(path)?;
+        for (i, member) in self.members.iter().enumerate() {
+            member.save(&member_path(path, i))?;
+        }
+        Ok(())
+    }
+
+    /// Loads the networks of all members saved with [save()](DQNEnsemble::save) from the
+    /// directory at `path`.
+    ///
+    /// Fails like [DQNAgentTrainer::load()], and with [RurelError::InvalidFormat] if the
+    /// directory holds the networks of an ensemble with another number of members.
+    #[cfg(feature = "save")]
+    pub fn load(&mut self, path: &str) -> Result<(), RurelError>
+    where
+        N: Activations,
+    {
+        let members = self.members.len();
+        let saved = (0..)
+            .take_while(|&i| std::path::Path::new(&member_path(path, i)).exists())
+            .count();
+        if saved != members {
+            return Err(RurelError::InvalidFormat(format!(
+                "expected an ensemble of {} members, found one of {}",
+                members, saved
+            )));
+        }
+        for (i, member) in self.members.iter_mut().enumerate() {
+            member.load(&member_path(path, i))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path of the file of member `i` of an ensemble saved to the directory at `path`.
+#[cfg(feature = "save")]
+fn member_path(path: &str, i: usize) -> String {
+    std::path::Path::new(path)
+        .join(format!("{}.safetensors", i))
+        .to_string_lossy()
+        .into_owned()
+}
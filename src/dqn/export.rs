@@ -0,0 +1,289 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for saving the Q-network as a safetensors file which describes its architecture.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use ::safetensors::tensor::TensorView;
+use ::safetensors::{Dtype, SafeTensors};
+use dfdx::nn::tensor_collection::{
+    RecursiveWalker, TensorCollection, TensorOptions, TensorVisitor, ViewTensorName, ViewTensorRef,
+};
+use dfdx::prelude::*;
+
+use crate::RurelError;
+
+/// The version of the format of saved models, stored in their metadata as `format_version`.
+/// Models saved before it was stored are of version `0`, whose weights are laid out the same.
+const FORMAT_VERSION: u32 = 1;
+
+/// An architecture of the Q-network which names its activation functions, so that saved models
+/// record them in their metadata, and loading a model into a network with other activation
+/// functions fails. The names are fixed by this crate, so they don't depend on the compiler or
+/// on the paths of the types.
+///
+/// This is implemented for the activation functions and the common layers of dfdx, and for
+/// tuples of up to six of them, such as [QNetwork](super::QNetwork). A network of your own types
+/// has to implement it to be saved:
+///
+/// ```
+/// use dfdx::prelude::*;
+/// use rurel::dqn::Activations;
+///
+/// // A layer of your own, which is followed by a ReLU
+/// struct Block<const I: usize, const O: usize>;
+///
+/// impl<const I: usize, const O: usize> Activations for Block<I, O> {
+///     fn activations(names: &mut Vec<&'static str>) {
+///         names.push("ReLU");
+///     }
+/// }
+///
+/// let mut names = Vec::new();
+/// <((Linear<1, 8>, Tanh), Block<8, 8>, Linear<8, 2>)>::activations(&mut names);
+/// assert_eq!(names, ["Tanh", "ReLU"]);
+/// ```
+pub trait Activations {
+    /// Appends the names of the activation functions of this architecture to `names`, in the
+    /// order they are applied. Layers without an activation function add nothing.
+    fn activations(_names: &mut Vec<&'static str>) {}
+}
+
+macro_rules! impl_activation {
+    ($($name:ident),*) => {
+        $(
+            impl Activations for $name {
+                fn activations(names: &mut Vec<&'static str>) {
+                    names.push(stringify!($name));
+                }
+            }
+        )*
+    };
+}
+
+impl_activation!(
+    ReLU,
+    FastGeLU,
+    AccurateGeLU,
+    Sigmoid,
+    Tanh,
+    Sin,
+    Cos,
+    Ln,
+    Exp,
+    Square,
+    Sqrt,
+    Abs,
+    Softmax,
+    LogSoftmax
+);
+
+#[allow(deprecated)]
+impl Activations for GeLU {
+    fn activations(names: &mut Vec<&'static str>) {
+        names.push("GeLU");
+    }
+}
+
+impl<E: dfdx::dtypes::Dtype> Activations for LeakyReLU<E> {
+    fn activations(names: &mut Vec<&'static str>) {
+        names.push("LeakyReLU");
+    }
+}
+
+impl<const I: usize, const O: usize> Activations for builders::Linear<I, O> {}
+impl<const I: usize, const O: usize> Activations for builders::UnbiasedLinear<I, O> {}
+impl<const M: usize> Activations for builders::LayerNorm1D<M> {}
+impl<const M: usize> Activations for builders::BatchNorm1D<M> {}
+impl<const N: usize> Activations for DropoutOneIn<N> {}
+impl Activations for Dropout {}
+
+impl<F: Activations> Activations for Residual<F> {
+    fn activations(names: &mut Vec<&'static str>) {
+        F::activations(names);
+    }
+}
+
+impl<F: Activations, R: Activations> Activations for GeneralizedResidual<F, R> {
+    fn activations(names: &mut Vec<&'static str>) {
+        F::activations(names);
+        R::activations(names);
+    }
+}
+
+impl<T: Activations, const N: usize> Activations for Repeated<T, N> {
+    fn activations(names: &mut Vec<&'static str>) {
+        for _ in 0..N {
+            T::activations(names);
+        }
+    }
+}
+
+macro_rules! impl_activations_for_tuple {
+    ($($layer:ident),*) => {
+        impl<$($layer: Activations),*> Activations for ($($layer,)*) {
+            fn activations(names: &mut Vec<&'static str>) {
+                $($layer::activations(names);)*
+            }
+        }
+    };
+}
+
+impl_activations_for_tuple!(L1);
+impl_activations_for_tuple!(L1, L2);
+impl_activations_for_tuple!(L1, L2, L3);
+impl_activations_for_tuple!(L1, L2, L3, L4);
+impl_activations_for_tuple!(L1, L2, L3, L4, L5);
+impl_activations_for_tuple!(L1, L2, L3, L4, L5, L6);
+
+/// A parameter of the network, by name, with its shape and its values as little-endian bytes.
+struct Weight {
+    name: String,
+    shape: Vec<usize>,
+    data: Vec<u8>,
+}
+
+/// Collects the weights of all tensors it visits, in the order of the network.
+struct Weights(Vec<Weight>);
+
+impl<D: Device<f32>> TensorVisitor<f32, D> for Weights {
+    type Viewer = (ViewTensorRef, ViewTensorName);
+    type Err = D::Err;
+    type E2 = f32;
+    type D2 = D;
+
+    fn visit<S: Shape>(
+        &mut self,
+        _: TensorOptions<S, f32, D>,
+        (t, name): (&Tensor<S, f32, D>, String),
+    ) -> Result<Option<Tensor<S, f32, D>>, Self::Err> {
+        self.0.push(Weight {
+            name,
+            shape: t.shape().concrete().into(),
+            data: t.as_vec().iter().flat_map(|v| v.to_le_bytes()).collect(),
+        });
+        Ok(None)
+    }
+}
+
+/// Saves the weights of `model` to the safetensors file at `path`, together with the metadata
+/// `format_version`, `state_size`, `action_size`, `hidden_sizes`, `architecture` and
+/// `activations`, with the names of the activation functions of the network, and the `extra`
+/// metadata. The file is written to a temporary file first, which
+/// then replaces the file at `path`, so an interrupted save doesn't corrupt the previous one.
+pub(crate) fn save<M, D, const STATE_SIZE: usize, const ACTION_SIZE: usize>(
+    model: &M,
+    architecture: &str,
+    activations: &[&str],
+    extra: HashMap<String, String>,
+    path: &str,
+) -> Result<(), RurelError>
+where
+    M: TensorCollection<f32, D>,
+    D: Device<f32>,
+{
+    let mut weights = Weights(Vec::new());
+    M::iter_tensors(&mut RecursiveWalker {
+        m: (model, String::new()),
+        f: &mut weights,
+    })
+    .map_err(|e| RurelError::Device(format!("unable to read the weights of the network: {}", e)))?;
+
+    // the outputs of every linear layer but the last
+    let mut layers: Vec<String> = weights
+        .0
+        .iter()
+        .filter(|w| w.name.ends_with("weight") && w.shape.len() == 2)
+        .map(|w| w.shape[0].to_string())
+        .collect();
+    layers.pop();
+    let mut metadata = HashMap::from([
+        ("format_version".to_string(), FORMAT_VERSION.to_string()),
+        ("state_size".to_string(), STATE_SIZE.to_string()),
+        ("action_size".to_string(), ACTION_SIZE.to_string()),
+        (
+            "hidden_sizes".to_string(),
+            format!("[{}]", layers.join(",")),
+        ),
+        ("architecture".to_string(), architecture.to_string()),
+        (
+            "activations".to_string(),
+            format!("[{}]", activations.join(",")),
+        ),
+    ]);
+    metadata.extend(extra);
+
+    let views = weights
+        .0
+        .iter()
+        .map(|w| {
+            let view = TensorView::new(Dtype::F32, w.shape.clone(), &w.data)?;
+            Ok((w.name.clone(), view))
+        })
+        .collect::<Result<Vec<_>, ::safetensors::SafeTensorError>>()?;
+    let temporary = format!("{}.tmp", path);
+    ::safetensors::serialize_to_file(views, &Some(metadata), Path::new(&temporary))?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Checks that the metadata of the safetensors file at `path`, if it has any, is of a supported
+/// format version, and describes a network with `STATE_SIZE` inputs and `ACTION_SIZE` outputs,
+/// with the activation functions `activations`.
+pub(crate) fn check<const STATE_SIZE: usize, const ACTION_SIZE: usize>(
+    path: &str,
+    activations: &[&str],
+) -> Result<(), RurelError> {
+    let metadata = match metadata(path)? {
+        Some(metadata) => metadata,
+        None => return Ok(()),
+    };
+    if let Some(version) = metadata.get("format_version") {
+        let found: u32 = version.parse().map_err(|_| {
+            RurelError::InvalidFormat(format!("invalid format version {:?}", version))
+        })?;
+        if found > FORMAT_VERSION {
+            return Err(RurelError::UnsupportedVersion {
+                found,
+                supported: FORMAT_VERSION,
+            });
+        }
+    }
+    for (key, expected) in [("state_size", STATE_SIZE), ("action_size", ACTION_SIZE)] {
+        if let Some(found) = metadata.get(key) {
+            if *found != expected.to_string() {
+                return Err(RurelError::InvalidFormat(format!(
+                    "expected a network with a {} of {}, found one with {}",
+                    key, expected, found
+                )));
+            }
+        }
+    }
+    if let Some(found) = metadata.get("activations") {
+        let expected = format!("[{}]", activations.join(","));
+        if *found != expected {
+            return Err(RurelError::InvalidFormat(format!(
+                "expected a network with the activations {}, found one with {}",
+                expected, found
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the names of the activation functions of the architecture `N`, in order.
+pub(crate) fn activation_names<N: Activations>() -> Vec<&'static str> {
+    let mut names = Vec::new();
+    N::activations(&mut names);
+    names
+}
+
+/// Returns the metadata of the safetensors file at `path`, if it has any.
+pub(crate) fn metadata(path: &str) -> Result<Option<HashMap<String, String>>, RurelError> {
+    let buffer = std::fs::read(path)?;
+    let (_, header) = SafeTensors::read_metadata(&buffer)?;
+    Ok(header.metadata().clone())
+}
@@ -0,0 +1,209 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for states made of the last few states of an agent, for environments in which a single
+//! state doesn't tell everything, such as the direction a ball moves in.
+
+use std::collections::VecDeque;
+
+use super::encoder::{encode_state, StateEncoder};
+use crate::mdp::wrap::{forward_agent, forward_state};
+use crate::mdp::{Agent, State};
+
+/// A state which holds the last `K` states of an agent, the current one last, so that a
+/// Q-network learns from a window of them rather than from the current state alone. Train with
+/// a [HistoryAgent], which wraps the agent and moves the window on with every action. All
+/// methods of [State] are forwarded to the current state.
+///
+/// A window is encoded into `K` times [LEN](StateEncoder::LEN) values of the encoding of the
+/// states: the encodings of its states, the oldest first. At the start of an episode, before `K`
+/// states were seen, the encodings of the missing states are zeros, in front of those of the
+/// states seen so far. The window converts into the array of values a
+/// [DQNAgentTrainer](super::DQNAgentTrainer) takes, whose `STATE_SIZE` is then
+/// `K * S::LEN`, and [new_encoded()](super::DQNAgentTrainer::new_encoded) checks it.
+///
+/// # Example
+///
+/// ```
+/// use rurel::dqn::{DQNAgentTrainer, HistoryAgent, HistoryWrapper, StateEncoder};
+/// use rurel::mdp::{Agent, State};
+///
+/// // Where the walker is, but not which way it came from
+/// // A `Walker` moves by `Move(-1)` or `Move(1)` along a line, and is rewarded with its position,
+/// // which `Position` implements `StateEncoder` for as `[x]`
+/// # #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = Move;
+/// #     fn reward(&self) -> f64 {
+/// #         self.0 as f64
+/// #     }
+/// #     fn actions(&self) -> Vec<Move> {
+/// #         vec![Move(-1), Move(1)]
+/// #     }
+/// # }
+/// #
+/// # impl StateEncoder for Position {
+/// #     const LEN: usize = 1;
+/// #     fn encode(&self) -> Vec<f32> {
+/// #         vec![self.0 as f32]
+/// #     }
+/// # }
+/// #
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Move(i32);
+/// #
+/// # impl From<Move> for [f32; 2] {
+/// #     fn from(m: Move) -> Self {
+/// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+/// #     }
+/// # }
+/// # impl From<[f32; 2]> for Move {
+/// #     fn from(v: [f32; 2]) -> Self {
+/// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, m: &Move) {
+/// #         self.0 = Position(self.0 .0 + m.0);
+/// #     }
+/// # }
+///
+/// // The last three positions, the oldest first, with zeros before any were seen
+/// let mut agent = HistoryAgent::<_, _, 3>::new(Walker(Position(5)));
+/// let encoded = |agent: &HistoryAgent<Walker, Position, 3>| {
+///     <[f32; 3]>::from(agent.current_state().clone())
+/// };
+/// assert_eq!(encoded(&agent), [0.0, 0.0, 5.0]);
+/// agent.take_action(&Move(1));
+/// assert_eq!(encoded(&agent), [0.0, 5.0, 6.0]);
+/// agent.take_action(&Move(1));
+/// agent.take_action(&Move(-1));
+/// assert_eq!(encoded(&agent), [6.0, 7.0, 6.0]);
+/// assert_eq!(agent.current_state().current(), &Position(6));
+///
+/// // The Q-network takes the window of three positions
+/// let trainer = DQNAgentTrainer::<HistoryWrapper<Position, 3>, 3, 2, 16>::new_encoded(0.9, 1e-3)
+///     .unwrap();
+/// assert!(trainer.expected_value(agent.current_state()).iter().all(|v| v.is_finite()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HistoryWrapper<S, const K: usize> {
+    /// The states of the window, the oldest first, of which there are at most `K`.
+    frames: VecDeque<S>,
+}
+
+impl<S, const K: usize> HistoryWrapper<S, K> {
+    /// Creates the window at the start of an episode, which only holds its first state `state`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `K` is `0`.
+    pub fn new(state: S) -> HistoryWrapper<S, K> {
+        assert!(K > 0, "the window must hold at least one state");
+        let mut frames = VecDeque::with_capacity(K);
+        frames.push_back(state);
+        HistoryWrapper { frames }
+    }
+
+    /// Moves the window on to `state`, the state the agent arrived at, dropping the oldest state
+    /// if the window is full.
+    pub fn push(&mut self, state: S) {
+        if self.frames.len() == K {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(state);
+    }
+
+    /// Returns the current state, which is the last of the window.
+    pub fn current(&self) -> &S {
+        self.frames.back().expect("the window is never empty")
+    }
+
+    /// Returns the states of the window, the oldest first, of which there are fewer than `K` at
+    /// the start of an episode.
+    pub fn frames(&self) -> impl Iterator<Item = &S> {
+        self.frames.iter()
+    }
+}
+
+forward_state!(
+    impl[S: State, const K: usize] State for HistoryWrapper<S, K>,
+    inner: S = |window| window.current(),
+);
+
+impl<S: StateEncoder, const K: usize> StateEncoder for HistoryWrapper<S, K> {
+    const LEN: usize = K * S::LEN;
+
+    fn encode(&self) -> Vec<f32> {
+        let mut values = vec![0.0; (K - self.frames.len()) * S::LEN];
+        for state in &self.frames {
+            values.extend(state.encode());
+        }
+        values
+    }
+}
+
+/// # Panics
+///
+/// Panics if `N` isn't `K` times [StateEncoder::LEN], which
+/// [new_encoded()](super::DQNAgentTrainer::new_encoded) checks when the trainer is created.
+impl<S: StateEncoder, const K: usize, const N: usize> From<HistoryWrapper<S, K>> for [f32; N] {
+    fn from(window: HistoryWrapper<S, K>) -> Self {
+        encode_state(&window).expect("the window must be encoded into K times the state length")
+    }
+}
+
+/// An agent which wraps another agent, and is always in the [HistoryWrapper] of the last `K`
+/// states of the agent it wraps. See [HistoryWrapper] for an example.
+pub struct HistoryAgent<G, S, const K: usize> {
+    agent: G,
+    window: HistoryWrapper<S, K>,
+}
+
+impl<G, S, const K: usize> HistoryAgent<G, S, K>
+where
+    G: Agent<S>,
+    S: State,
+{
+    /// Wraps `agent`, starting the window with its current state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `K` is `0`.
+    pub fn new(agent: G) -> HistoryAgent<G, S, K> {
+        let window = HistoryWrapper::new(agent.current_state().clone());
+        HistoryAgent { agent, window }
+    }
+
+    /// Returns the wrapped agent.
+    pub fn inner(&self) -> &G {
+        &self.agent
+    }
+
+    /// Returns the wrapped agent, consuming this agent.
+    pub fn into_inner(self) -> G {
+        self.agent
+    }
+
+    /// Moves the window on to the state the wrapped agent arrived at.
+    fn update_window(&mut self) {
+        self.window.push(self.agent.current_state().clone());
+    }
+}
+
+forward_agent!(
+    impl[G: Agent<S>, S: State, const K: usize]
+        Agent<HistoryWrapper<S, K>> for HistoryAgent<G, S, K>,
+    agent: agent,
+    state: window,
+    update: update_window,
+);
@@ -0,0 +1,3091 @@
+// source: https://raw.githubusercontent.com/coreylowman/dfdx/main/examples/rl-dqn.rs
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use dfdx::optim::Optimizer;
+use dfdx::prelude::*;
+use rand::seq::SliceRandom;
+
+pub use self::action::DiscreteActionSpace;
+pub use self::dueling::{DuelingHead, DuelingQNetwork};
+pub use self::encoder::{encode_state, StateEncoder};
+pub use self::ensemble::{DQNEnsemble, EnsembleValue};
+#[cfg(feature = "save")]
+pub use self::export::Activations;
+pub use self::history::{HistoryAgent, HistoryWrapper};
+use self::normalize::Normalizer;
+pub use self::normalize::{RewardNormalization, RunningStats};
+pub use self::optimizer::OptimizerConfig;
+use self::optimizer::{gradient_norm, AnyOptimizer};
+use self::replay::{Experience, PrioritizedReplayBuffer};
+use crate::cache::ActionCache;
+use crate::early_stop::{Check, Plateau};
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::learn::{check_alpha, check_gamma};
+use crate::strategy::terminate::TerminationStrategy;
+use crate::{
+    plan, rollout, AgentTrainer, CacheStats, EarlyStopping, EarlyStoppingReport, EvalStats,
+    Lookahead, QValue, RolloutResult, RurelError, Trainer, ValueStore,
+};
+
+mod action;
+pub mod dueling;
+mod encoder;
+mod ensemble;
+#[cfg(feature = "save")]
+mod export;
+mod history;
+mod normalize;
+mod optimizer;
+pub mod replay;
+
+const BATCH: usize = 64;
+/// The number of training steps taken on every batch given to
+/// [train_dqn()](DQNAgentTrainer::train_dqn).
+const TRAIN_STEPS: usize = 20;
+/// The default number of transitions kept in the replay buffer.
+const REPLAY_CAPACITY: usize = 10_000;
+
+/// The default architecture of the Q-network: two hidden layers of `INNER_SIZE` neurons, each
+/// followed by the activation function `Activation`, which is [ReLU] by default. Any activation
+/// function of dfdx can be used instead, such as [Tanh], [LeakyReLU] or [FastGeLU] for smoother
+/// value functions:
+///
+/// ```
+/// use dfdx::prelude::*;
+/// use rurel::dqn::{DQNAgentTrainer, QNetwork};
+/// use rurel::mdp::State;
+///
+/// // Unrewarded positions on a line: `Position(x)` is encoded as `[x]`, and `Move(-1)` and
+/// // `Move(1)` one-hot
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Move(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = Move;
+/// #     fn reward(&self) -> f64 {
+/// #         0.0
+/// #     }
+/// #     fn actions(&self) -> Vec<Move> {
+/// #         vec![Move(-1), Move(1)]
+/// #     }
+/// # }
+/// #
+/// # impl From<Position> for [f32; 1] {
+/// #     fn from(p: Position) -> Self {
+/// #         [p.0 as f32]
+/// #     }
+/// # }
+/// # impl From<Move> for [f32; 2] {
+/// #     fn from(m: Move) -> Self {
+/// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+/// #     }
+/// # }
+/// # impl From<[f32; 2]> for Move {
+/// #     fn from(v: [f32; 2]) -> Self {
+/// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+/// #     }
+/// # }
+///
+/// let trainer =
+///     DQNAgentTrainer::<Position, 1, 2, 32, AutoDevice, QNetwork<1, 2, 32, Tanh>>::new(0.9, 1e-3);
+/// assert!(trainer.best_action(&Position(0)).is_some());
+/// ```
+///
+/// Saved models record their activation functions, see
+/// [DQNAgentTrainer::save()](DQNAgentTrainer::save).
+pub type QNetwork<
+    const STATE_SIZE: usize,
+    const ACTION_SIZE: usize,
+    const INNER_SIZE: usize,
+    Activation = ReLU,
+> = (
+    (Linear<STATE_SIZE, INNER_SIZE>, Activation),
+    (Linear<INNER_SIZE, INNER_SIZE>, Activation),
+    Linear<INNER_SIZE, ACTION_SIZE>,
+);
+
+/// How the loss of a training step is computed from the TD errors of its transitions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Loss {
+    /// The squared TD error.
+    Mse,
+    /// The Huber, or smooth L1, loss with the given delta: half the squared TD error while its
+    /// magnitude is at most delta, and delta times its magnitude, minus half delta, beyond that.
+    /// Large TD errors therefore affect training much less than with [Loss::Mse].
+    Huber(f32),
+}
+
+/// A batch of any number of rows of `SIZE` values.
+type Batch<const SIZE: usize, D, T = NoneTape> = Tensor<(usize, Const<SIZE>), f32, D, T>;
+
+/// The Q-network with architecture `N`, built on device `D`.
+type QNetworkDevice<N, D> = <N as BuildOnDevice<D, f32>>::Built;
+
+/// A built Q-network, which maps states of `STATE_SIZE` values to the values of `ACTION_SIZE`
+/// actions, both for single states and for training batches.
+///
+/// This is implemented for every network built from a dfdx architecture with these input and
+/// output sizes, such as [QNetwork].
+pub trait QNetworkModel<const STATE_SIZE: usize, const ACTION_SIZE: usize, D: Device<f32>>:
+    Module<Tensor<Rank1<STATE_SIZE>, f32, D>, Output = Tensor<Rank1<ACTION_SIZE>, f32, D>>
+    + Module<Batch<STATE_SIZE, D>, Output = Batch<ACTION_SIZE, D>>
+    + Module<
+        Batch<STATE_SIZE, D, OwnedTape<f32, D>>,
+        Output = Batch<ACTION_SIZE, D, OwnedTape<f32, D>>,
+    > + TensorCollection<f32, D>
+    + Clone
+{
+}
+
+impl<const STATE_SIZE: usize, const ACTION_SIZE: usize, D: Device<f32>, T>
+    QNetworkModel<STATE_SIZE, ACTION_SIZE, D> for T
+where
+    T: Module<Tensor<Rank1<STATE_SIZE>, f32, D>, Output = Tensor<Rank1<ACTION_SIZE>, f32, D>>
+        + Module<Batch<STATE_SIZE, D>, Output = Batch<ACTION_SIZE, D>>
+        + Module<
+            Batch<STATE_SIZE, D, OwnedTape<f32, D>>,
+            Output = Batch<ACTION_SIZE, D, OwnedTape<f32, D>>,
+        > + TensorCollection<f32, D>
+        + Clone,
+{
+}
+
+/// How the target network of a [DQNAgentTrainer] follows the Q-network that is being trained.
+/// The target network computes the bootstrap targets, so letting it lag behind keeps the targets
+/// from moving with every update, which makes training more stable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetUpdate {
+    /// Copies the Q-network into the target network every `n` training steps. `Hard(1)` amounts
+    /// to not using a target network.
+    Hard(usize),
+    /// Moves the target network towards the Q-network after every training step, by setting it to
+    /// `tau * q_network + (1 - tau) * target_network` (Polyak averaging).
+    Soft(f64),
+}
+
+/// An `DQNAgentTrainer` can be trained for using a certain [Agent](mdp/trait.Agent.html). After
+/// training, the `DQNAgentTrainer` contains learned knowledge about the process, and can be queried
+/// for this. For example, you can ask the `DQNAgentTrainer` the expected values of all possible
+/// actions in a given state.
+///
+/// Every action is identified by the index of the output of the Q-network that estimates its
+/// value; see [DiscreteActionSpace].
+///
+/// The network is stored on, and trained with, the dfdx device `D`. By default this is
+/// [AutoDevice], which is the CPU, or the GPU if the `cuda` feature is enabled. Use
+/// [new_on_device()](DQNAgentTrainer::new_on_device) to pick a device explicitly.
+///
+/// The architecture of the Q-network is `N`, which defaults to [QNetwork]: two hidden layers of
+/// `INNER_SIZE` neurons. Any dfdx architecture with `STATE_SIZE` inputs and `ACTION_SIZE` outputs
+/// can be used instead, such as the dueling [DuelingQNetwork], or your own, in which case
+/// `INNER_SIZE` is ignored. For example, a network with a hidden layer of 128 neurons followed by
+/// one of 32 neurons:
+///
+/// ```
+/// use dfdx::prelude::*;
+/// use rurel::dqn::DQNAgentTrainer;
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Position(i32);
+///
+/// impl State for Position {
+///     type A = Step;
+///     fn reward(&self) -> f64 {
+///         -(self.0 as f64).abs()
+///     }
+///     fn actions(&self) -> Vec<Step> {
+///         vec![Step(-1), Step(1)]
+///     }
+/// }
+///
+/// impl From<Position> for [f32; 1] {
+///     fn from(p: Position) -> Self {
+///         [p.0 as f32]
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Step(i32);
+///
+/// impl From<Step> for [f32; 2] {
+///     fn from(s: Step) -> Self {
+///         if s.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+///     }
+/// }
+///
+/// impl From<[f32; 2]> for Step {
+///     fn from(v: [f32; 2]) -> Self {
+///         if v[0] > v[1] { Step(-1) } else { Step(1) }
+///     }
+/// }
+///
+/// struct Walker(Position);
+///
+/// impl Agent<Position> for Walker {
+///     fn current_state(&self) -> &Position {
+///         &self.0
+///     }
+///     fn take_action(&mut self, step: &Step) {
+///         self.0 = Position((self.0 .0 + step.0).clamp(-5, 5));
+///     }
+/// }
+///
+/// type Network = (
+///     (Linear<1, 128>, ReLU),
+///     (Linear<128, 32>, ReLU),
+///     Linear<32, 2>,
+/// );
+///
+/// let mut trainer = DQNAgentTrainer::<Position, 1, 2, 0, AutoDevice, Network>::new(0.9, 1e-3);
+/// trainer.train(
+///     &mut Walker(Position(0)),
+///     &mut FixedIterations::new(200),
+///     &RandomExploration,
+/// );
+/// assert!(trainer.best_action(&Position(3)).is_some());
+/// ```
+///
+/// The code is partially taken from https://github.com/coreylowman/dfdx/blob/main/examples/rl-dqn.rs.
+///
+pub struct DQNAgentTrainer<
+    S,
+    const STATE_SIZE: usize,
+    const ACTION_SIZE: usize,
+    const INNER_SIZE: usize,
+    D = AutoDevice,
+    N = QNetwork<STATE_SIZE, ACTION_SIZE, INNER_SIZE>,
+> where
+    S: State + Into<[f32; STATE_SIZE]>,
+    S::A: DiscreteActionSpace<ACTION_SIZE>,
+    D: Device<f32>,
+    N: BuildOnDevice<D, f32>,
+{
+    /// The [discount factor](https://en.wikipedia.org/wiki/Q-learning#Discount_factor) for future rewards.
+    gamma: f32,
+    /// The Q-network that is being trained.
+    q_network: QNetworkDevice<N, D>,
+    /// The target Q-network that is used to compute the target Q-values.
+    target_q_net: QNetworkDevice<N, D>,
+    /// How the target Q-network follows the Q-network.
+    target_update: TargetUpdate,
+    /// Whether the bootstrap targets are computed as in Double DQN.
+    double_dqn: bool,
+    /// Whether only the actions of `State::actions()` are taken into account.
+    action_masking: bool,
+    /// The loss the Q-network is trained to minimize.
+    loss: Loss,
+    /// The number of training steps taken so far.
+    steps: usize,
+    /// The number of episodes trained so far.
+    episodes: usize,
+    /// The number of episodes between checkpoints, the path they are saved to and the names of
+    /// the activation functions of the network, if they are saved.
+    #[cfg(feature = "save")]
+    checkpoints: Option<(usize, String, Vec<&'static str>)>,
+    /// The error of the last checkpoint, if it couldn't be saved.
+    #[cfg(feature = "save")]
+    checkpoint_error: Option<RurelError>,
+    /// The most recent transitions, which training samples its minibatches from.
+    replay_buffer: PrioritizedReplayBuffer<STATE_SIZE, ACTION_SIZE>,
+    /// The number of transitions in every minibatch sampled from the replay buffer.
+    minibatch_size: usize,
+    /// The number of micro-batches every minibatch is split into, whose gradients are summed
+    /// before the optimizer takes a step.
+    gradient_accumulation: usize,
+    /// The importance-sampling exponent at the start of training.
+    beta: f64,
+    /// The number of training steps over which the importance-sampling exponent is increased
+    /// to `1`.
+    beta_annealing_steps: usize,
+    /// The largest norm the gradients of a training step may have before they are scaled down.
+    max_gradient_norm: Option<f32>,
+    /// The range the TD targets are clamped to.
+    value_clip: Option<(f32, f32)>,
+    /// The penalty subtracted from the reward of every step.
+    step_penalty: f32,
+    /// How the rewards are normalized, with the statistics gathered so far, if they are.
+    reward_normalization: Option<Normalizer>,
+    /// The number of training steps whose gradients were scaled down.
+    clipped_steps: usize,
+    /// The loss of every training step, if it is being recorded.
+    loss_history: Option<Vec<f32>>,
+    /// The actions of recently seen states, if they are cached.
+    action_cache: Option<ActionCache<S>>,
+    /// The optimizer that is used to train the Q-network.
+    optimizer: AnyOptimizer<QNetworkDevice<N, D>, D>,
+    /// The device the networks are stored on.
+    dev: D,
+    /// Preserves the type of the state.
+    phantom: std::marker::PhantomData<S>,
+}
+
+impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize, N>
+    DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, AutoDevice, N>
+where
+    S: State + Into<[f32; STATE_SIZE]>,
+    S::A: DiscreteActionSpace<ACTION_SIZE>,
+    N: BuildOnDevice<AutoDevice, f32>,
+    N::Built: QNetworkModel<STATE_SIZE, ACTION_SIZE, AutoDevice>,
+{
+    /// Creates a new `DQNAgentTrainer` with the given parameters, on the default device.
+    ///
+    /// # Arguments
+    ///
+    /// * `gamma` - The discount factor for future rewards.
+    /// * `learning_rate` - The learning rate for the optimizer.
+    ///
+    /// # Returns
+    ///
+    /// A new `DQNAgentTrainer` with the given parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` isn't between `0` and `1`, or if the learning rate isn't positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::panic;
+    ///
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// // Unrewarded positions on a line, encoded as `[x]`, with the single action `Stay`
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Stay;
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Stay;
+    /// #     fn reward(&self) -> f64 {
+    /// #         0.0
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Stay> {
+    /// #         vec![Stay]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// # impl From<Stay> for [f32; 1] {
+    /// #     fn from(_: Stay) -> Self {
+    /// #         [1.0]
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 1]> for Stay {
+    /// #     fn from(_: [f32; 1]) -> Self {
+    /// #         Stay
+    /// #     }
+    /// # }
+    ///
+    /// let new = |gamma, learning_rate| {
+    ///     panic::catch_unwind(|| DQNAgentTrainer::<Position, 1, 1, 4>::new(gamma, learning_rate))
+    /// };
+    /// assert!(new(0.99, 1e-3).is_ok());
+    /// assert!(new(1.5, 1e-3).is_err());
+    /// assert!(new(-0.1, 1e-3).is_err());
+    /// assert!(new(0.99, 0.0).is_err());
+    /// assert!(new(0.99, -1e-3).is_err());
+    /// ```
+    pub fn new(
+        gamma: f32,
+        learning_rate: f64,
+    ) -> DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, AutoDevice, N> {
+        Self::new_on_device(gamma, learning_rate, AutoDevice::default())
+    }
+
+    /// Creates a new `DQNAgentTrainer` which trains its network with the given optimizer, on the
+    /// default device. [new()](DQNAgentTrainer::new) is the same as using
+    /// [OptimizerConfig::sgd()].
+    ///
+    /// # Arguments
+    ///
+    /// * `gamma` - The discount factor for future rewards.
+    /// * `optimizer` - The optimizer to use, along with its hyperparameters.
+    ///
+    /// # Returns
+    ///
+    /// A new `DQNAgentTrainer` with the given parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` isn't between `0` and `1`, or if the learning rate isn't positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dfdx::prelude::*;
+    /// use rurel::dqn::{DQNAgentTrainer, OptimizerConfig};
+    /// use rurel::mdp::State;
+    ///
+    /// // Unrewarded positions on a line: `Position(x)` is encoded as `[x]`, and `Move(-1)` and
+    /// // `Move(1)` one-hot
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         0.0
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(-1), Move(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    ///
+    /// let adam = OptimizerConfig::Adam(AdamConfig {
+    ///     lr: 1e-3,
+    ///     betas: [0.9, 0.999],
+    ///     eps: 1e-8,
+    ///     weight_decay: None,
+    /// });
+    /// let trainer = DQNAgentTrainer::<Position, 1, 2, 16>::new_with_optimizer(0.9, adam);
+    /// ```
+    pub fn new_with_optimizer(
+        gamma: f32,
+        optimizer: OptimizerConfig,
+    ) -> DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, AutoDevice, N> {
+        Self::new_on_device_with_optimizer(gamma, optimizer, AutoDevice::default())
+    }
+
+    /// Creates a new `DQNAgentTrainer` like [new()](DQNAgentTrainer::new), for states which are
+    /// encoded with a [StateEncoder].
+    ///
+    /// Fails with [RurelError::EncodingLength] if the states are encoded into another number of
+    /// values than `STATE_SIZE`. See [StateEncoder] for an example.
+    pub fn new_encoded(
+        gamma: f32,
+        learning_rate: f64,
+    ) -> Result<DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, AutoDevice, N>, RurelError>
+    where
+        S: StateEncoder,
+    {
+        encoder::check_len::<S, STATE_SIZE>()?;
+        Ok(Self::new(gamma, learning_rate))
+    }
+}
+
+impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize, D, N>
+    DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N>
+where
+    S: State + Into<[f32; STATE_SIZE]>,
+    S::A: DiscreteActionSpace<ACTION_SIZE>,
+    D: Device<f32> + DeviceBuildExt,
+    D: TensorToArray<Rank1<ACTION_SIZE>, f32, Array = [f32; ACTION_SIZE]>,
+    N: BuildOnDevice<D, f32>,
+    N::Built: QNetworkModel<STATE_SIZE, ACTION_SIZE, D>,
+{
+    /// Creates a new `DQNAgentTrainer` with the given parameters, which stores and trains its
+    /// network on the given dfdx device.
+    ///
+    /// # Arguments
+    ///
+    /// * `gamma` - The discount factor for future rewards.
+    /// * `learning_rate` - The learning rate for the optimizer.
+    /// * `dev` - The device to use, such as `Cpu::default()`, or `Cuda::default()` when the
+    ///   `cuda` feature is enabled.
+    ///
+    /// # Returns
+    ///
+    /// A new `DQNAgentTrainer` with the given parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` isn't between `0` and `1`, or if the learning rate isn't positive.
+    pub fn new_on_device(
+        gamma: f32,
+        learning_rate: f64,
+        dev: D,
+    ) -> DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N> {
+        Self::new_on_device_with_optimizer(gamma, OptimizerConfig::sgd(learning_rate), dev)
+    }
+
+    /// Creates a new `DQNAgentTrainer` which trains its network with the given optimizer, and
+    /// stores it on the given dfdx device.
+    ///
+    /// # Arguments
+    ///
+    /// * `gamma` - The discount factor for future rewards.
+    /// * `optimizer` - The optimizer to use, along with its hyperparameters.
+    /// * `dev` - The device to use, such as `Cpu::default()`, or `Cuda::default()` when the
+    ///   `cuda` feature is enabled.
+    ///
+    /// # Returns
+    ///
+    /// A new `DQNAgentTrainer` with the given parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` isn't between `0` and `1`, or if the learning rate isn't positive.
+    pub fn new_on_device_with_optimizer(
+        gamma: f32,
+        optimizer: OptimizerConfig,
+        dev: D,
+    ) -> DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N> {
+        check_gamma(gamma as f64);
+        check_alpha(optimizer.learning_rate());
+
+        // initialize model
+        let q_net = dev.build_module::<N, f32>();
+        let target_q_net = q_net.clone();
+
+        // initialize optimizer
+        let optimizer = AnyOptimizer::new(&q_net, optimizer);
+
+        DQNAgentTrainer {
+            gamma,
+            q_network: q_net,
+            target_q_net,
+            target_update: TargetUpdate::Hard(TRAIN_STEPS),
+            double_dqn: false,
+            action_masking: false,
+            loss: Loss::Huber(1.0),
+            steps: 0,
+            episodes: 0,
+            #[cfg(feature = "save")]
+            checkpoints: None,
+            #[cfg(feature = "save")]
+            checkpoint_error: None,
+            replay_buffer: PrioritizedReplayBuffer::new(REPLAY_CAPACITY, 0.0),
+            minibatch_size: BATCH,
+            gradient_accumulation: 1,
+            beta: 1.0,
+            beta_annealing_steps: 0,
+            max_gradient_norm: None,
+            value_clip: None,
+            step_penalty: 0.0,
+            reward_normalization: None,
+            clipped_steps: 0,
+            loss_history: None,
+            action_cache: None,
+            optimizer,
+            dev,
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Fetches the learned value for the given `Action` in the given `State`, or `None` if no
+    /// value was learned.
+    pub fn expected_value(&self, state: &S) -> [f32; ACTION_SIZE] {
+        self.expected_values(std::slice::from_ref(state))[0]
+    }
+
+    /// Returns `true` for every state, as the Q-network estimates the values of all states, like
+    /// [AgentTrainer::is_known()] does for the states it learned values for. Unlike a table, the
+    /// network doesn't keep track of the states it was trained on: its estimates for the states it
+    /// never saw are whatever it generalizes to from the ones it did, which are only as good as
+    /// the encoding of the states lets them be, and can be arbitrary. To tell how uncertain the
+    /// estimates for a state are, train a [DQNEnsemble] and compare the variance of its values
+    /// across states.
+    pub fn is_known(&self, _state: &S) -> bool {
+        true
+    }
+
+    /// Returns the [expected_value()](DQNAgentTrainer::expected_value) of every state in
+    /// `states`, in the same order. All states are encoded into a single batch, which takes a
+    /// single pass through the network, so this is much faster than evaluating the states one
+    /// by one, such as for the positions after every legal move in a game.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// // Positions on a line, rewarded by how close they are to 0: `Position(x)` is encoded as
+    /// // `[x]`, and the moves `Move(-3)` to `Move(3)` one-hot
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -(self.0 as f64).abs()
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         (-3..=3).map(Move).collect()
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 7] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         let mut v = [0.0; 7];
+    /// #         v[(m.0 + 3) as usize] = 1.0;
+    /// #         v
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 7]> for Move {
+    /// #     fn from(v: [f32; 7]) -> Self {
+    /// #         let best = (0..7).max_by(|&i, &j| v[i].total_cmp(&v[j])).unwrap();
+    /// #         Move(best as i32 - 3)
+    /// #     }
+    /// # }
+    ///
+    /// let trainer = DQNAgentTrainer::<Position, 1, 7, 16>::new(0.9, 1e-3);
+    /// let state = Position(10);
+    /// // The positions after every move, evaluated together
+    /// let next_states: Vec<Position> =
+    ///     state.actions().iter().map(|m| Position(state.0 + m.0)).collect();
+    /// let values = trainer.expected_values(&next_states);
+    /// assert_eq!(values.len(), 7);
+    /// for (next_state, values) in next_states.iter().zip(&values) {
+    ///     let one_by_one = trainer.expected_value(next_state);
+    ///     assert!(values.iter().zip(&one_by_one).all(|(a, b)| (a - b).abs() < 1e-5));
+    /// }
+    /// ```
+    pub fn expected_values(&self, states: &[S]) -> Vec<[f32; ACTION_SIZE]> {
+        let encoded: Vec<[f32; STATE_SIZE]> =
+            states.iter().map(|state| state.clone().into()).collect();
+        self.q_values_batch(&encoded)
+    }
+
+    /// Returns the output of the Q-network for a state that is already encoded into the values
+    /// the network takes: the value of every action, by index. The encoding is normalized as in
+    /// training, and `NaN` outputs are replaced with `0`, but the values aren't decoded into an
+    /// action, so they can be post-processed freely, such as to combine the values of several
+    /// networks, or to build a controller of your own.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// // Positions on a line, rewarded by how close they are to 0: `Position(x)` is encoded as
+    /// // `[x]`, and `Move(-1)` and `Move(1)` one-hot
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -(self.0 as f64).abs()
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(-1), Move(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    ///
+    /// // An ensemble of two networks, which averages their values
+    /// let first = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3);
+    /// let second = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3);
+    /// let ensemble = |encoded: [f32; 1]| -> [f32; 2] {
+    ///     let (a, b) = (first.q_values(encoded), second.q_values(encoded));
+    ///     [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0]
+    /// };
+    /// assert!(ensemble([3.0]).iter().all(|v| v.is_finite()));
+    /// assert_eq!(first.q_values([3.0]), first.expected_value(&Position(3)));
+    /// ```
+    pub fn q_values(&self, encoded: [f32; STATE_SIZE]) -> [f32; ACTION_SIZE] {
+        self.q_values_batch(&[encoded])[0]
+    }
+
+    /// Returns the [q_values()](DQNAgentTrainer::q_values) of every encoded state in `encoded`,
+    /// in the same order, computed in a single pass through the network.
+    pub fn q_values_batch(&self, encoded: &[[f32; STATE_SIZE]]) -> Vec<[f32; ACTION_SIZE]> {
+        if encoded.is_empty() {
+            return Vec::new();
+        }
+        let batch: Batch<STATE_SIZE, D> = self
+            .dev
+            .tensor_from_vec(encoded.concat(), (encoded.len(), Const))
+            .normalize::<Axis<1>>(0.001);
+        self.q_network
+            .forward(batch)
+            .nans_to(0f32)
+            .as_vec()
+            .chunks(ACTION_SIZE)
+            .map(|values| values.try_into().unwrap())
+            .collect()
+    }
+
+    /// Returns the value of every action in `state.actions()`, as estimated by the Q-network.
+    /// The values of all actions are computed in a single pass through the network.
+    ///
+    /// Actions with an [index()](DiscreteActionSpace::index) that isn't below `ACTION_SIZE`
+    /// have no output of the network: debug builds panic on them, and release builds leave them
+    /// out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::panic;
+    ///
+    /// use rurel::dqn::{DQNAgentTrainer, DiscreteActionSpace};
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Position(i32);
+    /// #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+    /// enum Move {
+    ///     Left,
+    ///     Right,
+    ///     // A move that was added without making room for it in the network
+    ///     Jump,
+    /// }
+    ///
+    /// impl DiscreteActionSpace<2> for Move {
+    ///     fn index(&self) -> usize {
+    ///         *self as usize
+    ///     }
+    ///     fn from_index(index: usize) -> Move {
+    ///         [Move::Left, Move::Right][index]
+    ///     }
+    /// }
+    ///
+    /// impl State for Position {
+    ///     type A = Move;
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<Move> {
+    ///         vec![Move::Left, Move::Right, Move::Jump]
+    ///     }
+    /// }
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    ///
+    /// let trainer = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3);
+    /// // Debug builds of rurel panic, and release builds leave the jump out
+    /// let values = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+    ///     trainer.action_values(&Position(0))
+    /// }));
+    /// if let Ok(values) = values {
+    ///     assert_eq!(values.len(), 2);
+    ///     assert!(!values.contains_key(&Move::Jump));
+    /// }
+    /// ```
+    pub fn action_values(&self, state: &S) -> HashMap<S::A, f64> {
+        let values = self.expected_value(state);
+        self.actions(state)
+            .into_iter()
+            .filter_map(|action| {
+                let value = values[checked_index(&action)?] as f64;
+                Some((action, value))
+            })
+            .collect()
+    }
+
+    /// Sets how the target network follows the Q-network during training. By default, the
+    /// Q-network is copied into the target network after every batch of training steps, which is
+    /// `TargetUpdate::Hard(20)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target_update` is `TargetUpdate::Hard(0)`, or `TargetUpdate::Soft(tau)` with a
+    /// `tau` outside of `0..=1`.
+    ///
+    /// # Example
+    ///
+    /// In a state that always loops back to itself, the learned value moves back and forth much
+    /// less on its way to the true value when the bootstrap targets come from a slowly following
+    /// target network:
+    ///
+    /// ```
+    /// use rurel::dqn::{DQNAgentTrainer, TargetUpdate};
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Loop;
+    ///
+    /// impl State for Loop {
+    ///     type A = Stay;
+    ///     fn reward(&self) -> f64 {
+    ///         1.0
+    ///     }
+    ///     fn actions(&self) -> Vec<Stay> {
+    ///         vec![Stay]
+    ///     }
+    /// }
+    ///
+    /// impl From<Loop> for [f32; 2] {
+    ///     fn from(_: Loop) -> Self {
+    ///         [0.0, 1.0]
+    ///     }
+    /// }
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Stay;
+    ///
+    /// impl From<Stay> for [f32; 1] {
+    ///     fn from(_: Stay) -> Self {
+    ///         [1.0]
+    ///     }
+    /// }
+    ///
+    /// impl From<[f32; 1]> for Stay {
+    ///     fn from(_: [f32; 1]) -> Self {
+    ///         Stay
+    ///     }
+    /// }
+    ///
+    /// // How much further the value moved than the distance it covered
+    /// let oscillation = |target_update| {
+    ///     let mut trainer =
+    ///         DQNAgentTrainer::<Loop, 2, 1, 16>::new(0.9, 1e-2).with_target_update(target_update);
+    ///     let mut values = Vec::new();
+    ///     for _ in 0..50 {
+    ///         let (state, action) = ([0.0, 1.0], [1.0]);
+    ///         trainer.train_dqn([state; 64], [action; 64], [state; 64], [1.0; 64], [false; 64]);
+    ///         values.push(trainer.expected_value(&Loop)[0]);
+    ///     }
+    ///     let moved: f32 = values.windows(2).map(|w| (w[1] - w[0]).abs()).sum();
+    ///     moved - (values[49] - values[0]).abs()
+    /// };
+    ///
+    /// let without_target_network = oscillation(TargetUpdate::Hard(1));
+    /// let with_target_network = oscillation(TargetUpdate::Soft(0.01));
+    /// assert!(with_target_network < without_target_network / 2.0);
+    /// ```
+    pub fn with_target_update(mut self, target_update: TargetUpdate) -> Self {
+        match target_update {
+            TargetUpdate::Hard(n) => assert!(n > 0, "the target update interval must be positive"),
+            TargetUpdate::Soft(tau) => {
+                assert!((0.0..=1.0).contains(&tau), "tau must be between 0 and 1")
+            }
+        }
+        self.target_update = target_update;
+        self
+    }
+
+    /// Sets whether the bootstrap targets are computed as in Double DQN, which is off by default.
+    ///
+    /// Normally, the target network both selects and evaluates the best next action, so that
+    /// errors in its estimates make the maximum too high, and the learned values overestimate the
+    /// real ones. With Double DQN, the best next action is selected by the Q-network that is
+    /// being trained, and only evaluated by the target network, which reduces this
+    /// overestimation.
+    ///
+    /// # Example
+    ///
+    /// From the start state, the only action leads to a state with four actions, which all end
+    /// training with a noisy reward of `-0.1` on average. The value of the start state is
+    /// therefore `0.9 * -0.1`, which Double DQN overestimates less:
+    ///
+    /// ```
+    /// use rand::{Rng, SeedableRng};
+    /// use rurel::dqn::{DQNAgentTrainer, TargetUpdate};
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Start;
+    ///
+    /// impl State for Start {
+    ///     type A = Pick;
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<Pick> {
+    ///         vec![Pick(0)]
+    ///     }
+    /// }
+    ///
+    /// impl From<Start> for [f32; 2] {
+    ///     fn from(_: Start) -> Self {
+    ///         [1.0, 0.0]
+    ///     }
+    /// }
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Pick(usize);
+    ///
+    /// impl From<Pick> for [f32; 4] {
+    ///     fn from(pick: Pick) -> Self {
+    ///         let mut action = [0.0; 4];
+    ///         action[pick.0] = 1.0;
+    ///         action
+    ///     }
+    /// }
+    ///
+    /// impl From<[f32; 4]> for Pick {
+    ///     fn from(_: [f32; 4]) -> Self {
+    ///         Pick(0)
+    ///     }
+    /// }
+    ///
+    /// let estimate = |double_dqn| {
+    ///     let mut trainer = DQNAgentTrainer::<Start, 2, 4, 16>::new(0.9, 1e-2)
+    ///         .with_target_update(TargetUpdate::Soft(0.01))
+    ///         .with_double_dqn(double_dqn);
+    ///     let (start, next) = ([1.0, 0.0], [0.0, 1.0]);
+    ///     let mut states = [start; 64];
+    ///     let mut actions = [Pick(0).into(); 64];
+    ///     let mut next_states = [next; 64];
+    ///     let mut rewards = [0.0; 64];
+    ///     let mut dones = [false; 64];
+    ///     for i in 32..64 {
+    ///         states[i] = next;
+    ///         actions[i] = Pick(i % 4).into();
+    ///         next_states[i] = start;
+    ///         dones[i] = true;
+    ///     }
+    ///
+    ///     let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    ///     let mut total = 0.0;
+    ///     for i in 0..40 {
+    ///         for reward in &mut rewards[32..] {
+    ///             *reward = rng.gen_range(-1.2..1.0);
+    ///         }
+    ///         trainer.train_dqn(states, actions, next_states, rewards, dones);
+    ///         if i >= 20 {
+    ///             total += trainer.expected_value(&Start)[0];
+    ///         }
+    ///     }
+    ///     total / 20.0
+    /// };
+    ///
+    /// assert!(estimate(true) < estimate(false));
+    /// ```
+    pub fn with_double_dqn(mut self, double_dqn: bool) -> Self {
+        self.double_dqn = double_dqn;
+        self
+    }
+
+    /// Sets whether the actions that can't be taken in a state are masked out. By default, they
+    /// aren't.
+    ///
+    /// The network has an output for every action, whether or not it can be taken in the state
+    /// at hand. With masking, only the actions of `state.actions()` are taken into account:
+    /// [best_action()](DQNAgentTrainer::best_action) picks the best of them, like
+    /// [best_legal_action()](DQNAgentTrainer::best_legal_action), and the bootstrap target of a
+    /// transition is the value of the best action that can be taken in its next state. A next
+    /// state without any actions is treated like the end of training, so its value is `0`.
+    /// Without masking, an illegal action that the network overestimates inflates the targets of
+    /// every transition leading to its state.
+    ///
+    /// Masking doesn't change the loss: a training step only computes the loss of the actions
+    /// that were taken, which are always legal, so the outputs of masked actions never get a
+    /// gradient of their own. They only change along with the weights they share with the other
+    /// outputs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // A `Walker` moves by `Move(-1)` or `Move(1)`, and is rewarded by how close it is to 5. The
+    /// // walls at 0 and 10 block the way, so there is only one move there. `Position(x)` is
+    /// // encoded as `[x]`
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// # struct Position(i32);
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -(self.0 as f64 - 5.0).abs()
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         // The walls at 0 and 10 block the way
+    /// #         match self.0 {
+    /// #             0 => vec![Move(1)],
+    /// #             10 => vec![Move(-1)],
+    /// #             _ => vec![Move(-1), Move(1)],
+    /// #         }
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, m: &Move) {
+    /// #         self.0 = Position(self.0 .0 + m.0);
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer =
+    ///     DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3).with_action_masking(true);
+    /// for _ in 0..5 {
+    ///     let mut agent = Walker(Position(0));
+    ///     trainer.train(&mut agent, &mut FixedIterations::new(50), &RandomExploration);
+    /// }
+    /// // Whatever the network learned, the walls can't be walked through
+    /// assert_eq!(trainer.best_action(&Position(0)), Some(Move(1)));
+    /// assert_eq!(trainer.best_action(&Position(10)), Some(Move(-1)));
+    /// ```
+    pub fn with_action_masking(mut self, action_masking: bool) -> Self {
+        self.action_masking = action_masking;
+        self
+    }
+
+    /// Sets the loss the Q-network is trained to minimize. By default, this is
+    /// [Loss::Huber] with a delta of `1`, which is the loss the trainer always minimized before
+    /// the loss could be chosen, so that existing training doesn't change. Set [Loss::Mse] for the
+    /// plain squared TD error.
+    ///
+    /// # Examples
+    ///
+    /// A bandit that usually pays nothing, but sometimes pays `20`. The occasional large TD
+    /// errors keep throwing the estimate around with [Loss::Mse], much less so with
+    /// [Loss::Huber]:
+    ///
+    /// ```
+    /// use rand::{Rng, SeedableRng};
+    /// use rurel::dqn::{DQNAgentTrainer, Loss};
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Arm;
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Pull;
+    ///
+    /// impl State for Arm {
+    ///     type A = Pull;
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<Pull> {
+    ///         vec![Pull]
+    ///     }
+    /// }
+    ///
+    /// impl From<Arm> for [f32; 1] {
+    ///     fn from(_: Arm) -> Self {
+    ///         [1.0]
+    ///     }
+    /// }
+    /// impl From<Pull> for [f32; 1] {
+    ///     fn from(_: Pull) -> Self {
+    ///         [1.0]
+    ///     }
+    /// }
+    /// impl From<[f32; 1]> for Pull {
+    ///     fn from(_: [f32; 1]) -> Self {
+    ///         Pull
+    ///     }
+    /// }
+    ///
+    /// // The standard deviation of the estimate over the later batches of training
+    /// let spread = |loss: Loss| {
+    ///     let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    ///     let mut trainer = DQNAgentTrainer::<Arm, 1, 1, 8>::new(0.0, 1e-2).with_loss(loss);
+    ///     let mut estimates = vec![];
+    ///     for i in 0..60 {
+    ///         let rewards = std::array::from_fn(|_| if rng.gen_bool(0.05) { 20.0 } else { 0.0 });
+    ///         trainer.train_dqn([[1.0]; 64], [[1.0]; 64], [[1.0]; 64], rewards, [true; 64]);
+    ///         if i >= 20 {
+    ///             estimates.push(trainer.expected_value(&Arm)[0]);
+    ///         }
+    ///     }
+    ///     let mean = estimates.iter().sum::<f32>() / estimates.len() as f32;
+    ///     let variance = estimates.iter().map(|e| (e - mean).powi(2)).sum::<f32>()
+    ///         / estimates.len() as f32;
+    ///     variance.sqrt()
+    /// };
+    /// assert!(spread(Loss::Huber(1.0)) < spread(Loss::Mse));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the delta of [Loss::Huber] isn't positive.
+    pub fn with_loss(mut self, loss: Loss) -> Self {
+        if let Loss::Huber(delta) = loss {
+            assert!(delta > 0.0, "the delta of the Huber loss must be positive");
+        }
+        self.loss = loss;
+        self
+    }
+
+    /// Sets the number of transitions the replay buffer holds (`capacity`), and the number of
+    /// transitions in every minibatch sampled from it (`minibatch_size`). By default, the buffer
+    /// holds 10000 transitions and minibatches have 64 transitions. This empties the replay
+    /// buffer.
+    ///
+    /// During [train()](DQNAgentTrainer::train), every transition is added to the replay buffer,
+    /// replacing the oldest one once the buffer is full. After every transition, the Q-network
+    /// takes a training step on a minibatch sampled at random from the buffer. Until
+    /// the buffer holds `minibatch_size` transitions, no training steps are taken. The buffer is
+    /// kept between calls to [train()](DQNAgentTrainer::train).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` or `minibatch_size` is `0`.
+    pub fn with_replay_buffer(mut self, capacity: usize, minibatch_size: usize) -> Self {
+        assert!(minibatch_size > 0, "the minibatch size must be positive");
+        self.replay_buffer = PrioritizedReplayBuffer::new(capacity, self.replay_buffer.alpha());
+        self.minibatch_size = minibatch_size;
+        self
+    }
+
+    /// Splits every minibatch into `micro_batches` micro-batches, which pass through the
+    /// Q-network one after another, and sums up their gradients before the optimizer takes a
+    /// step. By default, every minibatch passes through the network at once.
+    ///
+    /// As the loss of a minibatch is the mean of the losses of its transitions, the summed
+    /// gradients are those of the whole minibatch, so this trains like a minibatch of
+    /// `minibatch_size` transitions with the memory of a micro-batch. Use it together with
+    /// [with_replay_buffer()](DQNAgentTrainer::with_replay_buffer) to train on minibatches that
+    /// don't fit into memory at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dfdx::prelude::*;
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// // The positions 0 to 3, rewarded with their number and one-hot encoded, with the moves
+    /// // `Move(-1)` and `Move(1)`
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(usize);
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         self.0 as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(-1), Move(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 4] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         let mut v = [0.0; 4];
+    /// #         v[p.0] = 1.0;
+    /// #         v
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    ///
+    /// // Two networks starting from the same weights
+    /// let new = || {
+    ///     DQNAgentTrainer::<Position, 4, 2, 16>::new_on_device(0.9, 1e-2, AutoDevice::seed_from_u64(0))
+    /// };
+    /// let mut whole = new();
+    /// let mut accumulated = new().with_gradient_accumulation(8);
+    ///
+    /// let states: [[f32; 4]; 64] = std::array::from_fn(|i| Position(i % 4).into());
+    /// let actions: [[f32; 2]; 64] = std::array::from_fn(|i| Move(i as i32 % 3 - 1).into());
+    /// let next_states: [[f32; 4]; 64] = std::array::from_fn(|i| Position((i + 1) % 4).into());
+    /// let rewards: [f32; 64] = std::array::from_fn(|i| (i % 4) as f32);
+    /// for trainer in [&mut whole, &mut accumulated] {
+    ///     trainer.train_dqn(states, actions, next_states, rewards, [false; 64]);
+    /// }
+    ///
+    /// // Eight micro-batches of eight transitions train like one minibatch of 64
+    /// for x in 0..4 {
+    ///     let whole = whole.expected_value(&Position(x));
+    ///     let accumulated = accumulated.expected_value(&Position(x));
+    ///     for (w, a) in whole.iter().zip(accumulated) {
+    ///         assert!((w - a).abs() < 1e-4);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `micro_batches` is `0`.
+    pub fn with_gradient_accumulation(mut self, micro_batches: usize) -> Self {
+        assert!(
+            micro_batches > 0,
+            "the number of micro-batches must be positive"
+        );
+        self.gradient_accumulation = micro_batches;
+        self
+    }
+
+    /// Makes training sample transitions from the replay buffer with a probability proportional
+    /// to `|td_error|^alpha`, where `td_error` is the last TD error of the transition, instead of
+    /// uniformly. See
+    /// [PrioritizedReplayBuffer] for the details. This empties the
+    /// replay buffer.
+    ///
+    /// Sampling transitions with high TD errors more often than others biases training, so the
+    /// loss of every transition is weighted by its importance-sampling weight
+    /// `(len * probability)^-beta`. The exponent `beta` is increased linearly from the given value
+    /// to `1`, for full bias correction, over the first `beta_annealing_steps` training steps.
+    /// Typical values are `alpha = 0.6` and `beta = 0.4`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is negative, or `beta` is outside of `0..=1`.
+    pub fn with_prioritized_replay(
+        mut self,
+        alpha: f64,
+        beta: f64,
+        beta_annealing_steps: usize,
+    ) -> Self {
+        assert!((0.0..=1.0).contains(&beta), "beta must be between 0 and 1");
+        self.replay_buffer = PrioritizedReplayBuffer::new(self.replay_buffer.capacity(), alpha);
+        self.beta = beta;
+        self.beta_annealing_steps = beta_annealing_steps;
+        self
+    }
+
+    /// Scales the gradients of every training step down to a norm of `max_norm` whenever their
+    /// norm is larger, which keeps a single large TD error from throwing the Q-network off. By
+    /// default, gradients aren't clipped. [clipped_steps()](DQNAgentTrainer::clipped_steps)
+    /// counts how often this happens.
+    ///
+    /// # Examples
+    ///
+    /// Training on a reward of a billion, with the targets clamped to a thousand, keeps the values
+    /// finite:
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Jackpot;
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Pull;
+    ///
+    /// impl State for Jackpot {
+    ///     type A = Pull;
+    ///     fn reward(&self) -> f64 {
+    ///         1e9
+    ///     }
+    ///     fn actions(&self) -> Vec<Pull> {
+    ///         vec![Pull]
+    ///     }
+    /// }
+    ///
+    /// impl From<Jackpot> for [f32; 1] {
+    ///     fn from(_: Jackpot) -> Self {
+    ///         [1.0]
+    ///     }
+    /// }
+    /// impl From<Pull> for [f32; 1] {
+    ///     fn from(_: Pull) -> Self {
+    ///         [1.0]
+    ///     }
+    /// }
+    /// impl From<[f32; 1]> for Pull {
+    ///     fn from(_: [f32; 1]) -> Self {
+    ///         Pull
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = DQNAgentTrainer::<Jackpot, 1, 1, 16>::new(0.99, 1e-2)
+    ///     .with_gradient_clipping(1.0)
+    ///     .with_target_clipping(1e3);
+    /// for _ in 0..5 {
+    ///     trainer.train_dqn([[1.0]; 64], [[1.0]; 64], [[1.0]; 64], [1e9; 64], [false; 64]);
+    /// }
+    /// assert!(trainer.expected_value(&Jackpot)[0].is_finite());
+    /// assert!(trainer.clipped_steps() > 0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_norm` isn't positive.
+    pub fn with_gradient_clipping(mut self, max_norm: f32) -> Self {
+        assert!(max_norm > 0.0, "the maximum gradient norm must be positive");
+        self.max_gradient_norm = Some(max_norm);
+        self
+    }
+
+    /// Clamps the TD targets the Q-network is trained towards to `-max_abs..=max_abs`. By
+    /// default, targets aren't clamped. [with_value_clip()](DQNAgentTrainer::with_value_clip)
+    /// clamps them to a range which isn't symmetric.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_abs` isn't positive.
+    pub fn with_target_clipping(mut self, max_abs: f32) -> Self {
+        assert!(max_abs > 0.0, "the maximum target must be positive");
+        self.value_clip = Some((-max_abs, max_abs));
+        self
+    }
+
+    /// Clamps the TD targets the Q-network is trained towards to the range `(min, max)`, or
+    /// doesn't clamp them if `None`, which is the default. Targets that bootstrap from an
+    /// overestimated next state are then kept from feeding the overestimate back into the
+    /// network, which would otherwise let the values run away.
+    ///
+    /// If every reward is between `r_min` and `r_max`, the discounted return of any episode is
+    /// between `r_min / (1 - gamma)` and `r_max / (1 - gamma)`, if the bound is negative or
+    /// positive respectively, and between `0` and the bound otherwise, since a return can end
+    /// after a single reward. This range, with the
+    /// [step penalty](DQNAgentTrainer::with_step_penalty) subtracted from both rewards, clamps
+    /// no true value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Loop;
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Stay;
+    ///
+    /// impl State for Loop {
+    ///     type A = Stay;
+    ///     fn reward(&self) -> f64 {
+    ///         1.0
+    ///     }
+    ///     fn actions(&self) -> Vec<Stay> {
+    ///         vec![Stay]
+    ///     }
+    /// }
+    ///
+    /// impl From<Loop> for [f32; 1] {
+    ///     fn from(_: Loop) -> Self {
+    ///         [1.0]
+    ///     }
+    /// }
+    /// impl From<Stay> for [f32; 1] {
+    ///     fn from(_: Stay) -> Self {
+    ///         [1.0]
+    ///     }
+    /// }
+    /// impl From<[f32; 1]> for Stay {
+    ///     fn from(_: [f32; 1]) -> Self {
+    ///         Stay
+    ///     }
+    /// }
+    ///
+    /// // Rewards in [-1, 1] with a discount of 0.5 make for values in [-2, 2]
+    /// let (r_min, r_max, gamma) = (-1.0, 1.0, 0.5);
+    /// let range = (r_min / (1.0 - gamma), r_max / (1.0 - gamma));
+    /// assert_eq!(range, (-2.0, 2.0));
+    ///
+    /// // Staying forever is worth 2, but the targets are clipped to at most 1.5
+    /// let mut trainer = DQNAgentTrainer::<Loop, 1, 1, 16>::new(gamma as f32, 1e-2)
+    ///     .with_value_clip(Some((-1.5, 1.5)));
+    /// for _ in 0..50 {
+    ///     trainer.train_dqn([[1.0]; 64], [[1.0]; 64], [[1.0]; 64], [1.0; 64], [false; 64]);
+    /// }
+    /// let value = trainer.expected_value(&Loop)[0];
+    /// assert!((value - 1.5).abs() < 0.05);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is larger than `max`, or either is NaN.
+    pub fn with_value_clip(mut self, value_clip: Option<(f64, f64)>) -> Self {
+        if let Some((min, max)) = value_clip {
+            assert!(min <= max, "the value range must not be empty");
+        }
+        self.value_clip = value_clip.map(|(min, max)| (min as f32, max as f32));
+        self
+    }
+
+    /// Sets the penalty that is subtracted from the reward of every step taken during training,
+    /// which is `0` by default. Like
+    /// [AgentTrainer::with_step_penalty()](crate::AgentTrainer::with_step_penalty), this makes
+    /// shorter episodes better without changing the rewards of the states themselves.
+    pub fn with_step_penalty(mut self, step_penalty: f32) -> Self {
+        self.step_penalty = step_penalty;
+        self
+    }
+
+    /// Normalizes the reward of every step taken during training, after the
+    /// [step penalty](DQNAgentTrainer::with_step_penalty), by the running statistics of the
+    /// rewards or returns seen so far, as set by `normalization`, so that the network learns
+    /// values of a stable scale whatever the scale of the rewards. Setting it to `None`, which is
+    /// the default, learns from the rewards as they are. Setting it again starts over with new
+    /// statistics.
+    ///
+    /// Until the statistics have a standard deviation, such as for the first reward, rewards are
+    /// not divided by it. Normalized rewards are clipped to between `-10` and `10`, so that a
+    /// reward far out of the statistics so far can't dominate the replay buffer.
+    ///
+    /// The values the network learns are those of the normalized rewards. The statistics are
+    /// saved with the model by [save()](DQNAgentTrainer::save), and restored with it by
+    /// [load()](DQNAgentTrainer::load), so that a loaded model goes on learning on the same
+    /// scale, and its values can be mapped back to that of the rewards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::dqn::{DQNAgentTrainer, RewardNormalization};
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // The rewards are in the thousands, which would make the network diverge
+    /// // A `Walker` moves by `Move(-1)` or `Move(1)` between `Position(0)` and `Position(3)`,
+    /// // which are one-hot encoded, and is rewarded with 1000 times its position
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(usize);
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         1000.0 * self.0 as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(-1), Move(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 4] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         std::array::from_fn(|i| if i == p.0 { 1.0 } else { 0.0 })
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, m: &Move) {
+    /// #         self.0 = Position((self.0 .0 as i32 + m.0).clamp(0, 3) as usize);
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer = DQNAgentTrainer::<Position, 4, 2, 16>::new(0.5, 1e-3)
+    ///     .with_reward_normalization(Some(RewardNormalization::Rewards));
+    /// for _ in 0..20 {
+    ///     let mut agent = Walker(Position(0));
+    ///     trainer.train(&mut agent, &mut FixedIterations::new(10), &RandomExploration);
+    /// }
+    ///
+    /// // The statistics are those of the rewards of every step, which were between 0 and 3000
+    /// let stats = trainer.reward_stats().unwrap();
+    /// assert!(stats.count() >= 20 * 10);
+    /// assert!(stats.mean() > 0.0 && stats.mean() < 3000.0);
+    /// assert!(stats.std_dev() > 0.0);
+    /// assert!(trainer.expected_value(&Position(3)).iter().all(|v| v.abs() < 100.0));
+    ///
+    /// // The statistics are those of the discounted returns. The first reward is 1000 or 2000,
+    /// // which isn't divided by the standard deviation of a single return, and every normalized
+    /// // reward is clipped, so no loss is anywhere near the scale of the rewards
+    /// let mut trainer = DQNAgentTrainer::<Position, 4, 2, 16>::new(0.5, 1e-3)
+    ///     .with_reward_normalization(Some(RewardNormalization::Returns))
+    ///     .with_loss_history(true);
+    /// for _ in 0..20 {
+    ///     let mut agent = Walker(Position(1));
+    ///     trainer.train(&mut agent, &mut FixedIterations::new(10), &RandomExploration);
+    /// }
+    /// let stats = trainer.reward_stats().unwrap();
+    /// assert!(stats.count() >= 20 * 10);
+    /// assert!(stats.mean() > 0.0);
+    /// assert!(!trainer.loss_history().is_empty());
+    /// assert!(trainer.loss_history().iter().all(|loss| *loss < 100.0));
+    /// ```
+    pub fn with_reward_normalization(mut self, normalization: Option<RewardNormalization>) -> Self {
+        self.reward_normalization = normalization.map(Normalizer::new);
+        self
+    }
+
+    /// Returns the running statistics that the rewards are
+    /// [normalized](DQNAgentTrainer::with_reward_normalization) by: those of the rewards, or of
+    /// the discounted returns, seen so far. Returns `None` if rewards aren't normalized.
+    pub fn reward_stats(&self) -> Option<RunningStats> {
+        self.reward_normalization
+            .as_ref()
+            .map(|normalizer| normalizer.stats)
+    }
+
+    /// Returns the number of training steps whose gradients were scaled down by
+    /// [with_gradient_clipping()](DQNAgentTrainer::with_gradient_clipping).
+    pub fn clipped_steps(&self) -> usize {
+        self.clipped_steps
+    }
+
+    /// Returns the number of episodes trained so far, including those before the checkpoint
+    /// training was resumed from, if any.
+    pub fn episodes(&self) -> usize {
+        self.episodes
+    }
+
+    /// Sets whether the loss of every training step is recorded, so that it can be inspected
+    /// with [loss_history()](DQNAgentTrainer::loss_history). By default, it isn't. Turning
+    /// recording off discards the recorded losses.
+    ///
+    /// # Examples
+    ///
+    /// Training on the same batch over and over makes the loss go down:
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// // Unrewarded positions on a line: `Position(x)` is encoded as `[x]`, and `Move(-1)` and
+    /// // `Move(1)` one-hot
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         0.0
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(-1), Move(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer =
+    ///     DQNAgentTrainer::<Position, 1, 2, 16>::new(0.0, 1e-2).with_loss_history(true);
+    /// let states = std::array::from_fn(|i| [i as f32]);
+    /// let actions = std::array::from_fn(|i| if i % 2 == 0 { [1.0, 0.0] } else { [0.0, 1.0] });
+    /// let rewards = std::array::from_fn(|i| if i % 2 == 0 { 1.0 } else { -1.0 });
+    /// for trial in 1..=10 {
+    ///     trainer.train_dqn(states, actions, states, rewards, [true; 64]);
+    ///     // One loss for each of the 20 training steps of every call
+    ///     assert_eq!(trainer.loss_history().len(), trial * 20);
+    /// }
+    ///
+    /// let losses = trainer.loss_history();
+    /// let mean = |losses: &[f32]| losses.iter().sum::<f32>() / losses.len() as f32;
+    /// assert!(mean(&losses[180..]) < mean(&losses[..20]));
+    /// ```
+    pub fn with_loss_history(mut self, record: bool) -> Self {
+        self.loss_history = if record { Some(Vec::new()) } else { None };
+        self
+    }
+
+    /// Returns the loss of every training step taken since recording was turned on with
+    /// [with_loss_history()](DQNAgentTrainer::with_loss_history), oldest first, or an empty slice
+    /// if the losses aren't recorded.
+    pub fn loss_history(&self) -> &[f32] {
+        self.loss_history.as_deref().unwrap_or(&[])
+    }
+
+    /// Caches the [actions()](State::actions) of the `capacity` most recently seen states, like
+    /// [AgentTrainer::with_action_cache()]. The trainer enumerates the actions of every state it
+    /// computes the [action_values()](DQNAgentTrainer::action_values) of, which training does
+    /// before every action, and of every state it masks the actions of, with
+    /// [action masking](DQNAgentTrainer::with_action_masking). By default, nothing is cached.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    pub fn with_action_cache(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the action cache must hold at least one state"
+        );
+        self.action_cache = Some(ActionCache::new(capacity));
+        self
+    }
+
+    /// Returns how often the [action cache](DQNAgentTrainer::with_action_cache) held the
+    /// actions of the states they were needed for, or `None` if actions aren't cached.
+    pub fn action_cache_stats(&self) -> Option<CacheStats> {
+        self.action_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Returns the actions of `state`, from the action cache if there is one.
+    fn actions(&self, state: &S) -> Vec<S::A> {
+        match &self.action_cache {
+            Some(cache) => cache.actions(state),
+            None => state.actions(),
+        }
+    }
+
+    /// Returns the discount factor for future rewards.
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Sets the discount factor for future rewards, which is used by all training steps from then
+    /// on. See [DiscountSchedule](crate::strategy::learn::DiscountSchedule) for changing it as
+    /// training goes on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Loop;
+    ///
+    /// impl State for Loop {
+    ///     type A = Stay;
+    ///     fn reward(&self) -> f64 {
+    ///         1.0
+    ///     }
+    ///     fn actions(&self) -> Vec<Stay> {
+    ///         vec![Stay]
+    ///     }
+    /// }
+    ///
+    /// impl From<Loop> for [f32; 2] {
+    ///     fn from(_: Loop) -> Self {
+    ///         [0.0, 1.0]
+    ///     }
+    /// }
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Stay;
+    ///
+    /// impl From<Stay> for [f32; 1] {
+    ///     fn from(_: Stay) -> Self {
+    ///         [1.0]
+    ///     }
+    /// }
+    ///
+    /// impl From<[f32; 1]> for Stay {
+    ///     fn from(_: [f32; 1]) -> Self {
+    ///         Stay
+    ///     }
+    /// }
+    ///
+    /// // Staying forever earns a reward of 1 every step, which is worth more with a higher
+    /// // discount factor
+    /// let train = |gamma| {
+    ///     let mut trainer = DQNAgentTrainer::<Loop, 2, 1, 16>::new(0.0, 1e-2);
+    ///     trainer.set_gamma(gamma);
+    ///     assert_eq!(trainer.gamma(), gamma);
+    ///     for _ in 0..10 {
+    ///         let (state, action) = ([0.0, 1.0], [1.0]);
+    ///         trainer.train_dqn([state; 64], [action; 64], [state; 64], [1.0; 64], [false; 64]);
+    ///     }
+    ///     trainer.expected_value(&Loop)[0]
+    /// };
+    /// assert!(train(0.9) > train(0.0) + 0.5);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` isn't between `0` and `1`.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        check_gamma(gamma as f64);
+        self.gamma = gamma;
+    }
+
+    /// Returns the importance-sampling exponent for the current training step.
+    fn current_beta(&self) -> f64 {
+        if self.steps >= self.beta_annealing_steps {
+            return 1.0;
+        }
+        let progress = self.steps as f64 / self.beta_annealing_steps as f64;
+        self.beta + (1.0 - self.beta) * progress
+    }
+
+    /// Returns a clone of the entire learned state to be saved or used elsewhere.
+    pub fn export_learned_values(&self) -> QNetworkDevice<N, D> {
+        self.learned_values().clone()
+    }
+
+    // Returns a reference to the learned state.
+    pub fn learned_values(&self) -> &QNetworkDevice<N, D> {
+        &self.q_network
+    }
+
+    /// Imports a model, completely replacing any learned progress
+    pub fn import_model(&mut self, model: QNetworkDevice<N, D>) {
+        self.q_network.clone_from(&model);
+        self.target_q_net.clone_from(&self.q_network);
+    }
+
+    /// Imports a model stored on another device, copying it to the device of this trainer and
+    /// completely replacing any learned progress.
+    pub fn import_model_from<D2>(&mut self, model: &QNetworkDevice<N, D2>)
+    where
+        D2: Device<f32>,
+        N: BuildOnDevice<D2, f32>,
+        QNetworkDevice<N, D2>: TensorCollection<f32, D2, To<f32, D> = QNetworkDevice<N, D>>,
+    {
+        self.import_model(model.to_device(&self.dev));
+    }
+
+    /// Pre-trains the Q-network on the values learned by a tabular `trainer`, by regressing the
+    /// network's value of every learned state and action onto the learned value, for the given
+    /// number of `epochs`. Every epoch goes through all learned values once, in a random order,
+    /// in minibatches of the [minibatch size](DQNAgentTrainer::with_replay_buffer). Afterwards,
+    /// the target network is set to the pre-trained network, so further training bootstraps from
+    /// the pre-trained values.
+    ///
+    /// The states of `trainer` don't need to be of the same type as the states of this trainer,
+    /// which allows warm-starting from a table learned on a coarser abstraction of the states.
+    /// They must be encoded compatibly, however: a tabular state has to be encoded into the
+    /// values that the states it stands for are encoded into (or values close to them), and its
+    /// actions have to be encoded into the same action indices as the actions of this trainer,
+    /// since the network only ever sees the encodings.
+    ///
+    /// Returns the mean loss of every epoch.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // The positions 0 to 3, rewarded with their number, with the moves `Move(0)` and `Move(1)`,
+    /// // all of them one-hot encoded
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(usize);
+    /// # #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    /// # struct Move(usize);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         self.0 as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(0), Move(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 4] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         let mut v = [0.0; 4];
+    /// #         v[p.0] = 1.0;
+    /// #         v
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         let mut v = [0.0; 2];
+    /// #         v[m.0] = 1.0;
+    /// #         v
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         Move(if v[0] > v[1] { 0 } else { 1 })
+    /// #     }
+    /// # }
+    ///
+    /// // A table which prefers the first move on the left, and the second move on the right
+    /// let mut table = AgentTrainer::new();
+    /// table.import_state(
+    ///     (0..4)
+    ///         .map(|x| {
+    ///             let left = if x < 2 { 1.0 } else { -1.0 };
+    ///             (Position(x), [(Move(0), left), (Move(1), -left)].into_iter().collect())
+    ///         })
+    ///         .collect(),
+    /// );
+    ///
+    /// let mut trainer = DQNAgentTrainer::<Position, 4, 2, 16>::new(0.9, 1e-2);
+    /// let losses = trainer.warm_start(&table, 200);
+    /// assert!(losses.last().unwrap() < &losses[0]);
+    /// for x in 0..4 {
+    ///     assert_eq!(trainer.best_action(&Position(x)), table.best_action(&Position(x)));
+    /// }
+    /// ```
+    pub fn warm_start<T, V>(&mut self, trainer: &AgentTrainer<T, V>, epochs: usize) -> Vec<f32>
+    where
+        T: State + Into<[f32; STATE_SIZE]>,
+        T::A: DiscreteActionSpace<ACTION_SIZE>,
+        V: QValue,
+    {
+        let mut samples: Vec<([f32; STATE_SIZE], usize, f32)> = trainer
+            .iter_values()
+            .filter_map(|(state, action, value)| {
+                let state = state.clone().into();
+                let action = checked_index(action)?;
+                Some((state, action, value.to_f64() as f32))
+            })
+            .collect();
+        if samples.is_empty() {
+            return vec![0.0; epochs];
+        }
+        let mut losses = Vec::with_capacity(epochs);
+        for _epoch in 0..epochs {
+            samples.shuffle(&mut rand::thread_rng());
+            let total: f32 = samples
+                .chunks(self.minibatch_size)
+                .map(|batch| self.regression_step(batch) * batch.len() as f32)
+                .sum();
+            losses.push(total / samples.len() as f32);
+        }
+        self.target_q_net.clone_from(&self.q_network);
+        losses
+    }
+
+    /// Takes a single training step of the Q-network towards the given values of the given
+    /// states and actions, returning the loss.
+    fn regression_step(&mut self, samples: &[([f32; STATE_SIZE], usize, f32)]) -> f32 {
+        let n = samples.len();
+        let states: Batch<STATE_SIZE, D> = self
+            .dev
+            .tensor_from_vec(samples.iter().flat_map(|s| s.0).collect(), (n, Const))
+            .normalize::<Axis<1>>(0.001);
+        let actions: Tensor<(usize,), usize, _> = self
+            .dev
+            .tensor_from_vec(samples.iter().map(|s| s.1).collect(), (n,));
+        let targets: Tensor<(usize,), f32, _> = self
+            .dev
+            .tensor_from_vec(samples.iter().map(|s| s.2).collect(), (n,));
+        let weights: Tensor<(usize,), f32, _> = self.dev.tensor_from_vec(vec![1.0; n], (n,));
+
+        let grads = self.q_network.alloc_grads();
+        let action_qs = self.q_network.forward(states.trace(grads)).select(actions);
+        let loss = self.loss(action_qs, targets, weights);
+        let value = loss.as_vec()[0];
+        let grads = loss.backward();
+        self.optimizer
+            .update(&mut self.q_network, &grads)
+            .expect("Unused params");
+        value
+    }
+
+    /// Returns the best action for the given `State`: the action whose
+    /// [index](DiscreteActionSpace::index) is that of the highest output of the Q-network.
+    ///
+    /// With [action masking](DQNAgentTrainer::with_action_masking), this is the same as
+    /// [best_legal_action()](DQNAgentTrainer::best_legal_action).
+    pub fn best_action(&self, state: &S) -> Option<S::A> {
+        self.best_action_with_value(state).map(|(action, _)| action)
+    }
+
+    /// Returns the [best action](DQNAgentTrainer::best_action) for the given `State`, or
+    /// [RurelError::NoActions] if the state has no actions. Unlike a table, the Q-network has a
+    /// value for every state, so there is no best action only if there is no action at all.
+    pub fn try_best_action(&self, state: &S) -> Result<S::A, RurelError> {
+        if self.actions(state).is_empty() {
+            return Err(RurelError::NoActions);
+        }
+        self.best_action(state).ok_or(RurelError::NoActions)
+    }
+
+    /// Returns the [best action](DQNAgentTrainer::best_action) for the given `State` together
+    /// with the value the Q-network estimates for it, from the same pass through the network.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// // The unrewarded positions 0 to 2, one-hot encoded, with the moves `Move(-1)` and `Move(1)`
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// # struct Position(usize);
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         0.0
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(-1), Move(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 3] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         let mut v = [0.0; 3];
+    /// #         v[p.0] = 1.0;
+    /// #         v
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    ///
+    /// let trainer = DQNAgentTrainer::<Position, 3, 2, 16>::new(0.9, 1e-3);
+    /// let (action, value) = trainer.best_action_with_value(&Position(1)).unwrap();
+    /// let values = trainer.expected_value(&Position(1));
+    /// assert_eq!(Some(action), trainer.best_action(&Position(1)));
+    /// assert_eq!(value, values[0].max(values[1]));
+    /// ```
+    pub fn best_action_with_value(&self, state: &S) -> Option<(S::A, f32)> {
+        let values = self.expected_value(state);
+        if self.action_masking {
+            return best_legal(self.actions(state), &values);
+        }
+        let (index, value) = values
+            .iter()
+            .enumerate()
+            .max_by(|(_, v1), (_, v2)| v1.total_cmp(v2))?;
+        Some((S::A::from_index(index), *value))
+    }
+
+    /// Returns the action of `state.actions()` with the highest value, as estimated by the
+    /// Q-network, or `None` if `state` has no actions. Unlike
+    /// [best_action()](DQNAgentTrainer::best_action), which takes the action of the highest output
+    /// of the network, this never returns an action that can't be taken in `state`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// // Unrewarded positions between walls at 0 and 10, which block the way, so there is only one
+    /// // move there. `Position(x)` is encoded as `[x]`, and `Move(-1)` and `Move(1)` one-hot
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// # struct Position(i32);
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         0.0
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         // The walls at 0 and 10 block the way
+    /// #         match self.0 {
+    /// #             0 => vec![Move(1)],
+    /// #             10 => vec![Move(-1)],
+    /// #             _ => vec![Move(-1), Move(1)],
+    /// #         }
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    ///
+    /// // Whatever the untrained network thinks, only one move is legal at each wall
+    /// let trainer = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3);
+    /// assert_eq!(trainer.best_legal_action(&Position(0)), Some(Move(1)));
+    /// assert_eq!(trainer.best_legal_action(&Position(10)), Some(Move(-1)));
+    /// ```
+    pub fn best_legal_action(&self, state: &S) -> Option<S::A> {
+        best_legal(self.actions(state), &self.expected_value(state)).map(|(action, _)| action)
+    }
+
+    /// Returns the [best_legal_action()](DQNAgentTrainer::best_legal_action) of every state in
+    /// `states`, in the same order, evaluating all states in a single pass through the network
+    /// like [expected_values()](DQNAgentTrainer::expected_values).
+    pub fn best_legal_actions(&self, states: &[S]) -> Vec<Option<S::A>> {
+        states
+            .iter()
+            .zip(self.expected_values(states))
+            .map(|(state, values)| {
+                best_legal(self.actions(state), &values).map(|(action, _)| action)
+            })
+            .collect()
+    }
+
+    /// Returns the action to take in the current state of `agent`, found by searching the states
+    /// reachable from it with clones of `agent`, like [AgentTrainer::plan()], and scoring the
+    /// states at the end of the search by the value of their
+    /// [best legal action](DQNAgentTrainer::best_legal_action). Returns `None` if the state has
+    /// no actions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::{Lookahead, Search};
+    ///
+    /// // A `Walker` moves by `Move(-1)` or `Move(1)` between `Position(0)` and `Position(2)`,
+    /// // which are one-hot encoded, and ends at 2 with a reward of 10
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(usize);
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         if self.0 == 2 { 10.0 } else { 0.0 }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(-1), Move(1)]
+    /// #     }
+    /// #     fn is_terminal(&self) -> bool {
+    /// #         self.0 == 2
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 3] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         let mut v = [0.0; 3];
+    /// #         v[p.0] = 1.0;
+    /// #         v
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    /// #
+    /// # #[derive(Clone)]
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, m: &Move) {
+    /// #         self.0 = Position((self.0 .0 as i32 + m.0).clamp(0, 2) as usize);
+    /// #     }
+    /// # }
+    ///
+    /// // The untrained network knows nothing about the goal two steps away, but the search does
+    /// let trainer = DQNAgentTrainer::<Position, 3, 2, 16>::new(0.9, 1e-3);
+    /// let lookahead = Lookahead::new(2, 0.9, Search::Expectimax { samples: 1 });
+    /// assert_eq!(trainer.plan(&Walker(Position(0)), &lookahead), Some(Move(1)));
+    /// ```
+    pub fn plan<G>(&self, agent: &G, lookahead: &Lookahead) -> Option<S::A>
+    where
+        G: Agent<S> + Clone,
+    {
+        plan::plan(
+            agent,
+            lookahead,
+            |state| self.actions(state),
+            |state| {
+                best_legal(self.actions(state), &self.expected_value(state))
+                    .map(|(action, value)| (action, value as f64))
+            },
+        )
+    }
+
+    /// Trains the Q-network on the given batch of transitions, taking 20 training steps.
+    #[allow(clippy::boxed_local)]
+    pub fn train_dqn(
+        &mut self,
+        states: [[f32; STATE_SIZE]; BATCH],
+        actions: [[f32; ACTION_SIZE]; BATCH],
+        next_states: [[f32; STATE_SIZE]; BATCH],
+        rewards: [f32; BATCH],
+        dones: [bool; BATCH],
+    ) {
+        let experiences: Vec<Experience<STATE_SIZE, ACTION_SIZE>> = (0..BATCH)
+            .map(|i| Experience {
+                state: states[i],
+                action: action_index(&actions[i]),
+                reward: rewards[i],
+                next_state: next_states[i],
+                next_legal: [true; ACTION_SIZE],
+                done: dones[i],
+            })
+            .collect();
+        let weights = [1.0; BATCH];
+        for _step in 0..TRAIN_STEPS {
+            self.train_step(&experiences, &weights);
+        }
+    }
+
+    /// Returns the mean of the losses of the given Q-values, weighted by the given weights.
+    fn loss(
+        &self,
+        action_qs: Tensor<(usize,), f32, D, OwnedTape<f32, D>>,
+        target_q: Tensor<(usize,), f32, D>,
+        weights: Tensor<(usize,), f32, D>,
+    ) -> Tensor<Rank0, f32, D, OwnedTape<f32, D>> {
+        let losses = match self.loss {
+            Loss::Mse => (action_qs - target_q).square(),
+            Loss::Huber(delta) => huber_error(action_qs, target_q, delta),
+        };
+        (losses * weights).mean()
+    }
+
+    /// Returns the tensors of the given transitions and their weights, with the TD targets of the
+    /// transitions.
+    fn micro_batch(
+        &self,
+        experiences: &[Experience<STATE_SIZE, ACTION_SIZE>],
+        weights: &[f32],
+    ) -> MicroBatch<STATE_SIZE, D> {
+        let n = experiences.len();
+        let weights: Tensor<(usize,), f32, _> = self.dev.tensor_from_vec(weights.to_vec(), (n,));
+        // a next state without legal actions has no value to bootstrap from
+        let dones: Tensor<(usize,), f32, _> = self.dev.tensor_from_vec(
+            experiences
+                .iter()
+                .map(|e| {
+                    if e.done || !e.next_legal.contains(&true) {
+                        1f32
+                    } else {
+                        0f32
+                    }
+                })
+                .collect(),
+            (n,),
+        );
+        let rewards: Tensor<(usize,), f32, _> = self
+            .dev
+            .tensor_from_vec(experiences.iter().map(|e| e.reward).collect(), (n,));
+        let actions: Tensor<(usize,), usize, _> = self
+            .dev
+            .tensor_from_vec(experiences.iter().map(|e| e.action).collect(), (n,));
+
+        // Convert to tensors and normalize the states for better training
+        let states: Batch<STATE_SIZE, D> = self
+            .dev
+            .tensor_from_vec(
+                experiences.iter().flat_map(|e| e.state).collect(),
+                (n, Const),
+            )
+            .normalize::<Axis<1>>(0.001);
+        let next_states: Batch<STATE_SIZE, D> = self
+            .dev
+            .tensor_from_vec(
+                experiences.iter().flat_map(|e| e.next_state).collect(),
+                (n, Const),
+            )
+            .normalize::<Axis<1>>(0.001);
+
+        // targ_q = R + discount * max(Q(S'))
+        //   or, with Double DQN, R + discount * Q_target(S')[argmax(Q(S'))]
+        // curr_q = Q(S)[A]
+        // loss = mean(weight * loss(curr_q, targ_q))
+        // With action masking, the max and argmax only go over the legal actions of S'.
+        let next_q = if self.double_dqn || self.action_masking {
+            // with Double DQN, the Q-network selects the next action, the target network
+            // evaluates it
+            let selector = if self.double_dqn {
+                &self.q_network
+            } else {
+                &self.target_q_net
+            };
+            let next_actions: Vec<usize> = selector
+                .forward(next_states.clone())
+                .as_vec()
+                .chunks(ACTION_SIZE)
+                .zip(experiences)
+                .map(|(values, e)| {
+                    let mut best = None;
+                    for (i, v) in values.iter().enumerate() {
+                        let legal = !self.action_masking || e.next_legal[i];
+                        if legal && best.is_none_or(|b: usize| *v > values[b]) {
+                            best = Some(i);
+                        }
+                    }
+                    best.unwrap_or(0)
+                })
+                .collect();
+            let next_actions = self.dev.tensor_from_vec(next_actions, (n,));
+            self.target_q_net.forward(next_states).select(next_actions)
+        } else {
+            self.target_q_net.forward(next_states).max::<(usize,), _>()
+        };
+        let mut target_q = (next_q * (-dones + 1.0)) * self.gamma + rewards;
+        if let Some((min, max)) = self.value_clip {
+            target_q = target_q.clamp(min, max);
+        }
+
+        MicroBatch {
+            states,
+            actions,
+            targets: target_q,
+            weights,
+        }
+    }
+
+    /// Passes the given micro-batches through the Q-network one after another, and returns the
+    /// sum of their gradients, scaled by `scale`, together with the loss and the TD errors of all
+    /// `n` transitions in them.
+    fn accumulate_gradients(
+        &self,
+        micro_batches: &[MicroBatch<STATE_SIZE, D>],
+        n: usize,
+        scale: f32,
+    ) -> (Gradients<f32, D>, f32, Vec<f32>) {
+        let mut grads = self.q_network.alloc_grads();
+        let mut loss = 0.0;
+        let mut td_errors = Vec::with_capacity(n);
+        for batch in micro_batches {
+            let action_qs = self
+                .q_network
+                .forward(batch.states.clone().trace(grads))
+                .select(batch.actions.clone());
+            td_errors.extend(
+                batch
+                    .targets
+                    .as_vec()
+                    .into_iter()
+                    .zip(action_qs.as_vec())
+                    .map(|(target, current)| target - current),
+            );
+            // every micro-batch counts as much as its share of the transitions, so that the
+            // sum is the mean over all transitions
+            let share = batch.actions.shape().0 as f32 / n as f32;
+            let batch_loss = self.loss(action_qs, batch.targets.clone(), batch.weights.clone());
+            loss += batch_loss.as_vec()[0] * share;
+            grads = (batch_loss * (share * scale)).backward();
+        }
+        (grads, loss, td_errors)
+    }
+
+    /// Takes a single training step of the Q-network on the given transitions, with the loss of
+    /// every transition weighted by the given weight, and updates the target network. Returns the
+    /// TD error of every transition.
+    fn train_step(
+        &mut self,
+        experiences: &[Experience<STATE_SIZE, ACTION_SIZE>],
+        weights: &[f32],
+    ) -> Vec<f32> {
+        let n = experiences.len();
+        let size = n.div_ceil(self.gradient_accumulation);
+        let micro_batches: Vec<_> = experiences
+            .chunks(size)
+            .zip(weights.chunks(size))
+            .map(|(experiences, weights)| self.micro_batch(experiences, weights))
+            .collect();
+
+        let (mut grads, loss, td_errors) = self.accumulate_gradients(&micro_batches, n, 1.0);
+        if let Some(history) = &mut self.loss_history {
+            history.push(loss);
+        }
+
+        if let Some(max_norm) = self.max_gradient_norm {
+            let norm = gradient_norm(&self.q_network, &grads);
+            if norm > max_norm {
+                // The gradients are linear in the loss, so scaling the loss scales them
+                grads = self
+                    .accumulate_gradients(&micro_batches, n, max_norm / norm)
+                    .0;
+                self.clipped_steps += 1;
+            }
+        }
+
+        // update weights with optimizer
+        self.optimizer
+            .update(&mut self.q_network, &grads)
+            .expect("Unused params");
+
+        self.steps += 1;
+        match self.target_update {
+            TargetUpdate::Hard(n) => {
+                if self.steps.is_multiple_of(n) {
+                    self.target_q_net.clone_from(&self.q_network);
+                }
+            }
+            TargetUpdate::Soft(tau) => self.target_q_net.ema(&self.q_network, 1.0 - tau),
+        }
+        td_errors
+    }
+
+    /// Trains this [DQNAgentTrainer] using the given [ExplorationStrategy] and
+    /// [Agent] until the [TerminationStrategy] decides to stop, or the agent arrives at a
+    /// [terminal](State::is_terminal) state.
+    ///
+    /// Every transition is added to the replay buffer, and the Q-network is trained on
+    /// minibatches sampled from it, as described in
+    /// [with_replay_buffer()](DQNAgentTrainer::with_replay_buffer) and
+    /// [with_prioritized_replay()](DQNAgentTrainer::with_prioritized_replay).
+    ///
+    /// Before every action, the exploration strategy is given the
+    /// [action_values()](DQNAgentTrainer::action_values) of the current state, so value-aware
+    /// strategies such as [EpsilonGreedy](crate::strategy::explore::EpsilonGreedy) and
+    /// [BoltzmannExploration](crate::strategy::explore::BoltzmannExploration) follow what the
+    /// network has learned so far. To explore less as training goes on, keep a single
+    /// [DecayingEpsilonGreedy](crate::strategy::explore::DecayingEpsilonGreedy) for all episodes,
+    /// as its epsilon decays with every action it picks:
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::{DecayingEpsilonGreedy, EpsilonDecay};
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // A `Walker` moves by `Move(-1)` or `Move(1)` between `Position(0)` and `Position(10)`, and
+    /// // is rewarded by how close it is to 5. `Position(x)` is encoded as `[x]`
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Move(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Move;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -((self.0 - 5).abs() as f64)
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Move> {
+    /// #         vec![Move(-1), Move(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// # impl From<Move> for [f32; 2] {
+    /// #     fn from(m: Move) -> Self {
+    /// #         if m.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 2]> for Move {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Move(-1) } else { Move(1) }
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, m: &Move) {
+    /// #         self.0 = Position((self.0 .0 + m.0).clamp(0, 10));
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3);
+    /// // Explore fully at first, and only 5% of the time after 1000 actions
+    /// let exploration = DecayingEpsilonGreedy::new(1.0, 0.05, EpsilonDecay::Linear(0.00095));
+    /// for _ in 0..50 {
+    ///     let mut agent = Walker(Position(0));
+    ///     trainer.train(&mut agent, &mut FixedIterations::new(20), &exploration);
+    /// }
+    /// assert_eq!(exploration.epsilon(), 0.05);
+    /// ```
+    pub fn train(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        termination_strategy.start_episode();
+        let mut rng = rand::thread_rng();
+        // the discounted return of the episode so far, which returns are normalized by
+        let mut episode_return = 0.0;
+        loop {
+            let s_t = agent.current_state().clone();
+            let values = NetworkValues(|state: &S| self.action_values(state));
+            let action = exploration_strategy.pick_action_with_values(agent, &values);
+
+            // current action value
+            let mut reward =
+                agent.step_reward(&action) - s_t.cost(&action) - self.step_penalty as f64;
+            if let Some(normalizer) = &mut self.reward_normalization {
+                episode_return = self.gamma as f64 * episode_return + reward;
+                reward = normalizer.normalize(reward, episode_return);
+            }
+            let s_t_next = agent.current_state();
+            // only terminal states have no future value; an episode that is merely stopped
+            // still bootstraps from the state it stopped in
+            let terminal = s_t_next.is_terminal();
+            let done = termination_strategy.should_stop(s_t_next) || terminal;
+            let next_legal = if self.action_masking {
+                legal_mask(self.actions(s_t_next))
+            } else {
+                [true; ACTION_SIZE]
+            };
+            if let Some(action) = checked_index(&action) {
+                self.replay_buffer.push(Experience {
+                    state: s_t.into(),
+                    action,
+                    reward: reward as f32,
+                    next_state: s_t_next.clone().into(),
+                    next_legal,
+                    done: terminal,
+                });
+            }
+
+            if self.replay_buffer.len() >= self.minibatch_size {
+                let samples =
+                    self.replay_buffer
+                        .sample(self.minibatch_size, self.current_beta(), &mut rng);
+                let minibatch: Vec<_> = samples.iter().map(|s| s.experience).collect();
+                let weights: Vec<_> = samples.iter().map(|s| s.weight).collect();
+                let td_errors = self.train_step(&minibatch, &weights);
+                for (sample, td_error) in samples.iter().zip(td_errors) {
+                    self.replay_buffer
+                        .update_priority(sample.index, td_error as f64);
+                }
+            }
+
+            if done {
+                break;
+            }
+        }
+        self.episodes += 1;
+
+        #[cfg(feature = "save")]
+        if let Some((every, path, activations)) = &self.checkpoints {
+            if self.episodes.is_multiple_of(*every) {
+                self.checkpoint_error = self
+                    .write_checkpoint(path, activations, exploration_strategy)
+                    .err();
+            }
+        }
+    }
+
+    /// Plays a single episode with `agent`, always taking the
+    /// [best_action()](DQNAgentTrainer::best_action), until `termination_strategy` decides to
+    /// stop. Nothing is learned from the episode and nothing is explored: the actions are those
+    /// the Q-network being trained values most, not the target network, so this evaluates what
+    /// was learned so far. See [AgentTrainer::rollout()] for an example.
+    pub fn rollout(
+        &self,
+        agent: &mut dyn Agent<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+    ) -> RolloutResult<S> {
+        rollout::rollout(agent, termination_strategy, |state| self.best_action(state))
+    }
+
+    /// Plays `episodes` episodes like [rollout()](DQNAgentTrainer::rollout), each with a fresh
+    /// agent from `make_agent` and a fresh termination strategy from `make_termination`, and
+    /// returns the mean and standard deviation of their total rewards. See
+    /// [AgentTrainer::evaluate()] for an example.
+    pub fn evaluate<G, T>(
+        &self,
+        make_agent: impl FnMut() -> G,
+        make_termination: impl FnMut() -> T,
+        episodes: usize,
+    ) -> EvalStats
+    where
+        G: Agent<S>,
+        T: TerminationStrategy<S>,
+    {
+        rollout::evaluate(make_agent, make_termination, episodes, |state| {
+            self.best_action(state)
+        })
+    }
+
+    /// Trains like [train()](DQNAgentTrainer::train) for up to `max_episodes` episodes, and
+    /// stops early once greedy evaluations stop improving, as set by `early_stopping`, like
+    /// [AgentTrainer::train_with_early_stopping()], which has an example. The trainer is left
+    /// with the Q-network of the best evaluation, which the target network is reset to.
+    pub fn train_with_early_stopping<G, T>(
+        &mut self,
+        mut make_agent: impl FnMut() -> G,
+        mut make_termination: impl FnMut() -> T,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        max_episodes: usize,
+        early_stopping: &EarlyStopping,
+    ) -> EarlyStoppingReport
+    where
+        G: Agent<S>,
+        T: TerminationStrategy<S>,
+    {
+        let mut plateau = Plateau::new(early_stopping);
+        let mut best = None;
+        for _ in 0..max_episodes {
+            self.train(
+                &mut make_agent(),
+                &mut make_termination(),
+                exploration_strategy,
+            );
+            if !plateau.trained() {
+                continue;
+            }
+            let stats = self.evaluate(
+                &mut make_agent,
+                &mut make_termination,
+                early_stopping.rollouts(),
+            );
+            match plateau.evaluated(stats) {
+                Check::Improved => best = Some(self.q_network.clone()),
+                Check::Stale => {}
+                Check::Stop => break,
+            }
+        }
+        if let Some(best) = best {
+            self.import_model(best);
+        }
+        plateau.into_report()
+    }
+
+    /// Saves the weights of the Q-network to a [safetensors](https://huggingface.co/docs/safetensors)
+    /// file, which can be loaded with [load()](DQNAgentTrainer::load), or outside of Rust, such
+    /// as with `safetensors.numpy.load_file()` in Python. The tensors are named after their path
+    /// in the network, such as `0.0.weight` for the weights of the first layer of [QNetwork],
+    /// and are stored as `f32`, with linear layers as `[outputs, inputs]`.
+    ///
+    /// The file also holds metadata: the `format_version` of the file, which
+    /// [load()](DQNAgentTrainer::load) checks, and a description of the network: `state_size`
+    /// and `action_size`, `hidden_sizes`, the number of outputs of every linear layer but the
+    /// last, such as `[64,64]`, `architecture`, the name of the Rust type of the network, and
+    /// `activations`, the activation functions of the network in order, such as `[ReLU,ReLU]`.
+    /// The name of the type is only informative, as it may change with the compiler; the
+    /// activation functions are named by [Activations], which networks of your own types have to
+    /// implement to be saved.
+    ///
+    /// The model is written to a temporary file first, which then replaces the file at `path`,
+    /// so an interrupted save doesn't corrupt a model saved before.
+    ///
+    /// Fails with [RurelError::Io] if the file can't be written, and with [RurelError::Device]
+    /// if the weights can't be read from the device of the network.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::State;
+    ///
+    /// // The positions and steps of the example of `DQNAgentTrainer`
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Step;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -(self.0 as f64).abs()
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Step> {
+    /// #         vec![Step(-1), Step(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// #
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Step(i32);
+    /// #
+    /// # impl From<Step> for [f32; 2] {
+    /// #     fn from(s: Step) -> Self {
+    /// #         if s.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<[f32; 2]> for Step {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Step(-1) } else { Step(1) }
+    /// #     }
+    /// # }
+    ///
+    /// let path = std::env::temp_dir().join("rurel_dqn.safetensors");
+    /// let path = path.to_str().unwrap();
+    /// let trainer = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3);
+    /// trainer.save(path).unwrap();
+    ///
+    /// let mut loaded = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3);
+    /// loaded.load(path).unwrap();
+    /// for x in -3..=3 {
+    ///     assert_eq!(loaded.expected_value(&Position(x)), trainer.expected_value(&Position(x)));
+    /// }
+    ///
+    /// let file = std::fs::read(path).unwrap();
+    /// let (_, header) = safetensors::SafeTensors::read_metadata(&file).unwrap();
+    /// let metadata = header.metadata().as_ref().unwrap();
+    /// assert_eq!(metadata["format_version"], "1");
+    /// assert_eq!(metadata["state_size"], "1");
+    /// assert_eq!(metadata["action_size"], "2");
+    /// assert_eq!(metadata["hidden_sizes"], "[16,16]");
+    /// assert_eq!(metadata["activations"], "[ReLU,ReLU]");
+    ///
+    /// // The weights would fit a network with other activation functions, but wouldn't work there
+    /// use dfdx::prelude::{AutoDevice, Tanh};
+    /// use rurel::dqn::QNetwork;
+    /// use rurel::RurelError;
+    ///
+    /// let mut tanh =
+    ///     DQNAgentTrainer::<Position, 1, 2, 16, AutoDevice, QNetwork<1, 2, 16, Tanh>>::new(0.9, 1e-3);
+    /// assert!(matches!(tanh.load(path), Err(RurelError::InvalidFormat(_))));
+    /// ```
+    #[cfg(feature = "save")]
+    pub fn save(&self, path: &str) -> Result<(), RurelError>
+    where
+        N: Activations,
+    {
+        export::save::<_, D, STATE_SIZE, ACTION_SIZE>(
+            &self.q_network,
+            std::any::type_name::<N>(),
+            &export::activation_names::<N>(),
+            self.normalization_metadata(),
+            path,
+        )
+    }
+
+    /// Loads a model saved with [save()](DQNAgentTrainer::save). The file doesn't depend on the
+    /// device it was saved from, so a model trained on the GPU can be loaded on the CPU and vice
+    /// versa.
+    ///
+    /// Fails with [RurelError::UnsupportedVersion] if the model was saved in a newer version of
+    /// the format, and with [RurelError::InvalidFormat] if the file isn't a saved model, or holds
+    /// a network of another architecture, including one whose metadata gives other state or
+    /// action sizes, or other activation functions. Files without metadata, such as those saved
+    /// by earlier versions, are still loaded.
+    ///
+    /// If the model was saved with
+    /// [reward normalization](DQNAgentTrainer::with_reward_normalization), the normalization and
+    /// its statistics are restored with it. Otherwise, whether and how this trainer normalizes
+    /// rewards is left as it is.
+    #[cfg(feature = "save")]
+    pub fn load(&mut self, path: &str) -> Result<(), RurelError>
+    where
+        N: Activations,
+    {
+        export::check::<STATE_SIZE, ACTION_SIZE>(path, &export::activation_names::<N>())?;
+        let normalizer = match export::metadata(path)? {
+            Some(metadata) => Normalizer::from_metadata(&metadata)?,
+            None => None,
+        };
+        self.q_network.load_safetensors(path)?;
+        self.target_q_net.clone_from(&self.q_network);
+        if normalizer.is_some() {
+            self.reward_normalization = normalizer;
+        }
+        Ok(())
+    }
+
+    /// Returns the metadata that records the reward normalization in a saved model, which is
+    /// empty if rewards aren't normalized.
+    #[cfg(feature = "save")]
+    fn normalization_metadata(&self) -> HashMap<String, String> {
+        self.reward_normalization
+            .as_ref()
+            .map(Normalizer::metadata)
+            .unwrap_or_default()
+    }
+
+    /// Saves a checkpoint to the file at `path` after every `every` episodes of training, with
+    /// [save_checkpoint()](DQNAgentTrainer::save_checkpoint), so that training can be resumed
+    /// with [load_checkpoint()](DQNAgentTrainer::load_checkpoint) if it is interrupted. Every
+    /// checkpoint replaces the previous one. If a checkpoint can't be saved, training goes on,
+    /// and the error is kept in [checkpoint_error()](DQNAgentTrainer::checkpoint_error). See
+    /// [AgentTrainer::with_checkpoints()] for an example.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is `0`.
+    #[cfg(feature = "save")]
+    pub fn with_checkpoints(mut self, every: usize, path: &str) -> Self
+    where
+        N: Activations,
+    {
+        assert!(every > 0, "checkpoints must be at least one episode apart");
+        self.checkpoints = Some((every, path.to_string(), export::activation_names::<N>()));
+        self
+    }
+
+    /// Returns the error of the last checkpoint saved during training, if it couldn't be saved,
+    /// or `None` if it was saved or no checkpoint was due yet. A failed checkpoint doesn't stop
+    /// training. See [AgentTrainer::checkpoint_error()] for an example.
+    #[cfg(feature = "save")]
+    pub fn checkpoint_error(&self) -> Option<&RurelError> {
+        self.checkpoint_error.as_ref()
+    }
+
+    /// Saves a checkpoint of training to the file at `path`, which can be loaded with
+    /// [load_checkpoint()](DQNAgentTrainer::load_checkpoint) to continue training where it was.
+    /// The checkpoint is the model as saved by [save()](DQNAgentTrainer::save), which holds the
+    /// number of [episodes](DQNAgentTrainer::episodes) and training steps so far and the state
+    /// of `exploration_strategy` in its metadata as well, as `episodes`, `steps` and
+    /// `exploration`.
+    ///
+    /// The replay buffer and the state of the optimizer aren't saved, so a resumed run first
+    /// fills the replay buffer again, and the optimizer starts over, such as the moment
+    /// estimates of Adam.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::{DecayingEpsilonGreedy, EpsilonDecay};
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // The `Walker` of the example of `DQNAgentTrainer`
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = Step;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -(self.0 as f64).abs()
+    /// #     }
+    /// #     fn actions(&self) -> Vec<Step> {
+    /// #         vec![Step(-1), Step(1)]
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<Position> for [f32; 1] {
+    /// #     fn from(p: Position) -> Self {
+    /// #         [p.0 as f32]
+    /// #     }
+    /// # }
+    /// #
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Step(i32);
+    /// #
+    /// # impl From<Step> for [f32; 2] {
+    /// #     fn from(s: Step) -> Self {
+    /// #         if s.0 < 0 { [1.0, 0.0] } else { [0.0, 1.0] }
+    /// #     }
+    /// # }
+    /// #
+    /// # impl From<[f32; 2]> for Step {
+    /// #     fn from(v: [f32; 2]) -> Self {
+    /// #         if v[0] > v[1] { Step(-1) } else { Step(1) }
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &Step) {
+    /// #         self.0 = Position((self.0 .0 + step.0).clamp(-5, 5));
+    /// #     }
+    /// # }
+    ///
+    /// let path = std::env::temp_dir().join("rurel_dqn_checkpoint.safetensors");
+    /// let path = path.to_str().unwrap();
+    /// let exploration = || DecayingEpsilonGreedy::new(1.0, 0.1, EpsilonDecay::Linear(0.01));
+    ///
+    /// let mut trainer = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3)
+    ///     .with_checkpoints(4, path);
+    /// let interrupted = exploration();
+    /// for _ in 0..6 {
+    ///     trainer.train(&mut Walker(Position(3)), &mut FixedIterations::new(9), &interrupted);
+    /// }
+    ///
+    /// // The checkpoint was saved after 4 episodes of 10 actions
+    /// let resumed = exploration();
+    /// let mut trainer = DQNAgentTrainer::<Position, 1, 2, 16>::new(0.9, 1e-3);
+    /// trainer.load_checkpoint(path, &resumed).unwrap();
+    /// assert_eq!(trainer.episodes(), 4);
+    /// assert!((resumed.epsilon() - 0.6).abs() < 1e-9);
+    /// ```
+    #[cfg(feature = "save")]
+    pub fn save_checkpoint(
+        &self,
+        path: &str,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) -> Result<(), RurelError>
+    where
+        N: Activations,
+    {
+        self.write_checkpoint(path, &export::activation_names::<N>(), exploration_strategy)
+    }
+
+    /// Saves a checkpoint to the file at `path`, with the names of the activation functions of
+    /// the network.
+    #[cfg(feature = "save")]
+    fn write_checkpoint(
+        &self,
+        path: &str,
+        activations: &[&str],
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) -> Result<(), RurelError> {
+        let exploration: Vec<String> = exploration_strategy
+            .save_state()
+            .iter()
+            .map(u64::to_string)
+            .collect();
+        let mut extra = self.normalization_metadata();
+        extra.extend([
+            ("episodes".to_string(), self.episodes.to_string()),
+            ("steps".to_string(), self.steps.to_string()),
+            ("exploration".to_string(), exploration.join(",")),
+        ]);
+        export::save::<_, D, STATE_SIZE, ACTION_SIZE>(
+            &self.q_network,
+            std::any::type_name::<N>(),
+            activations,
+            extra,
+            path,
+        )
+    }
+
+    /// Loads a checkpoint saved with [save_checkpoint()](DQNAgentTrainer::save_checkpoint) like
+    /// [load()](DQNAgentTrainer::load), and restores the number of episodes and training steps,
+    /// and the state of `exploration_strategy`, which has to be a strategy of the same kind as
+    /// the one the checkpoint was saved with.
+    ///
+    /// Fails like [load()](DQNAgentTrainer::load), and with [RurelError::InvalidFormat] if the
+    /// file isn't a checkpoint, or holds the state of another kind of exploration strategy.
+    #[cfg(feature = "save")]
+    pub fn load_checkpoint(
+        &mut self,
+        path: &str,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) -> Result<(), RurelError>
+    where
+        N: Activations,
+    {
+        let not_checkpoint =
+            || RurelError::InvalidFormat("the file is not a checkpoint".to_string());
+        let metadata = export::metadata(path)?.ok_or_else(not_checkpoint)?;
+        let counter = |key: &str| -> Result<usize, RurelError> {
+            metadata
+                .get(key)
+                .and_then(|count| count.parse().ok())
+                .ok_or_else(not_checkpoint)
+        };
+        let episodes = counter("episodes")?;
+        let steps = counter("steps")?;
+        let exploration = metadata.get("exploration").ok_or_else(not_checkpoint)?;
+        let exploration = exploration
+            .split(',')
+            .filter(|word| !word.is_empty())
+            .map(|word| word.parse().map_err(|_| not_checkpoint()))
+            .collect::<Result<Vec<u64>, RurelError>>()?;
+
+        self.load(path)?;
+        exploration_strategy.restore_state(&exploration)?;
+        self.episodes = episodes;
+        self.steps = steps;
+        Ok(())
+    }
+}
+
+/// Returns the index of `action`, or `None` if it isn't below `ACTION_SIZE`, in which case the
+/// action can't be learned or looked up. Such an index is a bug of the [DiscreteActionSpace] of
+/// the action, so debug builds panic, and release builds skip the action.
+fn checked_index<A, const ACTION_SIZE: usize>(action: &A) -> Option<usize>
+where
+    A: DiscreteActionSpace<ACTION_SIZE>,
+{
+    let index = action.index();
+    debug_assert!(
+        index < ACTION_SIZE,
+        "the index of an action must be below {}, but it is {}",
+        ACTION_SIZE,
+        index
+    );
+    (index < ACTION_SIZE).then_some(index)
+}
+
+/// Returns the index of the action with the highest positive value, or `0` if there is none.
+fn action_index<const ACTION_SIZE: usize>(action: &[f32; ACTION_SIZE]) -> usize {
+    let mut max_idx = 0;
+    let mut max_val = 0f32;
+    for (i, v) in action.iter().enumerate() {
+        if *v > max_val {
+            max_val = *v;
+            max_idx = i;
+        }
+    }
+    max_idx
+}
+
+/// The transitions of a training step that pass through the Q-network together, with their TD
+/// targets.
+struct MicroBatch<const STATE_SIZE: usize, D: Device<f32>> {
+    states: Batch<STATE_SIZE, D>,
+    actions: Tensor<(usize,), usize, D>,
+    targets: Tensor<(usize,), f32, D>,
+    weights: Tensor<(usize,), f32, D>,
+}
+
+/// The values of the actions of every state, as the network of a [DQNAgentTrainer] computes them
+/// with the function it wraps, for exploration strategies which pick actions by their values.
+/// The network is only queried when a strategy asks for values, so strategies which ignore
+/// them, such as [RandomExploration](crate::strategy::explore::RandomExploration), don't cost a
+/// forward pass for every step.
+struct NetworkValues<F>(F);
+
+impl<S, F> ValueStore<S> for NetworkValues<F>
+where
+    S: State,
+    F: Fn(&S) -> HashMap<S::A, f64>,
+{
+    fn get(&self, state: &S, action: &S::A) -> Option<f64> {
+        (self.0)(state).get(action).copied()
+    }
+
+    fn set(&mut self, _state: &S, _action: &S::A, _value: f64) -> Option<f64> {
+        unreachable!("exploration strategies only read the values of the network")
+    }
+
+    fn values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, f64>>> {
+        Some(Cow::Owned((self.0)(state)))
+    }
+}
+
+/// Returns the action of `actions` with the highest of the given values, together with that
+/// value, or `None` if there are no actions.
+fn best_legal<A, const ACTION_SIZE: usize>(
+    actions: Vec<A>,
+    values: &[f32; ACTION_SIZE],
+) -> Option<(A, f32)>
+where
+    A: DiscreteActionSpace<ACTION_SIZE>,
+{
+    actions
+        .into_iter()
+        .filter_map(|action| {
+            let value = values[checked_index(&action)?];
+            Some((action, value))
+        })
+        .max_by(|(_, v1), (_, v2)| v1.total_cmp(v2))
+}
+
+/// Returns which of all actions, by index, are among `actions`.
+fn legal_mask<A, const ACTION_SIZE: usize>(actions: Vec<A>) -> [bool; ACTION_SIZE]
+where
+    A: DiscreteActionSpace<ACTION_SIZE>,
+{
+    let mut legal = [false; ACTION_SIZE];
+    for index in actions.iter().filter_map(checked_index) {
+        legal[index] = true;
+    }
+    legal
+}
+
+impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize, N> Default
+    for DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, AutoDevice, N>
+where
+    S: State + Into<[f32; STATE_SIZE]>,
+    S::A: DiscreteActionSpace<ACTION_SIZE>,
+    N: BuildOnDevice<AutoDevice, f32>,
+    N::Built: QNetworkModel<STATE_SIZE, ACTION_SIZE, AutoDevice>,
+{
+    fn default() -> Self {
+        Self::new(0.99, 1e-3)
+    }
+}
+
+impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize, D, N> Trainer<S>
+    for DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, D, N>
+where
+    S: State + Into<[f32; STATE_SIZE]>,
+    S::A: DiscreteActionSpace<ACTION_SIZE>,
+    D: Device<f32> + DeviceBuildExt,
+    D: TensorToArray<Rank1<ACTION_SIZE>, f32, Array = [f32; ACTION_SIZE]>,
+    N: BuildOnDevice<D, f32>,
+    N::Built: QNetworkModel<STATE_SIZE, ACTION_SIZE, D>,
+{
+    fn train(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        DQNAgentTrainer::train(self, agent, termination_strategy, exploration_strategy);
+    }
+
+    fn best_action(&self, state: &S) -> Option<S::A> {
+        DQNAgentTrainer::best_action(self, state)
+    }
+
+    fn action_values(&self, state: &S) -> HashMap<S::A, f64> {
+        DQNAgentTrainer::action_values(self, state)
+    }
+}
@@ -0,0 +1,187 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for normalizing the rewards the DQN trainer learns from by their running statistics.
+
+#[cfg(feature = "save")]
+use std::collections::HashMap;
+
+#[cfg(feature = "save")]
+use crate::RurelError;
+
+/// What the rewards are scaled by, if they are normalized with
+/// [with_reward_normalization()](super::DQNAgentTrainer::with_reward_normalization).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardNormalization {
+    /// Every reward is standardized by the running mean and standard deviation of the rewards
+    /// seen so far.
+    Rewards,
+    /// Every reward is divided by the running standard deviation of the discounted returns of
+    /// the episodes so far, without being shifted, so that the values learned are of a stable
+    /// scale, and rewards keep their sign.
+    Returns,
+}
+
+/// The running count, mean and variance of a series of numbers, which are updated with every
+/// number with the algorithm of
+/// [Welford](https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Welford's_online_algorithm),
+/// without keeping the numbers.
+///
+/// # Example
+///
+/// ```
+/// use rurel::dqn::RunningStats;
+///
+/// let rewards = [3.0, -1.0, 4.0, 1.0, -5.0, 9.0, 2.0, 6.0];
+/// let mut stats = RunningStats::new();
+/// for reward in rewards {
+///     stats.push(reward);
+/// }
+///
+/// let n = rewards.len() as f64;
+/// let mean = rewards.iter().sum::<f64>() / n;
+/// let variance = rewards.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+/// assert_eq!(stats.count(), 8);
+/// assert!((stats.mean() - mean).abs() < 1e-12);
+/// assert!((stats.variance() - variance).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RunningStats {
+    count: u64,
+    mean: f64,
+    /// The sum of the squared differences of the numbers from their mean.
+    m2: f64,
+}
+
+impl RunningStats {
+    /// Creates the statistics of no numbers.
+    pub fn new() -> RunningStats {
+        RunningStats::default()
+    }
+
+    /// Adds `x` to the numbers.
+    pub fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Returns how many numbers were added.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the mean of the numbers, or `0` if there are none.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Returns the variance of the numbers, that of the population rather than of a sample, or
+    /// `0` if there are none.
+    pub fn variance(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+
+    /// Returns the standard deviation of the numbers, or `0` if there are none.
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+/// The normalization of the rewards of a trainer, with the statistics it has gathered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Normalizer {
+    pub(crate) normalization: RewardNormalization,
+    pub(crate) stats: RunningStats,
+}
+
+/// The smallest standard deviation rewards are divided by. Until the statistics have a larger
+/// one, such as after their first number, or while all numbers are the same, rewards are only
+/// shifted by the mean, if they are, rather than divided by next to nothing.
+const MIN_STD_DEV: f64 = 1e-8;
+
+/// The largest magnitude of a normalized reward, beyond which it is clipped, so that a single
+/// reward far out of the statistics so far doesn't dominate the replay buffer.
+const MAX_NORMALIZED_REWARD: f64 = 10.0;
+
+impl Normalizer {
+    /// Creates a normalizer which hasn't seen any reward.
+    pub(crate) fn new(normalization: RewardNormalization) -> Normalizer {
+        Normalizer {
+            normalization,
+            stats: RunningStats::new(),
+        }
+    }
+
+    /// Adds `reward`, whose discounted return in its episode so far is `episode_return`, to the
+    /// statistics, and returns it normalized by them, clipped to [MAX_NORMALIZED_REWARD].
+    pub(crate) fn normalize(&mut self, reward: f64, episode_return: f64) -> f64 {
+        let shifted = match self.normalization {
+            RewardNormalization::Rewards => {
+                self.stats.push(reward);
+                reward - self.stats.mean()
+            }
+            RewardNormalization::Returns => {
+                self.stats.push(episode_return);
+                reward
+            }
+        };
+        let std_dev = self.stats.std_dev();
+        let normalized = if std_dev < MIN_STD_DEV {
+            shifted
+        } else {
+            shifted / std_dev
+        };
+        normalized.clamp(-MAX_NORMALIZED_REWARD, MAX_NORMALIZED_REWARD)
+    }
+
+    /// Returns the normalization and its statistics as the metadata of a saved model.
+    #[cfg(feature = "save")]
+    pub(crate) fn metadata(&self) -> HashMap<String, String> {
+        let normalization = match self.normalization {
+            RewardNormalization::Rewards => "rewards",
+            RewardNormalization::Returns => "returns",
+        };
+        HashMap::from([
+            (
+                "reward_normalization".to_string(),
+                normalization.to_string(),
+            ),
+            ("reward_count".to_string(), self.stats.count.to_string()),
+            ("reward_mean".to_string(), self.stats.mean.to_string()),
+            ("reward_m2".to_string(), self.stats.m2.to_string()),
+        ])
+    }
+
+    /// Reads a normalizer from the metadata of a saved model, returning `None` if the model was
+    /// saved without normalization.
+    #[cfg(feature = "save")]
+    pub(crate) fn from_metadata(
+        metadata: &HashMap<String, String>,
+    ) -> Result<Option<Normalizer>, RurelError> {
+        let malformed =
+            || RurelError::InvalidFormat("invalid reward normalization metadata".to_string());
+        let normalization = match metadata.get("reward_normalization").map(String::as_str) {
+            None => return Ok(None),
+            Some("rewards") => RewardNormalization::Rewards,
+            Some("returns") => RewardNormalization::Returns,
+            Some(_) => return Err(malformed()),
+        };
+        let field = |key: &str| metadata.get(key).ok_or_else(malformed);
+        let stats = RunningStats {
+            count: field("reward_count")?.parse().map_err(|_| malformed())?,
+            mean: field("reward_mean")?.parse().map_err(|_| malformed())?,
+            m2: field("reward_m2")?.parse().map_err(|_| malformed())?,
+        };
+        Ok(Some(Normalizer {
+            normalization,
+            stats,
+        }))
+    }
+}
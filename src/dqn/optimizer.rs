@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the optimizers the Q-network can be trained with.
+
+use dfdx::nn::tensor_collection::{
+    RecursiveWalker, TensorCollection, TensorOptions, TensorVisitor, ViewTensorRef,
+};
+use dfdx::optim::{Adam, Optimizer, OptimizerUpdateError, RMSprop, Sgd};
+use dfdx::prelude::*;
+
+/// The optimizer a [DQNAgentTrainer](super::DQNAgentTrainer) trains its Q-network with, along
+/// with its hyperparameters. See
+/// [new_with_optimizer()](super::DQNAgentTrainer::new_with_optimizer).
+#[derive(Debug, Clone, Copy)]
+pub enum OptimizerConfig {
+    /// Stochastic gradient descent, optionally with momentum and weight decay.
+    Sgd(SgdConfig),
+    /// Adam, with the given betas and epsilon.
+    Adam(AdamConfig),
+    /// RMSprop, with the given smoothing constant, epsilon and optional momentum.
+    RMSprop(RMSpropConfig),
+}
+
+impl OptimizerConfig {
+    /// The optimizer used by [new()](super::DQNAgentTrainer::new): SGD with the given
+    /// `learning_rate` and a Nesterov momentum of `0.9`.
+    pub fn sgd(learning_rate: f64) -> OptimizerConfig {
+        OptimizerConfig::Sgd(SgdConfig {
+            lr: learning_rate,
+            momentum: Some(Momentum::Nesterov(0.9)),
+            weight_decay: None,
+        })
+    }
+
+    /// Returns the learning rate of the optimizer.
+    pub(crate) fn learning_rate(&self) -> f64 {
+        match self {
+            OptimizerConfig::Sgd(cfg) => cfg.lr,
+            OptimizerConfig::Adam(cfg) => cfg.lr,
+            OptimizerConfig::RMSprop(cfg) => cfg.lr,
+        }
+    }
+}
+
+/// An optimizer built from an [OptimizerConfig].
+pub(crate) enum AnyOptimizer<M, D: Storage<f32>> {
+    Sgd(Sgd<M, f32, D>),
+    Adam(Adam<M, f32, D>),
+    RMSprop(RMSprop<M, f32, D>),
+}
+
+impl<M, D: Device<f32>> AnyOptimizer<M, D> {
+    /// Creates the optimizer described by `config` for the given `model`.
+    pub(crate) fn new(model: &M, config: OptimizerConfig) -> AnyOptimizer<M, D> {
+        match config {
+            OptimizerConfig::Sgd(cfg) => AnyOptimizer::Sgd(Sgd::new(model, cfg)),
+            OptimizerConfig::Adam(cfg) => AnyOptimizer::Adam(Adam::new(model, cfg)),
+            OptimizerConfig::RMSprop(cfg) => AnyOptimizer::RMSprop(RMSprop::new(model, cfg)),
+        }
+    }
+}
+
+impl<M: TensorCollection<f32, D>, D: Device<f32>> Optimizer<M, D, f32> for AnyOptimizer<M, D> {
+    fn update(
+        &mut self,
+        module: &mut M,
+        gradients: &Gradients<f32, D>,
+    ) -> Result<(), OptimizerUpdateError<D::Err>> {
+        match self {
+            AnyOptimizer::Sgd(opt) => opt.update(module, gradients),
+            AnyOptimizer::Adam(opt) => opt.update(module, gradients),
+            AnyOptimizer::RMSprop(opt) => opt.update(module, gradients),
+        }
+    }
+}
+
+/// Sums the squares of the gradients of all trainable tensors it visits.
+struct SquaredNorm<'a, D: Storage<f32>> {
+    gradients: &'a Gradients<f32, D>,
+    total: f32,
+}
+
+impl<D: Device<f32>> TensorVisitor<f32, D> for SquaredNorm<'_, D> {
+    type Viewer = ViewTensorRef;
+    type Err = D::Err;
+    type E2 = f32;
+    type D2 = D;
+
+    fn visit<S: Shape>(
+        &mut self,
+        opts: TensorOptions<S, f32, D>,
+        t: &Tensor<S, f32, D>,
+    ) -> Result<Option<Tensor<S, f32, D>>, Self::Err> {
+        if opts.do_gradient_update {
+            self.total += self.gradients.get(t).square().sum::<Rank0, _>().as_vec()[0];
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the L2 norm of the gradients of all trainable parameters of `model`, which must all
+/// be present in `gradients`.
+pub(crate) fn gradient_norm<M: TensorCollection<f32, D>, D: Device<f32>>(
+    model: &M,
+    gradients: &Gradients<f32, D>,
+) -> f32 {
+    let mut op = SquaredNorm {
+        gradients,
+        total: 0.0,
+    };
+    M::iter_tensors(&mut RecursiveWalker {
+        m: model,
+        f: &mut op,
+    })
+    .expect("Unable to compute the gradient norm");
+    op.total.sqrt()
+}
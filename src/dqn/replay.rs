@@ -0,0 +1,308 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing the experience replay buffers of the DQN trainer.
+
+use rand::Rng;
+
+/// A transition, stored as inputs for the Q-network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Experience<const STATE_SIZE: usize, const ACTION_SIZE: usize> {
+    /// The state the action was taken in.
+    pub state: [f32; STATE_SIZE],
+    /// The index of the action that was taken.
+    pub action: usize,
+    /// The reward that was received after taking the action.
+    pub reward: f32,
+    /// The state that was arrived at after taking the action.
+    pub next_state: [f32; STATE_SIZE],
+    /// Which actions can be taken in the next state, by index. The bootstrap target only takes
+    /// these actions into account.
+    pub next_legal: [bool; ACTION_SIZE],
+    /// Whether the next state is terminal, so that no future value is bootstrapped from it.
+    pub done: bool,
+}
+
+/// A bounded buffer of the most recent transitions. Once the buffer is full, every new transition
+/// replaces the oldest one.
+pub struct ReplayBuffer<const STATE_SIZE: usize, const ACTION_SIZE: usize> {
+    capacity: usize,
+    experiences: Vec<Experience<STATE_SIZE, ACTION_SIZE>>,
+    /// The index of the oldest transition, which is replaced next once the buffer is full.
+    oldest: usize,
+}
+
+impl<const STATE_SIZE: usize, const ACTION_SIZE: usize> ReplayBuffer<STATE_SIZE, ACTION_SIZE> {
+    /// Constructs an empty buffer which holds up to `capacity` transitions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    pub fn new(capacity: usize) -> ReplayBuffer<STATE_SIZE, ACTION_SIZE> {
+        assert!(capacity > 0, "the replay buffer capacity must be positive");
+        ReplayBuffer {
+            capacity,
+            experiences: Vec::new(),
+            oldest: 0,
+        }
+    }
+
+    /// Returns the maximum number of transitions in the buffer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of transitions in the buffer.
+    pub fn len(&self) -> usize {
+        self.experiences.len()
+    }
+
+    /// Returns whether the buffer holds no transitions.
+    pub fn is_empty(&self) -> bool {
+        self.experiences.is_empty()
+    }
+
+    /// Returns the transition at `index`, or `None` if there is none.
+    pub fn get(&self, index: usize) -> Option<&Experience<STATE_SIZE, ACTION_SIZE>> {
+        self.experiences.get(index)
+    }
+
+    /// Adds a transition, replacing the oldest one if the buffer is full. Returns the index the
+    /// transition is stored at.
+    pub fn push(&mut self, experience: Experience<STATE_SIZE, ACTION_SIZE>) -> usize {
+        if self.experiences.len() < self.capacity {
+            self.experiences.push(experience);
+            self.experiences.len() - 1
+        } else {
+            let index = self.oldest;
+            self.experiences[index] = experience;
+            self.oldest = (self.oldest + 1) % self.capacity;
+            index
+        }
+    }
+
+    /// Samples `n` transitions uniformly at random, with replacement. Returns no transitions if
+    /// the buffer is empty.
+    pub fn sample<R: Rng>(
+        &self,
+        n: usize,
+        rng: &mut R,
+    ) -> Vec<Experience<STATE_SIZE, ACTION_SIZE>> {
+        if self.experiences.is_empty() {
+            return Vec::new();
+        }
+        (0..n)
+            .map(|_| self.experiences[rng.gen_range(0..self.experiences.len())])
+            .collect()
+    }
+}
+
+/// A binary tree over `capacity` priorities, where every node holds the sum of the priorities
+/// below it. This allows both updating a priority and finding the transition at a point in the
+/// cumulative priorities in `O(log n)`.
+struct SumTree {
+    capacity: usize,
+    /// The nodes of the tree: the root is at index `1`, the children of node `i` are at `2 * i`
+    /// and `2 * i + 1`, and the priorities themselves are at `capacity..2 * capacity`.
+    nodes: Vec<f64>,
+}
+
+impl SumTree {
+    fn new(capacity: usize) -> SumTree {
+        SumTree {
+            capacity,
+            nodes: vec![0.0; 2 * capacity],
+        }
+    }
+
+    fn total(&self) -> f64 {
+        self.nodes[1]
+    }
+
+    fn get(&self, index: usize) -> f64 {
+        self.nodes[self.capacity + index]
+    }
+
+    fn set(&mut self, index: usize, priority: f64) {
+        let mut node = self.capacity + index;
+        self.nodes[node] = priority;
+        while node > 1 {
+            node /= 2;
+            self.nodes[node] = self.nodes[2 * node] + self.nodes[2 * node + 1];
+        }
+    }
+
+    /// Returns the index of the priority in which the cumulative sum of priorities reaches
+    /// `value`.
+    fn find(&self, mut value: f64) -> usize {
+        let mut node = 1;
+        while node < self.capacity {
+            let left = 2 * node;
+            if value < self.nodes[left] || self.nodes[left + 1] == 0.0 {
+                node = left;
+            } else {
+                value -= self.nodes[left];
+                node = left + 1;
+            }
+        }
+        node - self.capacity
+    }
+}
+
+/// A transition sampled from a [PrioritizedReplayBuffer].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrioritizedSample<const STATE_SIZE: usize, const ACTION_SIZE: usize> {
+    /// The index of the transition in the buffer, for updating its priority.
+    pub index: usize,
+    /// The sampled transition.
+    pub experience: Experience<STATE_SIZE, ACTION_SIZE>,
+    /// The importance-sampling weight of the transition, which corrects for it being sampled
+    /// more or less often than under uniform sampling. The largest weight in a minibatch is `1`.
+    pub weight: f32,
+}
+
+/// A [ReplayBuffer] which samples transitions with a probability proportional to
+/// `priority^alpha`, where the priority of a transition is the absolute value of its last TD
+/// error. New transitions get the highest priority seen so far, so that they are sampled at least
+/// once soon.
+///
+/// With `alpha` `0`, all transitions are sampled uniformly, and all weights are `1`.
+///
+/// # Example
+///
+/// ```
+/// use rurel::dqn::replay::{Experience, PrioritizedReplayBuffer};
+///
+/// let mut buffer = PrioritizedReplayBuffer::new(100, 0.6);
+/// for i in 0..10 {
+///     let state = [i as f32];
+///     let experience = Experience {
+///         state,
+///         action: 0,
+///         reward: 0.0,
+///         next_state: state,
+///         next_legal: [true],
+///         done: false,
+///     };
+///     let index = buffer.push(experience);
+///     // Transition 3 surprised the network much more than the others
+///     buffer.update_priority(index, if i == 3 { 10.0 } else { 0.1 });
+/// }
+///
+/// let samples = buffer.sample(1000, 0.4, &mut rand::thread_rng());
+/// let surprising = samples.iter().filter(|s| s.experience.state == [3.0]).count();
+/// // Its probability is 10^0.6 / (10^0.6 + 9 * 0.1^0.6), about 0.6, instead of 0.1
+/// assert!(surprising > 400);
+/// // and it has the smallest importance-sampling weight
+/// assert!(samples.iter().all(|s| s.weight <= 1.0));
+/// let weight_of = |i: f32| samples.iter().find(|s| s.experience.state == [i]).unwrap().weight;
+/// assert!(weight_of(3.0) < weight_of(0.0));
+/// ```
+pub struct PrioritizedReplayBuffer<const STATE_SIZE: usize, const ACTION_SIZE: usize> {
+    buffer: ReplayBuffer<STATE_SIZE, ACTION_SIZE>,
+    priorities: SumTree,
+    alpha: f64,
+    /// The highest priority seen so far, which new transitions get.
+    max_priority: f64,
+}
+
+impl<const STATE_SIZE: usize, const ACTION_SIZE: usize>
+    PrioritizedReplayBuffer<STATE_SIZE, ACTION_SIZE>
+{
+    /// The amount added to every priority, so that transitions without TD error are still
+    /// sampled.
+    const EPSILON: f64 = 1e-6;
+
+    /// Constructs an empty buffer which holds up to `capacity` transitions, and prioritizes them
+    /// with exponent `alpha`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`, or `alpha` is negative.
+    pub fn new(capacity: usize, alpha: f64) -> PrioritizedReplayBuffer<STATE_SIZE, ACTION_SIZE> {
+        assert!(alpha >= 0.0, "alpha must not be negative");
+        PrioritizedReplayBuffer {
+            buffer: ReplayBuffer::new(capacity),
+            priorities: SumTree::new(capacity),
+            alpha,
+            max_priority: 1.0,
+        }
+    }
+
+    /// Returns the maximum number of transitions in the buffer.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Returns the exponent of the priorities.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Returns the number of transitions in the buffer.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns whether the buffer holds no transitions.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Adds a transition with the highest priority seen so far, replacing the oldest one if the
+    /// buffer is full. Returns the index the transition is stored at.
+    pub fn push(&mut self, experience: Experience<STATE_SIZE, ACTION_SIZE>) -> usize {
+        let index = self.buffer.push(experience);
+        self.priorities
+            .set(index, self.max_priority.powf(self.alpha));
+        index
+    }
+
+    /// Sets the priority of the transition at `index` from its latest TD error.
+    pub fn update_priority(&mut self, index: usize, td_error: f64) {
+        let priority = td_error.abs() + Self::EPSILON;
+        self.max_priority = self.max_priority.max(priority);
+        self.priorities.set(index, priority.powf(self.alpha));
+    }
+
+    /// Samples `n` transitions with a probability proportional to their priority, with
+    /// replacement, and computes their importance-sampling weights with exponent `beta`. Returns
+    /// no transitions if the buffer is empty.
+    ///
+    /// The cumulative priorities are split into `n` equal segments, and one transition is sampled
+    /// from every segment.
+    pub fn sample<R: Rng>(
+        &self,
+        n: usize,
+        beta: f64,
+        rng: &mut R,
+    ) -> Vec<PrioritizedSample<STATE_SIZE, ACTION_SIZE>> {
+        let total = self.priorities.total();
+        if self.buffer.is_empty() || total <= 0.0 {
+            return Vec::new();
+        }
+        let segment = total / n as f64;
+        let len = self.buffer.len() as f64;
+        let mut samples: Vec<(usize, f64)> = (0..n)
+            .map(|i| {
+                let value = segment * (i as f64 + rng.gen::<f64>());
+                let index = self.priorities.find(value.min(total)).min(self.len() - 1);
+                let probability = self.priorities.get(index) / total;
+                (index, (len * probability).powf(-beta))
+            })
+            .collect();
+        let max_weight = samples.iter().map(|&(_, w)| w).fold(0.0, f64::max);
+        for (_, weight) in &mut samples {
+            *weight /= max_weight;
+        }
+        samples
+            .into_iter()
+            .map(|(index, weight)| PrioritizedSample {
+                index,
+                experience: *self.buffer.get(index).unwrap(),
+                weight: weight as f32,
+            })
+            .collect()
+    }
+}
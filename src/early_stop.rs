@@ -0,0 +1,154 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for stopping training once periodic greedy evaluations stop improving.
+
+use crate::EvalStats;
+
+/// The settings of training that stops early, as done by
+/// [AgentTrainer::train_with_early_stopping()](crate::AgentTrainer::train_with_early_stopping):
+/// every `every` episodes, what was learned so far is evaluated with `rollouts` greedy episodes,
+/// and training stops once the mean total reward of an evaluation hasn't beaten the best one by
+/// more than the [minimum improvement](EarlyStopping::with_min_delta) for `patience` evaluations
+/// in a row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EarlyStopping {
+    every: usize,
+    rollouts: usize,
+    patience: usize,
+    min_delta: f64,
+}
+
+impl EarlyStopping {
+    /// Creates the settings of training which is evaluated with `rollouts` greedy episodes after
+    /// every `every` episodes, and stops after `patience` evaluations in a row without
+    /// improvement.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every`, `rollouts` or `patience` is `0`.
+    pub fn new(every: usize, rollouts: usize, patience: usize) -> EarlyStopping {
+        assert!(every > 0, "evaluations must be at least one episode apart");
+        assert!(rollouts > 0, "evaluations must play at least one episode");
+        assert!(patience > 0, "the patience must be at least one evaluation");
+        EarlyStopping {
+            every,
+            rollouts,
+            patience,
+            min_delta: 0.0,
+        }
+    }
+
+    /// Sets by how much the mean reward of an evaluation has to beat the best one to count as an
+    /// improvement, which is `0` by default, so that any improvement counts. A positive value
+    /// ignores the small improvements of evaluations in stochastic environments, which may only
+    /// be noise.
+    pub fn with_min_delta(mut self, min_delta: f64) -> Self {
+        self.min_delta = min_delta;
+        self
+    }
+
+    /// Returns the number of episodes between evaluations.
+    pub fn every(&self) -> usize {
+        self.every
+    }
+
+    /// Returns the number of greedy episodes every evaluation plays.
+    pub fn rollouts(&self) -> usize {
+        self.rollouts
+    }
+}
+
+/// What happened during training that stops early, as returned by
+/// [AgentTrainer::train_with_early_stopping()](crate::AgentTrainer::train_with_early_stopping).
+#[derive(Debug, Clone, PartialEq)]
+pub struct EarlyStoppingReport {
+    /// The number of episodes trained.
+    pub episodes: usize,
+    /// Whether training stopped because the evaluations stopped improving, rather than after
+    /// the largest number of episodes.
+    pub stopped_early: bool,
+    /// The number of episodes after which the best evaluation was made, whose values the trainer
+    /// is left with, or `None` if there was no evaluation.
+    pub best_episode: Option<usize>,
+    /// The statistics of the best evaluation, or `None` if there was none.
+    pub best: Option<EvalStats>,
+    /// The statistics of every evaluation, in order.
+    pub evaluations: Vec<EvalStats>,
+}
+
+/// Follows the evaluations of training that stops early, and tells what to do after each.
+pub(crate) struct Plateau<'e> {
+    early_stopping: &'e EarlyStopping,
+    best_reward: Option<f64>,
+    /// The number of evaluations in a row without improvement.
+    stale: usize,
+    report: EarlyStoppingReport,
+}
+
+/// What to do after an evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Check {
+    /// The evaluation is the best so far, so its values should be kept.
+    Improved,
+    /// The evaluation didn't improve, but training goes on.
+    Stale,
+    /// The evaluations stopped improving, so training should stop.
+    Stop,
+}
+
+impl<'e> Plateau<'e> {
+    /// Starts following the evaluations of training with `early_stopping`.
+    pub(crate) fn new(early_stopping: &'e EarlyStopping) -> Plateau<'e> {
+        Plateau {
+            early_stopping,
+            best_reward: None,
+            stale: 0,
+            report: EarlyStoppingReport {
+                episodes: 0,
+                stopped_early: false,
+                best_episode: None,
+                best: None,
+                evaluations: Vec::new(),
+            },
+        }
+    }
+
+    /// Counts an episode of training, and returns whether it is followed by an evaluation.
+    pub(crate) fn trained(&mut self) -> bool {
+        self.report.episodes += 1;
+        self.report
+            .episodes
+            .is_multiple_of(self.early_stopping.every)
+    }
+
+    /// Records the evaluation after the episodes trained so far.
+    pub(crate) fn evaluated(&mut self, stats: EvalStats) -> Check {
+        let improved = self
+            .best_reward
+            .is_none_or(|best| stats.mean_reward > best + self.early_stopping.min_delta);
+        let check = if improved {
+            self.best_reward = Some(stats.mean_reward);
+            self.report.best_episode = Some(self.report.episodes);
+            self.report.best = Some(stats.clone());
+            self.stale = 0;
+            Check::Improved
+        } else {
+            self.stale += 1;
+            if self.stale >= self.early_stopping.patience {
+                self.report.stopped_early = true;
+                Check::Stop
+            } else {
+                Check::Stale
+            }
+        };
+        self.report.evaluations.push(stats);
+        check
+    }
+
+    /// Returns what happened during training.
+    pub(crate) fn into_report(self) -> EarlyStoppingReport {
+        self.report
+    }
+}
@@ -0,0 +1,115 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The error type of the fallible operations of this crate.
+
+use std::fmt::{self, Display};
+use std::io;
+
+/// An error from one of the fallible operations of this crate, such as saving or loading learned
+/// values.
+#[derive(Debug)]
+pub enum RurelError {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// A file doesn't contain what was expected: it is corrupt, wasn't written by this crate, or
+    /// holds values of other types or a network of another architecture.
+    InvalidFormat(String),
+    /// A file was written in a newer version of its format than this version of the crate can
+    /// read, such as by a newer version of the crate.
+    UnsupportedVersion {
+        /// The format version of the file.
+        found: u32,
+        /// The latest format version this version of the crate reads.
+        supported: u32,
+    },
+    /// A state has no actions to take.
+    NoActions,
+    /// No values were learned for a state, so there is no best action to take in it.
+    UnknownState,
+    /// A state was encoded into another number of values than the network takes.
+    EncodingLength {
+        /// The number of values the network takes.
+        expected: usize,
+        /// The number of values of the encoding.
+        found: usize,
+    },
+    /// The device of a network failed, such as by running out of memory while reading its
+    /// weights.
+    Device(String),
+}
+
+impl Display for RurelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RurelError::Io(e) => write!(f, "I/O error: {}", e),
+            RurelError::InvalidFormat(msg) => write!(f, "invalid file: {}", msg),
+            RurelError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "the file has format version {}, but at most version {} is supported",
+                found, supported
+            ),
+            RurelError::NoActions => f.write_str("the state has no actions"),
+            RurelError::UnknownState => f.write_str("no values were learned for the state"),
+            RurelError::EncodingLength { expected, found } => write!(
+                f,
+                "the state was encoded into {} values, but {} were expected",
+                found, expected
+            ),
+            RurelError::Device(msg) => write!(f, "device error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RurelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RurelError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RurelError {
+    fn from(e: io::Error) -> Self {
+        RurelError::Io(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for RurelError {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            RurelError::Io(e.into())
+        } else {
+            RurelError::InvalidFormat(e.to_string())
+        }
+    }
+}
+
+#[cfg(feature = "save")]
+impl From<dfdx::tensor::safetensors::Error> for RurelError {
+    fn from(e: dfdx::tensor::safetensors::Error) -> Self {
+        use dfdx::tensor::safetensors::Error;
+
+        match e {
+            Error::IoError(e) => RurelError::Io(e),
+            Error::SafeTensorError(e) => e.into(),
+            Error::MismatchedDimension((found, expected)) => RurelError::InvalidFormat(format!(
+                "expected a tensor of shape {:?}, found one of shape {:?}",
+                expected, found
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "save")]
+impl From<safetensors::SafeTensorError> for RurelError {
+    fn from(e: safetensors::SafeTensorError) -> Self {
+        match e {
+            safetensors::SafeTensorError::IoError(e) => RurelError::Io(e),
+            e => RurelError::InvalidFormat(e.to_string()),
+        }
+    }
+}
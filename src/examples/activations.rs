@@ -0,0 +1,132 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg_attr(not(feature = "dqn"), allow(dead_code))]
+
+#[cfg(feature = "dqn")]
+use dfdx::prelude::*;
+#[cfg(feature = "dqn")]
+use rurel::dqn::{DQNAgentTrainer, QNetwork, QNetworkModel};
+use rurel::mdp::{Agent, State};
+
+/// The largest distance of the cart from the centre.
+const MAX_POSITION: i32 = 20;
+/// The largest speed of the cart.
+const MAX_VELOCITY: i32 = 4;
+
+/// A cart on a track, which has to be pushed to the centre of the track and kept there. The
+/// value of a state changes smoothly with the position and velocity of the cart.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Cart {
+    position: i32,
+    velocity: i32,
+}
+
+/// Pushing the cart changes its velocity by `-1`, `0` or `1`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Push(i32);
+
+impl State for Cart {
+    type A = Push;
+
+    fn reward(&self) -> f64 {
+        -(self.position as f64 / MAX_POSITION as f64).powi(2)
+    }
+
+    fn actions(&self) -> Vec<Push> {
+        vec![Push(-1), Push(0), Push(1)]
+    }
+}
+
+// The constant inputs keep the position and velocity apart when the network normalizes its
+// inputs
+impl From<Cart> for [f32; 4] {
+    fn from(cart: Cart) -> Self {
+        [
+            cart.position as f32 / MAX_POSITION as f32,
+            cart.velocity as f32 / MAX_VELOCITY as f32,
+            1.0,
+            -1.0,
+        ]
+    }
+}
+
+impl From<Push> for [f32; 3] {
+    fn from(push: Push) -> Self {
+        let mut v = [0.0; 3];
+        v[(push.0 + 1) as usize] = 1.0;
+        v
+    }
+}
+
+impl From<[f32; 3]> for Push {
+    fn from(v: [f32; 3]) -> Self {
+        let best = (0..3).max_by(|&a, &b| v[a].total_cmp(&v[b])).unwrap();
+        Push(best as i32 - 1)
+    }
+}
+
+struct Track(Cart);
+
+impl Agent<Cart> for Track {
+    fn current_state(&self) -> &Cart {
+        &self.0
+    }
+
+    fn take_action(&mut self, push: &Push) {
+        let velocity = (self.0.velocity + push.0).clamp(-MAX_VELOCITY, MAX_VELOCITY);
+        let position = (self.0.position + velocity).clamp(-MAX_POSITION, MAX_POSITION);
+        self.0 = Cart { position, velocity };
+    }
+}
+
+/// Returns a cart at rest at a random position.
+fn random_cart() -> Track {
+    use rand::Rng;
+
+    let position = rand::thread_rng().gen_range(-MAX_POSITION..=MAX_POSITION);
+    Track(Cart {
+        position,
+        velocity: 0,
+    })
+}
+
+/// Trains a network of architecture `N` on the track, and returns the mean and standard
+/// deviation of the total rewards of greedy episodes from random positions.
+#[cfg(feature = "dqn")]
+fn train_and_evaluate<N>() -> (f64, f64)
+where
+    N: BuildOnDevice<AutoDevice, f32>,
+    N::Built: QNetworkModel<4, 3, AutoDevice>,
+{
+    use rurel::strategy::explore::EpsilonGreedy;
+    use rurel::strategy::terminate::FixedIterations;
+
+    let mut trainer =
+        DQNAgentTrainer::<Cart, 4, 3, 32, AutoDevice, N>::new(0.9, 1e-2).with_double_dqn(true);
+    let exploration = EpsilonGreedy::new(0.2);
+    for _ in 0..50 {
+        trainer.train(
+            &mut random_cart(),
+            &mut FixedIterations::new(100),
+            &exploration,
+        );
+    }
+    let stats = trainer.evaluate(random_cart, || FixedIterations::new(50), 50);
+    (stats.mean_reward, stats.std_reward)
+}
+
+#[cfg(feature = "dqn")]
+fn main() {
+    println!("activation  mean reward  std. dev.");
+    let (mean, std) = train_and_evaluate::<QNetwork<4, 3, 32, ReLU>>();
+    println!("{:>10}  {:>11.3}  {:>9.3}", "ReLU", mean, std);
+    let (mean, std) = train_and_evaluate::<QNetwork<4, 3, 32, Tanh>>();
+    println!("{:>10}  {:>11.3}  {:>9.3}", "Tanh", mean, std);
+}
+
+#[cfg(not(feature = "dqn"))]
+fn main() {
+    panic!("Use the 'dqn' feature to run this example");
+}
@@ -0,0 +1,112 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A k-armed bandit: every arm pays a noisy reward around its own hidden mean. Compares how much
+//! reward random exploration, epsilon-greedy and UCB1 lose, compared to always pulling the best
+//! arm (their cumulative regret).
+
+use std::hash::{Hash, Hasher};
+
+use rand::Rng;
+use rurel::mdp::{Agent, State};
+use rurel::strategy::explore::{EpsilonGreedy, ExplorationStrategy, RandomExploration, UCB1};
+use rurel::strategy::learn::QLearning;
+use rurel::strategy::terminate::FixedIterations;
+use rurel::AgentTrainer;
+
+/// The hidden mean reward of every arm.
+const MEANS: [f64; 5] = [0.2, 0.5, 1.0, 0.8, 0.1];
+const PULLS: u32 = 1000;
+const RUNS: u32 = 100;
+
+/// The bandit has a single state. It carries the reward of the last pull, which is ignored when
+/// comparing states, so that all values are learned for that one state.
+#[derive(Clone)]
+struct Bandit {
+    last_reward: f64,
+}
+
+impl PartialEq for Bandit {
+    fn eq(&self, _: &Bandit) -> bool {
+        true
+    }
+}
+
+impl Eq for Bandit {}
+
+impl Hash for Bandit {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl State for Bandit {
+    /// The arm to pull.
+    type A = usize;
+
+    fn reward(&self) -> f64 {
+        self.last_reward
+    }
+
+    fn actions(&self) -> Vec<usize> {
+        (0..MEANS.len()).collect()
+    }
+}
+
+/// Pulls arms, and keeps track of the regret of the pulls.
+struct Gambler {
+    state: Bandit,
+    regret: f64,
+}
+
+impl Agent<Bandit> for Gambler {
+    fn current_state(&self) -> &Bandit {
+        &self.state
+    }
+
+    fn take_action(&mut self, arm: &usize) {
+        let best = MEANS.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        self.regret += best - MEANS[*arm];
+        let noise = rand::thread_rng().gen_range(-1.0..1.0);
+        self.state = Bandit {
+            last_reward: MEANS[*arm] + noise,
+        };
+    }
+}
+
+/// Returns the cumulative regret of `PULLS` pulls, averaged over `RUNS` runs which each start
+/// from scratch with the strategy returned by `exploration`.
+fn average_regret<E: ExplorationStrategy<Bandit>>(exploration: impl Fn() -> E) -> f64 {
+    let mut total = 0.0;
+    for _ in 0..RUNS {
+        let mut trainer = AgentTrainer::new();
+        let mut gambler = Gambler {
+            state: Bandit { last_reward: 0.0 },
+            regret: 0.0,
+        };
+        // a discount factor of 0 learns the mean reward of every arm
+        trainer.train(
+            &mut gambler,
+            &QLearning::new(0.1, 0.0, 0.0),
+            &mut FixedIterations::new(PULLS - 1),
+            &exploration(),
+        );
+        total += gambler.regret;
+    }
+    total / RUNS as f64
+}
+
+fn main() {
+    println!(
+        "cumulative regret after {} pulls, averaged over {} runs:",
+        PULLS, RUNS
+    );
+    println!(
+        "random:         {:.1}",
+        average_regret(|| RandomExploration)
+    );
+    println!(
+        "epsilon-greedy: {:.1}",
+        average_regret(|| EpsilonGreedy::new(0.1))
+    );
+    println!("UCB1:           {:.1}", average_regret(|| UCB1::new(1.0)));
+}
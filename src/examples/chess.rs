@@ -2,33 +2,24 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+#![cfg_attr(not(feature = "dqn"), allow(dead_code))]
+
 use std::path::PathBuf;
 
+use clap::Parser;
+#[cfg(feature = "dqn")]
+use rurel::dqn::{DQNAgentTrainer, DiscreteActionSpace};
+use rurel::{
+    mdp::{Agent, State},
+    strategy::terminate::TerminationStrategy,
+};
 #[cfg(feature = "dqn")]
-use rurel::dqn::DQNAgentTrainer;
-use rurel::{mdp::{Agent, State}, strategy::terminate::TerminationStrategy};
+use rurel::{Lookahead, RurelError, Search};
 use shakmaty::{Chess, Color, EnPassantMode, Move, Position, Role, Square};
-use clap::Parser;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct ChessState(Chess);
 
-fn u32_to_square(n: u32) -> Square {
-    Square::ALL[n as usize]
-}
-
-fn u32_to_role(n: u32) -> Role {
-    match n {
-        0 => Role::Pawn,
-        1 => Role::Knight,
-        2 => Role::Bishop,
-        3 => Role::Rook,
-        4 => Role::Queen,
-        5 => Role::King,
-        _ => panic!("Invalid role {}", n),
-    }
-}
-
 /// Split a u64 into two u32s.
 fn split_u64(n: u64) -> (u32, u32) {
     ((n & 0xFFFF_FFFF) as u32, (n >> 32) as u32)
@@ -36,7 +27,7 @@ fn split_u64(n: u64) -> (u32, u32) {
 
 macro_rules! generate_from_chess_state {
     (
-        $val:ident, 
+        $val:ident,
         $array:ident,
         $(($field:ident, $index:expr)),*
     ) => {
@@ -56,7 +47,8 @@ impl From<ChessState> for [f32; 20] {
         let board = val.0.board();
         // fill the first 16 elements with the bitboards
         generate_from_chess_state!(
-            board, array,
+            board,
+            array,
             (pawns, 0),
             (knights, 1),
             (bishops, 2),
@@ -67,11 +59,16 @@ impl From<ChessState> for [f32; 20] {
             (black, 7)
         );
         // then the turn
-        array[16] = if val.0.turn() == Color::White { 0.0 } else { 1.0 };
+        array[16] = if val.0.turn() == Color::White {
+            0.0
+        } else {
+            1.0
+        };
         // then halfmove clock
         array[17] = val.0.halfmoves() as f32;
         // then castling rights
-        let (castling_a, castling_b) = split_u64(val.0.into_setup(EnPassantMode::Legal).castling_rights.0);
+        let (castling_a, castling_b) =
+            split_u64(val.0.into_setup(EnPassantMode::Legal).castling_rights.0);
         array[18] = castling_a as f32;
         array[19] = castling_b as f32;
 
@@ -79,124 +76,51 @@ impl From<ChessState> for [f32; 20] {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-struct ChessAction(Move);
-
-// Into float array has to be implemented for the action,
-// so that the DQN can use it.
-impl From<ChessAction> for [f32; 6] {
-    fn from(val: ChessAction) -> Self {
-        match val.0 {
-            Move::Normal {
-                role,
-                from,
-                capture,
-                to,
-                promotion,
-            } => {
-                [
-                    0.0, // Normal
-                    role as u32 as f32,
-                    from as u32 as f32,
-                    match capture {
-                        Some(role) => role as u32 as f32 + 1.0,
-                        None => 0.0,
-                    },
-                    to as u32 as f32,
-                    match promotion {
-                        Some(role) => role as u32 as f32 + 1.0,
-                        None => 0.0,
-                    },
-                ]
-            }
-            Move::EnPassant { from, to } => {
-                [
-                    1.0, // En Passant
-                    0.0,
-                    from as u32 as f32,
-                    0.0,
-                    to as u32 as f32,
-                    0.0,
-                ]
-            }
-            Move::Castle { king, rook } => {
-                [
-                    2.0, // Castle
-                    0.0,
-                    king as u32 as f32,
-                    0.0,
-                    rook as u32 as f32,
-                    0.0,
-                ]
-            }
-            Move::Put { role, to } => {
-                [
-                    3.0, // Put
-                    role as u32 as f32,
-                    0.0,
-                    0.0,
-                    to as u32 as f32,
-                    0.0,
-                ]
-            }
+/// A move, by the squares it moves from and to, which is all it takes to tell the legal moves of
+/// a position apart, as pawns always promote to queens. Castling moves the king to the square of
+/// the rook.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+struct ChessAction {
+    from: Square,
+    to: Square,
+}
+
+// The DQN outputs the value of every move by index, and the moves are mapped to indices by
+// their squares, so that nothing is lost
+#[cfg(feature = "dqn")]
+impl DiscreteActionSpace<4096> for ChessAction {
+    fn index(&self) -> usize {
+        usize::from(self.from) * 64 + usize::from(self.to)
+    }
+
+    fn from_index(index: usize) -> ChessAction {
+        ChessAction {
+            from: Square::ALL[index / 64],
+            to: Square::ALL[index % 64],
         }
     }
 }
 
-// From float array has to be implemented for the action,
-// because output of the DQN is a float array like [0.1, 0.2, 0.1, 0.1]
-impl From<[f32; 6]> for ChessAction {
-    fn from(v: [f32; 6]) -> Self {
-        println!("{:?}", v);
-        match v[0] as u32 {
-            0 => {
-                let role = v[1] as u32;
-                let from = v[2] as u32;
-                let capture = if v[3] as u32 == 0 {
-                    None
-                } else {
-                    Some(v[3] as u32 - 1)
-                };
-                let to = v[4] as u32;
-                let promotion = if v[5] as u32 == 0 {
-                    None
-                } else {
-                    Some(v[5] as u32 - 1)
-                };
-                ChessAction(Move::Normal {
-                    role: u32_to_role(role),
-                    from: u32_to_square(from),
-                    capture: capture.map(|x| u32_to_role(x as u32 - 1)),
-                    to: to.try_into().unwrap(),
-                    promotion: promotion.map(|x| u32_to_role(x as u32 - 1)),
-                })
-            }
-            1 => {
-                let from = v[2] as u32;
-                let to = v[4] as u32;
-                ChessAction(Move::EnPassant {
-                    from: u32_to_square(from),
-                    to: u32_to_square(to),
-                })
-            }
-            2 => {
-                let king = v[2] as u32;
-                let rook = v[4] as u32;
-                ChessAction(Move::Castle {
-                    king: u32_to_square(king),
-                    rook: u32_to_square(rook),
-                })
-            }
-            3 => {
-                let role = v[1] as u32;
-                let to = v[4] as u32;
-                ChessAction(Move::Put {
-                    role: u32_to_role(role),
-                    to: u32_to_square(to),
-                })
-            }
-            _ => panic!("Invalid action"),
+impl ChessState {
+    /// Returns the legal move `action` stands for, if there is one.
+    fn find(&self, action: &ChessAction) -> Option<Move> {
+        self.0
+            .legal_moves()
+            .into_iter()
+            .find(|m| ChessAction::of(m) == Some(*action))
+    }
+}
+
+impl ChessAction {
+    /// Returns the action of `m`, or `None` if it promotes to anything but a queen.
+    fn of(m: &Move) -> Option<ChessAction> {
+        if m.promotion().is_some_and(|role| role != Role::Queen) {
+            return None;
         }
+        Some(ChessAction {
+            from: m.from()?,
+            to: m.to(),
+        })
     }
 }
 
@@ -213,17 +137,26 @@ impl State for ChessState {
                         -20.0
                     }
                 }
-                shakmaty::Outcome::Draw { .. } => 10.0,
+                shakmaty::Outcome::Draw => 10.0,
             },
             None => 0.0,
         }
     }
 
     fn actions(&self) -> Vec<ChessAction> {
-        self.0.legal_moves().iter().cloned().map(ChessAction).collect()
+        self.0
+            .legal_moves()
+            .iter()
+            .filter_map(ChessAction::of)
+            .collect()
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.0.outcome().is_some()
     }
 }
 
+#[derive(Clone)]
 struct ChessAgent(ChessState);
 
 impl Agent<ChessState> for ChessAgent {
@@ -232,15 +165,18 @@ impl Agent<ChessState> for ChessAgent {
     }
 
     fn take_action(&mut self, action: &ChessAction) {
-        self.0 = ChessState(self.0.0.clone().play(&action.0).unwrap());
+        let m = self.0.find(action).expect("the move is legal");
+        self.0 = ChessState(self.0 .0.clone().play(&m).unwrap());
     }
 }
 
+/// Cuts games off after 100 halfmoves without a capture or pawn move; finished games end on
+/// their own, as their states are terminal.
 struct ChessTermination;
 
 impl TerminationStrategy<ChessState> for ChessTermination {
     fn should_stop(&mut self, state: &ChessState) -> bool {
-        state.0.outcome().is_some() || state.0.halfmoves() >= 100
+        state.0.halfmoves() >= 100
     }
 }
 
@@ -252,34 +188,47 @@ struct Cli {
     /// The number of trials to run.
     #[arg(short, long, default_value = "10000")]
     trials: i32,
+
+    /// The path to save a checkpoint to every 100 trials. If it exists, training resumes from
+    /// it.
+    #[arg(short, long)]
+    checkpoint: Option<PathBuf>,
 }
 
 #[cfg(feature = "dqn")]
-fn main() {
+fn main() -> Result<(), RurelError> {
     use indicatif::ProgressIterator;
-    use rurel::strategy::explore::RandomExploration;
+    use rurel::strategy::explore::{DecayingEpsilonGreedy, EpsilonDecay};
 
     let cli = Cli::parse();
 
     // check if file exists; if so, load the model
     let trainer = if cli.file.exists() {
-        let mut trainer = DQNAgentTrainer::<ChessState, 20, 6, 64>::new(0.995, 1e-3);
-        trainer.load(&cli.file.to_str().unwrap()).unwrap();
+        let mut trainer = DQNAgentTrainer::<ChessState, 20, 4096, 64>::new(0.995, 1e-3);
+        trainer.load(cli.file.to_str().unwrap())?;
         trainer
     } else {
         let initial_state = ChessState(Chess::default());
 
-        let mut trainer = DQNAgentTrainer::<ChessState, 20, 6, 64>::new(0.995, 1e-3);
-        for _ in (0..cli.trials).progress() {
+        // only bootstrap from the legal moves of the next position, and keep the legal moves
+        // of recent positions, which are looked up again when the next move is picked
+        let mut trainer = DQNAgentTrainer::<ChessState, 20, 4096, 64>::new(0.995, 1e-3)
+            .with_action_masking(true)
+            .with_action_cache(1_000);
+        // explore less and less, as the network learns which moves are good
+        let exploration = DecayingEpsilonGreedy::new(1.0, 0.1, EpsilonDecay::Exponential(0.99995));
+        if let Some(checkpoint) = cli.checkpoint.as_ref().and_then(|path| path.to_str()) {
+            if std::path::Path::new(checkpoint).exists() {
+                trainer.load_checkpoint(checkpoint, &exploration)?;
+            }
+            trainer = trainer.with_checkpoints(100, checkpoint);
+        }
+        for _ in (trainer.episodes()..cli.trials as usize).progress() {
             let mut agent = ChessAgent(initial_state.clone());
-            trainer.train(
-                &mut agent,
-                &mut ChessTermination,
-                &RandomExploration,
-            );
+            trainer.train(&mut agent, &mut ChessTermination, &exploration);
         }
 
-        trainer.save(&cli.file.to_str().unwrap()).unwrap();
+        trainer.save(cli.file.to_str().unwrap())?;
 
         trainer
     };
@@ -298,9 +247,7 @@ fn main() {
         let action = if state.0.turn() == Color::White {
             let mut input = String::new();
             std::io::stdin().read_line(&mut input).unwrap();
-            let action = legal_moves
-                .iter()
-                .find(|m| m.to_string() == input.trim());
+            let action = legal_moves.iter().find(|m| m.to_string() == input.trim());
 
             let action = match action {
                 Some(action) => action,
@@ -313,23 +260,28 @@ fn main() {
                 }
             };
 
-            ChessAction(action.clone())
+            match ChessAction::of(action) {
+                Some(action) => action,
+                None => {
+                    println!("Pawns can only promote to queens");
+                    continue;
+                }
+            }
         } else {
-            let action = trainer
-                .best_action(&state)
-                .expect("No legal moves available");
-            
-            action
+            // look two moves ahead, the agent's and the reply, scoring the positions after
+            // them with the network
+            let lookahead = Lookahead::new(2, 0.995, Search::Minimax).with_node_budget(2_000);
+            trainer
+                .plan(&ChessAgent(state.clone()), &lookahead)
+                .ok_or(RurelError::NoActions)?
         };
 
-        println!(
-            "{} played: {}",
-            state.0.turn(),
-            action.0
-        );
-        state = ChessState(state.0.clone().play(&action.0).unwrap());
+        let m = state.find(&action).expect("the move is legal");
+        println!("{} played: {}", state.0.turn(), m);
+        state = ChessState(state.0.clone().play(&m).unwrap());
     }
 
+    Ok(())
 }
 
 #[cfg(not(feature = "dqn"))]
@@ -0,0 +1,165 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A walker learns to reach the corner of a grid which grows from 3x3 to 8x8 over the stages of
+//! a curriculum, and prints what every stage took and the policy it learned for the largest grid.
+
+use rand::Rng;
+use rurel::mdp::{Agent, State};
+use rurel::strategy::explore::EpsilonGreedy;
+use rurel::strategy::learn::QLearning;
+use rurel::strategy::terminate::FixedIterations;
+use rurel::{AgentTrainer, Curriculum, Stage};
+
+/// The walker only sees how far it is from the goal, rather than where it is, so that what it
+/// learned on a small grid still holds on the larger ones.
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct ToGoal {
+    dx: i32,
+    dy: i32,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+enum Move {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Move {
+    fn arrow(self) -> char {
+        match self {
+            Move::Up => '↑',
+            Move::Down => '↓',
+            Move::Left => '←',
+            Move::Right => '→',
+        }
+    }
+}
+
+impl State for ToGoal {
+    type A = Move;
+
+    fn reward(&self) -> f64 {
+        if self.is_terminal() {
+            10.0
+        } else {
+            // every step costs a little, so short paths are better
+            -0.1
+        }
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        vec![Move::Up, Move::Down, Move::Left, Move::Right]
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.dx == 0 && self.dy == 0
+    }
+}
+
+/// A walker on a grid of `size` by `size` cells, whose goal is the bottom right corner.
+struct Walker {
+    size: i32,
+    x: i32,
+    y: i32,
+    state: ToGoal,
+}
+
+impl Walker {
+    fn new(size: i32, x: i32, y: i32) -> Walker {
+        Walker {
+            size,
+            x,
+            y,
+            state: ToGoal {
+                dx: size - 1 - x,
+                dy: size - 1 - y,
+            },
+        }
+    }
+
+    /// Returns a walker on a random cell of the grid other than the goal.
+    fn random(size: i32) -> Walker {
+        loop {
+            let x = rand::thread_rng().gen_range(0..size);
+            let y = rand::thread_rng().gen_range(0..size);
+            if (x, y) != (size - 1, size - 1) {
+                return Walker::new(size, x, y);
+            }
+        }
+    }
+}
+
+impl Agent<ToGoal> for Walker {
+    fn current_state(&self) -> &ToGoal {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &Move) {
+        let (dx, dy) = match action {
+            Move::Up => (0, -1),
+            Move::Down => (0, 1),
+            Move::Left => (-1, 0),
+            Move::Right => (1, 0),
+        };
+        // walking into the edge of the grid keeps the walker in place
+        *self = Walker::new(
+            self.size,
+            (self.x + dx).clamp(0, self.size - 1),
+            (self.y + dy).clamp(0, self.size - 1),
+        );
+    }
+}
+
+fn main() {
+    // every stage is passed once greedy walks from random cells reach the goal about as fast
+    // as they can
+    let curriculum = (3..=8).fold(Curriculum::new(), |curriculum, size| {
+        let threshold = 10.0 - 0.1 * (size - 1) as f64;
+        curriculum.with_stage(
+            Stage::new(5000, move || Walker::random(size)).with_threshold(threshold, 50, 50),
+        )
+    });
+
+    let mut trainer = AgentTrainer::new();
+    let reports = trainer.train_curriculum(
+        curriculum,
+        &QLearning::new(0.5, 0.9, 0.0),
+        || FixedIterations::new(100),
+        &EpsilonGreedy::new(0.3),
+    );
+    for (size, report) in (3..).zip(&reports) {
+        let mean_reward = report
+            .evaluation
+            .as_ref()
+            .map_or(f64::NAN, |e| e.mean_reward);
+        println!(
+            "{size}x{size}: {} episodes, {}, mean reward {mean_reward:.2}",
+            report.episodes,
+            if report.passed {
+                "passed"
+            } else {
+                "not passed"
+            },
+        );
+    }
+
+    // G is the goal of the largest grid
+    let size = 8;
+    for y in 0..size {
+        let row: String = (0..size)
+            .map(|x| {
+                let walker = Walker::new(size, x, y);
+                if walker.state.is_terminal() {
+                    'G'
+                } else {
+                    trainer.best_action(&walker.state).map_or('?', Move::arrow)
+                }
+            })
+            .collect();
+        println!("{}", row);
+    }
+}
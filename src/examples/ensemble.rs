@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg_attr(not(feature = "dqn"), allow(dead_code))]
+
+#[cfg(feature = "dqn")]
+use rurel::dqn::{DQNAgentTrainer, DQNEnsemble};
+use rurel::mdp::{Agent, State};
+
+/// The number of positions on the line.
+const POSITIONS: usize = 21;
+/// The last position the agent can reach during training.
+const TRAINED: i32 = 10;
+
+/// A position on a line, where the agent has to walk to position 5. During training, the agent
+/// never gets past position 10, so the ensemble has to tell which positions it knows about.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Position(i32);
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Move(i32);
+
+impl State for Position {
+    type A = Move;
+
+    fn reward(&self) -> f64 {
+        -((self.0 - 5).abs() as f64)
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        vec![Move(-1), Move(1)]
+    }
+}
+
+// Every position is its own input of the network
+impl From<Position> for [f32; POSITIONS] {
+    fn from(p: Position) -> Self {
+        let mut v = [0.0; POSITIONS];
+        v[p.0 as usize] = 1.0;
+        v
+    }
+}
+
+impl From<Move> for [f32; 2] {
+    fn from(m: Move) -> Self {
+        if m.0 < 0 {
+            [1.0, 0.0]
+        } else {
+            [0.0, 1.0]
+        }
+    }
+}
+
+impl From<[f32; 2]> for Move {
+    fn from(v: [f32; 2]) -> Self {
+        if v[0] > v[1] {
+            Move(-1)
+        } else {
+            Move(1)
+        }
+    }
+}
+
+struct Walker(Position);
+
+impl Agent<Position> for Walker {
+    fn current_state(&self) -> &Position {
+        &self.0
+    }
+
+    fn take_action(&mut self, m: &Move) {
+        self.0 = Position((self.0 .0 + m.0).clamp(0, TRAINED));
+    }
+}
+
+#[cfg(feature = "dqn")]
+fn main() {
+    use rand::Rng;
+    use rurel::strategy::explore::EpsilonGreedy;
+    use rurel::strategy::terminate::FixedIterations;
+
+    let mut ensemble = DQNEnsemble::<Position, POSITIONS, 2, 32>::new(5, 0, |dev| {
+        DQNAgentTrainer::new_on_device(0.9, 1e-2, dev)
+    });
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        ensemble.train(
+            || Walker(Position(rng.gen_range(0..=TRAINED))),
+            || FixedIterations::new(20),
+            &EpsilonGreedy::new(0.2),
+        );
+    }
+
+    // The members agree where they were trained, and disagree where they weren't
+    println!("position  best move  mean value  std. dev.");
+    for x in 0..POSITIONS as i32 {
+        let state = Position(x);
+        let best = ensemble.best_action(&state).unwrap();
+        let value = ensemble.action_values(&state)[&best];
+        println!(
+            "{:>8}  {:>9}  {:>10.3}  {:>9.3}{}",
+            x,
+            best.0,
+            value.mean,
+            value.variance.sqrt(),
+            if x > TRAINED {
+                "  (never trained on)"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+#[cfg(not(feature = "dqn"))]
+fn main() {
+    panic!("Use the 'dqn' feature to run this example");
+}
@@ -0,0 +1,154 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A walker on a small grid learns to reach the goal without falling into any of the pits, and
+//! prints the policy it learned.
+
+use rand::Rng;
+use rurel::mdp::{Agent, State};
+use rurel::strategy::explore::EpsilonGreedy;
+use rurel::strategy::learn::QLearning;
+use rurel::strategy::terminate::TerminationStrategy;
+use rurel::AgentTrainer;
+
+const WIDTH: i32 = 6;
+const HEIGHT: i32 = 4;
+const GOAL: (i32, i32) = (5, 0);
+const PITS: [(i32, i32); 3] = [(2, 1), (3, 1), (3, 3)];
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct Cell {
+    x: i32,
+    y: i32,
+}
+
+impl Cell {
+    fn is_goal(&self) -> bool {
+        (self.x, self.y) == GOAL
+    }
+
+    fn is_pit(&self) -> bool {
+        PITS.contains(&(self.x, self.y))
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+enum Move {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Move {
+    fn arrow(self) -> char {
+        match self {
+            Move::Up => '↑',
+            Move::Down => '↓',
+            Move::Left => '←',
+            Move::Right => '→',
+        }
+    }
+}
+
+impl State for Cell {
+    type A = Move;
+
+    fn reward(&self) -> f64 {
+        if self.is_goal() {
+            10.0
+        } else if self.is_pit() {
+            -10.0
+        } else {
+            // every step costs a little, so short paths are better
+            -0.1
+        }
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        vec![Move::Up, Move::Down, Move::Left, Move::Right]
+    }
+}
+
+struct Walker {
+    state: Cell,
+}
+
+impl Agent<Cell> for Walker {
+    fn current_state(&self) -> &Cell {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &Move) {
+        let (dx, dy) = match action {
+            Move::Up => (0, -1),
+            Move::Down => (0, 1),
+            Move::Left => (-1, 0),
+            Move::Right => (1, 0),
+        };
+        // walking into the edge of the grid keeps the walker in place
+        self.state = Cell {
+            x: (self.state.x + dx).clamp(0, WIDTH - 1),
+            y: (self.state.y + dy).clamp(0, HEIGHT - 1),
+        };
+    }
+}
+
+/// Ends an episode once the walker reaches the goal or falls into a pit, or after 100 steps.
+struct GoalOrPit {
+    steps: u32,
+}
+
+impl TerminationStrategy<Cell> for GoalOrPit {
+    fn should_stop(&mut self, state: &Cell) -> bool {
+        self.steps += 1;
+        state.is_goal() || state.is_pit() || self.steps >= 100
+    }
+}
+
+/// Returns a random cell which is neither the goal nor a pit.
+fn random_start() -> Cell {
+    loop {
+        let cell = Cell {
+            x: rand::thread_rng().gen_range(0..WIDTH),
+            y: rand::thread_rng().gen_range(0..HEIGHT),
+        };
+        if !cell.is_goal() && !cell.is_pit() {
+            return cell;
+        }
+    }
+}
+
+fn main() {
+    let mut trainer = AgentTrainer::new();
+    for _ in 0..10000 {
+        // starting anywhere lets the walker learn what to do in every cell
+        let mut agent = Walker {
+            state: random_start(),
+        };
+        trainer.train(
+            &mut agent,
+            &QLearning::new(0.5, 0.9, 0.0),
+            &mut GoalOrPit { steps: 0 },
+            &EpsilonGreedy::new(0.3),
+        );
+    }
+
+    // G is the goal and X are the pits
+    for y in 0..HEIGHT {
+        let row: String = (0..WIDTH)
+            .map(|x| {
+                let cell = Cell { x, y };
+                if cell.is_goal() {
+                    'G'
+                } else if cell.is_pit() {
+                    'X'
+                } else {
+                    trainer.best_action(&cell).map_or('?', Move::arrow)
+                }
+            })
+            .collect();
+        println!("{}", row);
+    }
+}
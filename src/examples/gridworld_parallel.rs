@@ -0,0 +1,122 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Compares the time it takes to train on a gridworld one episode at a time, and with
+//! `AgentTrainer::train_parallel()`. Run with `--release`; the speedup depends on the number of
+//! cores.
+
+#![cfg_attr(not(feature = "rayon"), allow(dead_code))]
+
+use rurel::mdp::{Agent, State};
+
+const SIZE: i32 = 30;
+const EPISODES: usize = 64;
+const EPISODE_LENGTH: u32 = 5_000;
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct Cell {
+    x: i32,
+    y: i32,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct Move {
+    dx: i32,
+    dy: i32,
+}
+
+impl State for Cell {
+    type A = Move;
+
+    fn reward(&self) -> f64 {
+        if self.x == SIZE - 1 && self.y == SIZE - 1 {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        vec![
+            Move { dx: -1, dy: 0 },
+            Move { dx: 1, dy: 0 },
+            Move { dx: 0, dy: -1 },
+            Move { dx: 0, dy: 1 },
+        ]
+    }
+}
+
+struct Walker {
+    state: Cell,
+}
+
+impl Agent<Cell> for Walker {
+    fn current_state(&self) -> &Cell {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &Move) {
+        self.state = Cell {
+            x: (self.state.x + action.dx).clamp(0, SIZE - 1),
+            y: (self.state.y + action.dy).clamp(0, SIZE - 1),
+        };
+    }
+}
+
+fn walker() -> Walker {
+    Walker {
+        state: Cell { x: 0, y: 0 },
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn main() {
+    use std::time::Instant;
+
+    use rurel::strategy::explore::RandomExploration;
+    use rurel::strategy::learn::QLearning;
+    use rurel::strategy::terminate::FixedIterations;
+    use rurel::AgentTrainer;
+
+    let learning = QLearning::new(0.2, 0.9, 0.0);
+    let workers = rayon::current_num_threads();
+
+    let start = Instant::now();
+    let mut serial = AgentTrainer::new();
+    for _ in 0..EPISODES {
+        serial.train(
+            &mut walker(),
+            &learning,
+            &mut FixedIterations::new(EPISODE_LENGTH),
+            &RandomExploration,
+        );
+    }
+    let serial_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut parallel = AgentTrainer::new();
+    for _ in 0..EPISODES.div_ceil(workers) {
+        let mut agents: Vec<_> = (0..workers).map(|_| walker()).collect();
+        parallel.train_parallel(
+            &mut agents,
+            &learning,
+            &|| FixedIterations::new(EPISODE_LENGTH),
+            &RandomExploration,
+        );
+    }
+    let parallel_time = start.elapsed();
+
+    println!("{} episodes of {} steps", EPISODES, EPISODE_LENGTH);
+    println!("serial:   {:?}", serial_time);
+    println!("parallel: {:?} ({} threads)", parallel_time, workers);
+    println!(
+        "speedup:  {:.2}x",
+        serial_time.as_secs_f64() / parallel_time.as_secs_f64()
+    );
+}
+
+#[cfg(not(feature = "rayon"))]
+fn main() {
+    panic!("Use the 'rayon' feature to run this example");
+}
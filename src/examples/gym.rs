@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use rand::Rng;
+use rurel::mdp::{Environment, GymAgent};
+use rurel::strategy::explore::RandomExploration;
+use rurel::strategy::learn::QLearning;
+use rurel::strategy::terminate::FixedIterations;
+use rurel::AgentTrainer;
+
+/// The number of links of the chain.
+const LINKS: usize = 5;
+/// The probability that the opposite of the chosen action is taken.
+const SLIP: f64 = 0.2;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Action {
+    Forward,
+    Back,
+}
+
+/// The chain of the "NChain" environment of Gym: walking forward along the chain pays nothing
+/// until its last link, where walking forward pays 10, while walking back to the start always
+/// pays 2. The agent sometimes slips and takes the other action.
+struct Chain {
+    link: usize,
+}
+
+impl Environment for Chain {
+    type Obs = usize;
+    type Action = Action;
+
+    fn reset(&mut self) -> usize {
+        self.link = 0;
+        self.link
+    }
+
+    fn step(&mut self, action: &Action) -> (usize, f64, bool) {
+        let slipped = rand::thread_rng().gen_bool(SLIP);
+        let action = match (action, slipped) {
+            (Action::Forward, false) | (Action::Back, true) => Action::Forward,
+            _ => Action::Back,
+        };
+        let reward = match action {
+            Action::Forward if self.link == LINKS - 1 => 10.0,
+            Action::Forward => {
+                self.link += 1;
+                0.0
+            }
+            Action::Back => {
+                self.link = 0;
+                2.0
+            }
+        };
+        // the chain never ends on its own
+        (self.link, reward, false)
+    }
+
+    fn action_space(&self) -> Vec<Action> {
+        vec![Action::Forward, Action::Back]
+    }
+}
+
+fn main() {
+    let mut agent = GymAgent::new(Chain { link: 0 });
+    let mut trainer = AgentTrainer::new();
+    for _ in 0..1000 {
+        agent.reset();
+        trainer.train(
+            &mut agent,
+            &QLearning::new(0.1, 0.95, 0.0),
+            &mut FixedIterations::new(100),
+            &RandomExploration,
+        );
+    }
+
+    // walking all the way forward pays off, despite the reward for walking back
+    let mut learned: Vec<_> = trainer.learned_values().iter().collect();
+    learned.sort_by_key(|(state, _)| *state.observation());
+    println!("link\tforward\tback");
+    for (state, values) in learned {
+        println!(
+            "{}\t{:.2}\t{:.2}",
+            state.observation(),
+            values[&Action::Forward],
+            values[&Action::Back]
+        );
+    }
+}
@@ -82,27 +82,133 @@
 #[doc = include_str!("../README.md")]
 mod doc_test {}
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
 
-use mdp::{Agent, State};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+use cache::ActionCache;
+pub use cache::CacheStats;
+pub use curriculum::{Curriculum, Stage, StageReport};
+use early_stop::{Check, Plateau};
+pub use early_stop::{EarlyStopping, EarlyStoppingReport};
+pub use error::RurelError;
+use mdp::{Agent, State, Transition};
+use observe::{NullObserver, TransitionObserver};
+pub use off_policy::off_policy_evaluate;
+pub use plan::{Lookahead, Search};
+pub use policy::Policy;
+pub use rollout::{EvalStats, RolloutResult};
+pub use sample::{StartSampler, WeightedStarts};
+pub use store::{KeyedStore, ValueStore};
 use strategy::explore::ExplorationStrategy;
 use strategy::learn::LearningStrategy;
 use strategy::terminate::TerminationStrategy;
+pub use trainer::{TabularTrainer, Trainer};
+pub use value::QValue;
 
+mod cache;
+mod curriculum;
 #[cfg(feature = "dqn")]
 pub mod dqn;
+mod early_stop;
+mod error;
 pub mod mdp;
+pub mod metrics;
+pub mod observe;
+mod off_policy;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(any(feature = "serde", feature = "binary"))]
+mod persist;
+mod plan;
+mod policy;
+mod rollout;
+mod sample;
+mod store;
 pub mod strategy;
+mod trainer;
+mod value;
+
+/// Computes the value of the actions without a learned value in a state, from the state and the
+/// values learned for it.
+type DefaultValue<S, V> = Box<dyn Fn(&S, &HashMap<<S as State>::A, V>) -> V + Send + Sync>;
+
+/// Saves a checkpoint of a trainer, together with the states of its learning and exploration
+/// strategies.
+type SaveCheckpoint<S, V, Q> =
+    Box<dyn Fn(&AgentTrainer<S, V, Q>, &[u64], &[u64]) -> Result<(), RurelError> + Send + Sync>;
 
 /// An `AgentTrainer` can be trained for using a certain [Agent](mdp/trait.Agent.html). After
 /// training, the `AgentTrainer` contains learned knowledge about the process, and can be queried
 /// for this. For example, you can ask the `AgentTrainer` the expected values of all possible
 /// actions in a given state.
-pub struct AgentTrainer<S>
+///
+/// The learned values are stored as `f64` by default. They can also be stored as `f32`, to halve
+/// the memory they take; see [QValue]. They are kept in a `HashMap` by default, and can be kept
+/// elsewhere with [with_store()](AgentTrainer::with_store); see [ValueStore].
+pub struct AgentTrainer<S, V = f64, Q = HashMap<S, HashMap<<S as State>::A, V>>>
 where
     S: State,
+    V: QValue,
+    Q: ValueStore<S, V>,
 {
-    q: HashMap<S, HashMap<S::A, f64>>,
+    q: Q,
+    /// The number of episodes trained so far.
+    episodes: usize,
+    /// The number of transitions learned from for every state and action, if it is being
+    /// counted.
+    visits: Option<HashMap<S, HashMap<S::A, u64>>>,
+    /// The value of the actions for which no value was learned yet, if they aren't ignored.
+    initial_value: Option<V>,
+    /// The penalty subtracted from the reward of every step.
+    step_penalty: f64,
+    /// The number of times every transition into a terminal state is learned from.
+    terminal_updates: usize,
+    /// The scale of the bonus added to the rewards that are learned from for rarely taken
+    /// actions, if there is one.
+    exploration_bonus: Option<f64>,
+    /// The value of the actions without a learned value when querying, if it is computed.
+    default_value: Option<DefaultValue<S, V>>,
+    /// The generator that picks among the best actions, if ties are broken at random.
+    tie_rng: Option<Mutex<ChaCha12Rng>>,
+    /// The number of episodes between checkpoints, and how to save them, if they are saved.
+    checkpoints: Option<(usize, SaveCheckpoint<S, V, Q>)>,
+    /// The error of the last checkpoint, if it couldn't be saved.
+    checkpoint_error: Option<RurelError>,
+    /// The actions of recently seen states, if they are cached.
+    action_cache: Option<ActionCache<S>>,
+}
+
+/// How [AgentTrainer::best_action()] picks among actions with the same highest value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreaking {
+    /// The first of the tied actions, in the order of [State::actions()]. This is the default.
+    ActionOrder,
+    /// One of the tied actions at random, from a generator seeded with the given seed, so that
+    /// a trainer with the same seed and the same values picks the same sequence of actions.
+    Random(u64),
+}
+
+/// Statistics about a single training episode, reported by
+/// [AgentTrainer::train_with_observer()].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpisodeStats {
+    /// The index of the episode, counting from `0` for the first episode trained by the
+    /// [AgentTrainer].
+    pub episode: usize,
+    /// The number of actions taken during the episode.
+    pub steps: usize,
+    /// The sum of the rewards received during the episode.
+    pub total_reward: f64,
+    /// The number of states values were learned for after the episode, or `None` if the
+    /// [ValueStore] can't tell.
+    pub states: Option<usize>,
+    /// The number of values learned after the episode, one for every state and action, or
+    /// `None` if the [ValueStore] can't tell.
+    pub entries: Option<usize>,
 }
 
 impl<S> AgentTrainer<S>
@@ -110,80 +216,2364 @@ where
     S: State,
 {
     pub fn new() -> AgentTrainer<S> {
-        AgentTrainer { q: HashMap::new() }
+        AgentTrainer::default()
     }
+}
 
-    /// Fetches the learned values for the given state, by `Action`, or `None` if no value was
-    /// learned.
-    pub fn expected_values(&self, state: &S) -> Option<&HashMap<S::A, f64>> {
-        // XXX: make associated const with empty map and remove Option?
-        self.q.get(state)
+impl<S, V, Q> AgentTrainer<S, V, Q>
+where
+    S: State,
+    V: QValue,
+    Q: ValueStore<S, V>,
+{
+    /// Constructs an `AgentTrainer` which keeps the learned values in `store`, instead of a
+    /// `HashMap`. See [ValueStore] for an example.
+    pub fn with_store(store: Q) -> Self {
+        AgentTrainer {
+            q: store,
+            episodes: 0,
+            visits: None,
+            initial_value: None,
+            step_penalty: 0.0,
+            terminal_updates: 1,
+            exploration_bonus: None,
+            default_value: None,
+            tie_rng: None,
+            action_cache: None,
+            checkpoints: None,
+            checkpoint_error: None,
+        }
+    }
+
+    /// Returns the storage of the learned values.
+    pub fn store(&self) -> &Q {
+        &self.q
+    }
+
+    /// Returns the number of episodes trained so far, including those before the checkpoint
+    /// training was resumed from, if any.
+    pub fn episodes(&self) -> usize {
+        self.episodes
+    }
+
+    /// Sets the value of the actions for which no value was learned yet to `initial_value`.
+    /// By default, these actions are ignored.
+    ///
+    /// With an optimistic initial value, higher than any value that can actually be learned,
+    /// every action that wasn't tried yet looks better than the ones that were, so even a
+    /// greedy exploration strategy systematically tries every action.
+    ///
+    /// During training, the values of all actions of a state are set to `initial_value` when
+    /// the state is first seen, so the exploration strategy sees them, and the first update of a
+    /// value starts from `initial_value`. The initial value of the learning strategy is then
+    /// only used for states without actions. After training,
+    /// [expected_value()](AgentTrainer::expected_value) and
+    /// [best_action()](AgentTrainer::best_action) treat the actions without a learned value as
+    /// having value `initial_value`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::hash::{Hash, Hasher};
+    ///
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::GreedyExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // A single slot machine, with the reward of the last pull
+    /// #[derive(Clone)]
+    /// struct Machine(f64);
+    ///
+    /// impl PartialEq for Machine {
+    ///     fn eq(&self, _: &Machine) -> bool {
+    ///         true
+    ///     }
+    /// }
+    /// impl Eq for Machine {}
+    /// impl Hash for Machine {
+    ///     fn hash<H: Hasher>(&self, _: &mut H) {}
+    /// }
+    ///
+    /// impl State for Machine {
+    ///     type A = usize;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0
+    ///     }
+    ///     fn actions(&self) -> Vec<usize> {
+    ///         vec![0, 1, 2, 3]
+    ///     }
+    /// }
+    ///
+    /// // Arm `i` pays `i`
+    /// struct Gambler(Machine);
+    ///
+    /// impl Agent<Machine> for Gambler {
+    ///     fn current_state(&self) -> &Machine {
+    ///         &self.0
+    ///     }
+    ///     fn take_action(&mut self, arm: &usize) {
+    ///         self.0 = Machine(*arm as f64);
+    ///     }
+    /// }
+    ///
+    /// let train = |trainer: AgentTrainer<Machine>| {
+    ///     let mut trainer = trainer.with_visit_counts(true);
+    ///     let mut agent = Gambler(Machine(0.0));
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.5, 0.0, 0.0),
+    ///         &mut FixedIterations::new(99),
+    ///         &GreedyExploration,
+    ///     );
+    ///     let tried = (0..4).filter(|arm| trainer.visit_count(&Machine(0.0), arm) > 0);
+    ///     (tried.count(), trainer.best_action(&Machine(0.0)))
+    /// };
+    ///
+    /// // Without exploring, only the arms pulled before any value was learned are ever tried
+    /// assert!(train(AgentTrainer::new()).0 <= 2);
+    /// // Optimism tries every arm, and finds the best one
+    /// assert_eq!(train(AgentTrainer::new().with_initial_value(10.0)), (4, Some(3)));
+    /// ```
+    pub fn with_initial_value(mut self, initial_value: V) -> Self {
+        self.initial_value = Some(initial_value);
+        self
+    }
+
+    /// Sets the penalty that is subtracted from the reward of every step taken during training,
+    /// which is `0` by default. This makes shorter episodes better than longer ones, without
+    /// changing the rewards of the states themselves, so environments that only reward the
+    /// outcome of an episode at their [terminal](State::is_terminal) states still learn to reach
+    /// good outcomes quickly. The rewards reported by
+    /// [train_with_observer()](AgentTrainer::train_with_observer) include the penalty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(4)`, and only reaching
+    /// // the goal at 4 is rewarded
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     // Only reaching the goal is rewarded
+    /// #     fn reward(&self) -> f64 {
+    /// #         if self.is_terminal() { 1.0 } else { 0.0 }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// #     fn is_terminal(&self) -> bool {
+    /// #         self.0 == 4
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 4));
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer = AgentTrainer::new().with_step_penalty(0.1);
+    /// for _ in 0..500 {
+    ///     // Every episode ends at the goal, or after 101 steps
+    ///     trainer.train(
+    ///         &mut Walker(Position(2)),
+    ///         &QLearning::new(0.5, 1.0, 0.0),
+    ///         &mut FixedIterations::new(100),
+    ///         &RandomExploration,
+    ///     );
+    /// }
+    /// // Without discounting, only the penalty makes walking towards the goal better
+    /// assert_eq!(trainer.best_action(&Position(2)), Some(1));
+    /// let value = trainer.expected_value(&Position(3), &1).unwrap();
+    /// assert!((value - 0.9).abs() < 1e-6);
+    /// ```
+    pub fn with_step_penalty(mut self, step_penalty: f64) -> Self {
+        self.step_penalty = step_penalty;
+        self
+    }
+
+    /// Sets the number of times every transition into a [terminal](State::is_terminal) state is
+    /// learned from to `updates`, instead of once. In tasks where the only reward is at the goal,
+    /// this makes the goal signal, which every other value is learned from, stand out from the
+    /// many steps without reward. With Q Learning and a learning rate `alpha`, it is as if these
+    /// transitions were learned with a learning rate of `1 - (1 - alpha)^updates`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `updates` is `0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::SeededRandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // A 4x4 grid, with the goal in a corner
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Cell(i32, i32);
+    ///
+    /// impl State for Cell {
+    ///     type A = (i32, i32);
+    ///     fn reward(&self) -> f64 {
+    ///         if self.is_terminal() { 1.0 } else { 0.0 }
+    ///     }
+    ///     fn actions(&self) -> Vec<(i32, i32)> {
+    ///         vec![(0, -1), (0, 1), (-1, 0), (1, 0)]
+    ///     }
+    ///     fn is_terminal(&self) -> bool {
+    ///         *self == Cell(3, 3)
+    ///     }
+    /// }
+    ///
+    /// struct Walker(Cell);
+    ///
+    /// impl Agent<Cell> for Walker {
+    ///     fn current_state(&self) -> &Cell {
+    ///         &self.0
+    ///     }
+    ///     fn take_action(&mut self, (dx, dy): &(i32, i32)) {
+    ///         self.0 = Cell((self.0 .0 + dx).clamp(0, 3), (self.0 .1 + dy).clamp(0, 3));
+    ///     }
+    /// }
+    ///
+    /// // Both trainers take the same random walks to the goal
+    /// let train = |updates| {
+    ///     let mut trainer = AgentTrainer::new().with_terminal_updates(updates);
+    ///     let exploration = SeededRandomExploration::new(3);
+    ///     for _ in 0..20 {
+    ///         trainer.train(
+    ///             &mut Walker(Cell(0, 0)),
+    ///             &QLearning::new(0.1, 0.9, 0.0),
+    ///             &mut FixedIterations::new(1000),
+    ///             &exploration,
+    ///         );
+    ///     }
+    ///     trainer
+    /// };
+    /// let once = train(1);
+    /// let five_times = train(5);
+    ///
+    /// // The value of reaching the goal is learned faster...
+    /// let reach_goal = |trainer: &AgentTrainer<Cell>| {
+    ///     trainer.expected_value(&Cell(3, 2), &(0, 1)).unwrap()
+    /// };
+    /// assert!(reach_goal(&once) < 0.7);
+    /// assert!(reach_goal(&five_times) > 0.99);
+    /// // ...and so is the value of the way there, even from the start
+    /// let leave_start = |trainer: &AgentTrainer<Cell>| {
+    ///     trainer.expected_value(&Cell(0, 0), &(1, 0)).unwrap()
+    /// };
+    /// assert!(leave_start(&five_times) > 2.0 * leave_start(&once));
+    /// ```
+    pub fn with_terminal_updates(mut self, updates: usize) -> Self {
+        assert!(
+            updates > 0,
+            "terminal transitions must be learned from at least once"
+        );
+        self.terminal_updates = updates;
+        self
+    }
+
+    /// Sets the function which computes the value of the actions without a learned value, when
+    /// querying the learned values. It is given the state, and the values learned for it, which
+    /// are empty if the state was never visited, so it can return a constant, or something like
+    /// the mean of the learned values.
+    ///
+    /// By default, [best_action()](AgentTrainer::best_action) only picks among the actions with a
+    /// learned value, so a state where an early, mediocre action was learned keeps returning it
+    /// while better actions were never tried. With a default value,
+    /// [best_action()](AgentTrainer::best_action) takes the maximum over all actions of
+    /// `state.actions()` instead, and an unseen action is picked whenever its default value is
+    /// higher than every learned value. [expected_value()](AgentTrainer::expected_value) returns
+    /// the default value for unseen actions as well.
+    ///
+    /// This takes precedence over an [initial value](AgentTrainer::with_initial_value) when
+    /// querying, but unlike the initial value it doesn't change training.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Crossroads;
+    ///
+    /// impl State for Crossroads {
+    ///     type A = char;
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<char> {
+    ///         vec!['a', 'b', 'c']
+    ///     }
+    /// }
+    ///
+    /// // Only two of the three actions were tried
+    /// let values = || [(Crossroads, [('a', 2.0), ('b', -4.0)].into_iter().collect())];
+    ///
+    /// let mut constant = AgentTrainer::new().with_default_value(|_, _| 3.0);
+    /// constant.import_state(values().into_iter().collect());
+    /// assert_eq!(constant.best_action(&Crossroads), Some('c'));
+    ///
+    /// let mut mean = AgentTrainer::new().with_default_value(|_, learned| {
+    ///     learned.values().sum::<f64>() / learned.len().max(1) as f64
+    /// });
+    /// mean.import_state(values().into_iter().collect());
+    /// assert_eq!(mean.best_action(&Crossroads), Some('a'));
+    /// assert_eq!(mean.expected_value(&Crossroads, &'c'), Some(-1.0));
+    /// ```
+    pub fn with_default_value<F>(mut self, default_value: F) -> Self
+    where
+        F: Fn(&S, &HashMap<S::A, V>) -> V + Send + Sync + 'static,
+    {
+        self.default_value = Some(Box::new(default_value));
+        self
+    }
+
+    /// Sets how [best_action()](AgentTrainer::best_action) picks among actions with the same
+    /// highest value. By default, it picks the first of them in the order of
+    /// [State::actions()], so that what was learned is always evaluated the same way.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::{AgentTrainer, TieBreaking};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState;
+    ///
+    /// impl State for MyState {
+    ///     type A = char;
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<char> {
+    ///         vec!['c', 'a', 'b']
+    ///     }
+    /// }
+    ///
+    /// let tied = || {
+    ///     let mut trainer = AgentTrainer::new();
+    ///     trainer.import_state(
+    ///         [(MyState, [('a', 1.0), ('b', 1.0), ('c', 0.0)].into_iter().collect())]
+    ///             .into_iter()
+    ///             .collect(),
+    ///     );
+    ///     trainer
+    /// };
+    ///
+    /// // 'a' comes before 'b' in the actions of the state
+    /// let trainer = tied();
+    /// assert!((0..20).all(|_| trainer.best_action(&MyState) == Some('a')));
+    ///
+    /// // The same seed picks the same actions, which are all among the best ones
+    /// let picks = |trainer: AgentTrainer<MyState>| -> Vec<char> {
+    ///     (0..20).filter_map(|_| trainer.best_action(&MyState)).collect()
+    /// };
+    /// let first = picks(tied().with_tie_breaking(TieBreaking::Random(7)));
+    /// assert_eq!(first, picks(tied().with_tie_breaking(TieBreaking::Random(7))));
+    /// assert!(first.contains(&'a') && first.contains(&'b') && !first.contains(&'c'));
+    /// ```
+    pub fn with_tie_breaking(mut self, tie_breaking: TieBreaking) -> Self {
+        self.tie_rng = match tie_breaking {
+            TieBreaking::ActionOrder => None,
+            TieBreaking::Random(seed) => Some(Mutex::new(ChaCha12Rng::seed_from_u64(seed))),
+        };
+        self
+    }
+
+    /// Caches the [actions()](State::actions) of the `capacity` most recently seen states, for
+    /// environments in which enumerating the actions is expensive, such as the legal moves of a
+    /// chess position. The trainer enumerates the actions of a state to set their
+    /// [initial values](AgentTrainer::with_initial_value) when it first sees the state during
+    /// training, and to find the [best action](AgentTrainer::best_action). By default, nothing is
+    /// cached.
+    ///
+    /// The cache assumes that the actions of a state only depend on the state itself, as it
+    /// looks them up by the state. See [action_cache_stats()](AgentTrainer::action_cache_stats)
+    /// for how well it works.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// static ENUMERATED: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Position(i32);
+    ///
+    /// impl State for Position {
+    ///     type A = i32;
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<i32> {
+    ///         ENUMERATED.fetch_add(1, Ordering::Relaxed);
+    ///         vec![-1, 1]
+    ///     }
+    /// }
+    ///
+    /// let trainer: AgentTrainer<Position> = AgentTrainer::new().with_action_cache(2);
+    /// for x in [0, 1, 0, 0, 1, 2, 0] {
+    ///     trainer.best_action(&Position(x));
+    /// }
+    ///
+    /// // Position 0 was forgotten when position 2 was first seen
+    /// assert_eq!(ENUMERATED.load(Ordering::Relaxed), 4);
+    /// let stats = trainer.action_cache_stats().unwrap();
+    /// assert_eq!((stats.hits, stats.misses), (3, 4));
+    /// assert_eq!(stats.hit_rate(), Some(3.0 / 7.0));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    pub fn with_action_cache(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the action cache must hold at least one state"
+        );
+        self.action_cache = Some(ActionCache::new(capacity));
+        self
+    }
+
+    /// Returns how often the [action cache](AgentTrainer::with_action_cache) held the actions
+    /// of the states they were needed for, or `None` if actions aren't cached.
+    pub fn action_cache_stats(&self) -> Option<CacheStats> {
+        self.action_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Sets whether the number of times every action was taken in every state is counted during
+    /// training, by counting the transitions that are learned from. By default, it isn't, since
+    /// the counts take about as much memory as the learned values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // An unrewarded `Walker` steps by -1 or 1 between `Position(0)` and `Position(100)`
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         0.0
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 100));
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer = AgentTrainer::new().with_visit_counts(true);
+    /// let mut agent = Walker(Position(0));
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.9, 0.0),
+    ///     &mut FixedIterations::new(99),
+    ///     &RandomExploration,
+    /// );
+    ///
+    /// // Every one of the 100 actions was counted
+    /// let counts = trainer.visit_counts().unwrap();
+    /// let total: u64 = counts.values().flat_map(|m| m.values()).sum();
+    /// assert_eq!(total, 100);
+    /// // A random walk of 100 steps can't get further than position 100
+    /// assert_eq!(trainer.visit_count(&Position(100), &1), 0);
+    /// ```
+    pub fn with_visit_counts(mut self, count: bool) -> Self {
+        self.visits = if count { Some(HashMap::new()) } else { None };
+        self
+    }
+
+    /// Adds an exploration bonus of `beta / sqrt(n)` to the reward of every transition that is
+    /// learned from, where `n` is the number of times its action was taken in its state during
+    /// training, including this time. This counts visits like
+    /// [with_visit_counts()](AgentTrainer::with_visit_counts).
+    ///
+    /// Actions that were rarely taken look better than they are, so a value-aware exploration
+    /// strategy such as [EpsilonGreedy](strategy::explore::EpsilonGreedy) keeps trying them
+    /// instead of settling for the first reward it finds. The bonus shrinks as the actions are
+    /// taken more often, until what was learned is mostly the actual rewards. It only changes
+    /// what is learned: the statistics of the episodes and the transitions given to observers
+    /// hold the actual rewards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::EpsilonGreedy;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // A corridor with a small reward next to the start, and a large one far away
+    /// // A `Walker` steps by -1 or 1 along a line, and ends at 0 with a reward of 0.1, or at 12
+    /// // with a reward of 1
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         match self.0 {
+    /// #             0 => 0.1,
+    /// #             12 => 1.0,
+    /// #             _ => 0.0,
+    /// #         }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// #     fn is_terminal(&self) -> bool {
+    /// #         self.0 == 0 || self.0 == 12
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position(self.0 .0 + step);
+    /// #     }
+    /// # }
+    ///
+    /// // Returns in how many of 100 episodes the far goal is reached
+    /// let reached = |mut trainer: AgentTrainer<Position>| {
+    ///     (0..100)
+    ///         .filter(|_| {
+    ///             let mut agent = Walker(Position(2));
+    ///             trainer.train(
+    ///                 &mut agent,
+    ///                 &QLearning::new(0.5, 0.9, 0.0),
+    ///                 &mut FixedIterations::new(100),
+    ///                 &EpsilonGreedy::new(0.2),
+    ///             );
+    ///             agent.0 == Position(12)
+    ///         })
+    ///         .count()
+    /// };
+    ///
+    /// // Without the bonus, the small reward is found first, and followed from then on
+    /// assert!(reached(AgentTrainer::new().with_initial_value(0.0)) < 10);
+    /// assert!(reached(AgentTrainer::new().with_initial_value(0.0).with_exploration_bonus(0.1)) > 30);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `beta` is negative.
+    pub fn with_exploration_bonus(mut self, beta: f64) -> Self {
+        assert!(beta >= 0.0, "the exploration bonus must not be negative");
+        if self.visits.is_none() {
+            self.visits = Some(HashMap::new());
+        }
+        self.exploration_bonus = Some(beta);
+        self
+    }
+
+    /// Returns the number of times every action was taken in every state during training, or
+    /// `None` if [visits aren't counted](AgentTrainer::with_visit_counts).
+    pub fn visit_counts(&self) -> Option<&HashMap<S, HashMap<S::A, u64>>> {
+        self.visits.as_ref()
+    }
+
+    /// Returns the number of times `action` was taken in `state` during training, or `0` if
+    /// [visits aren't counted](AgentTrainer::with_visit_counts).
+    pub fn visit_count(&self, state: &S, action: &S::A) -> u64 {
+        self.visits
+            .as_ref()
+            .and_then(|visits| visits.get(state))
+            .and_then(|m| m.get(action))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the actions of `state`, from the action cache if there is one.
+    fn actions(&self, state: &S) -> Vec<S::A> {
+        match &self.action_cache {
+            Some(cache) => cache.actions(state),
+            None => state.actions(),
+        }
+    }
+
+    /// Sets the values of the actions of `state` for which no value was learned yet to the
+    /// initial value, if there is one.
+    fn initialize(&mut self, state: &S) {
+        if let Some(initial_value) = self.initial_value {
+            for action in self.actions(state) {
+                if self.q.get(state, &action).is_none() {
+                    self.q.set(state, &action, initial_value);
+                }
+            }
+        }
+    }
+
+    /// Counts a visit of `action` in `state`, if visits are counted.
+    fn count_visit(&mut self, state: &S, action: &S::A) {
+        if let Some(visits) = &mut self.visits {
+            *visits
+                .entry(state.clone())
+                .or_default()
+                .entry(action.clone())
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Counts a visit of the action of `transition` in its state, and returns the transition to
+    /// learn from, whose reward includes the exploration bonus, if there is one.
+    fn visit<'a>(&mut self, transition: &Transition<'a, S>) -> Transition<'a, S> {
+        self.count_visit(transition.state, transition.action);
+        let bonus = match self.exploration_bonus {
+            Some(beta) => {
+                // visits are no longer counted if counting was turned off afterwards
+                let count = self.visit_count(transition.state, transition.action).max(1);
+                beta / (count as f64).sqrt()
+            }
+            None => 0.0,
+        };
+        Transition {
+            reward: transition.reward + bonus,
+            ..*transition
+        }
+    }
+
+    /// Learns from `transition` with `learning_strategy`, telling it how often its state and
+    /// action were learned from before if visits are counted.
+    fn learn(
+        &mut self,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        transition: &Transition<S>,
+    ) -> f64 {
+        if self.visits.is_some() {
+            // the transition was already counted by visit()
+            let earlier = self
+                .visit_count(transition.state, transition.action)
+                .saturating_sub(1);
+            learning_strategy.learn_with_visits(&mut self.q, transition, earlier)
+        } else {
+            learning_strategy.learn(&mut self.q, transition)
+        }
+    }
+
+    /// Returns the number of times a transition into `next_state` is learned from.
+    fn updates_into(&self, next_state: &S) -> usize {
+        if next_state.is_terminal() {
+            self.terminal_updates
+        } else {
+            1
+        }
+    }
+
+    /// Saves a checkpoint, if checkpoints are saved and one is due after the last `trained`
+    /// episodes, which just ended, and keeps the error if it can't be saved.
+    fn checkpoint(
+        &mut self,
+        trained: usize,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+    ) {
+        if let Some((every, save)) = &self.checkpoints {
+            if self.episodes / every > (self.episodes - trained) / every {
+                let learning = learning_strategy.save_state();
+                let exploration = exploration_strategy.save_state();
+                self.checkpoint_error = save(self, &learning, &exploration).err();
+            }
+        }
     }
 
     /// Fetches the learned value for the given `Action` in the given `State`, or `None` if no
-    /// value was learned.
-    pub fn expected_value(&self, state: &S, action: &S::A) -> Option<f64> {
-        self.q.get(state).and_then(|m| m.get(action).copied())
+    /// value was learned. With an [initial value](AgentTrainer::with_initial_value), that value
+    /// is returned instead of `None`.
+    pub fn expected_value(&self, state: &S, action: &S::A) -> Option<V> {
+        self.q
+            .get(state, action)
+            .or_else(|| self.unseen_value(state, self.q.values(state).as_deref()))
     }
 
-    /// Returns a clone of the entire learned state to be saved or used elsewhere.
-    pub fn export_learned_values(&self) -> HashMap<S, HashMap<S::A, f64>> {
-        self.q.clone()
+    /// Fetches the learned value for the given `Action` in the given `State`, or `None` if no
+    /// value was learned. Unlike [expected_value()](AgentTrainer::expected_value), this ignores
+    /// the [initial value](AgentTrainer::with_initial_value) and the
+    /// [default value](AgentTrainer::with_default_value).
+    pub fn action_value(&self, state: &S, action: &S::A) -> Option<V> {
+        self.q.get(state, action)
     }
 
-    // Returns a reference to the learned state.
-    pub fn learned_values(&self) -> &HashMap<S, HashMap<S::A, f64>> {
-        &self.q
+    /// Returns whether any value was learned for `state`, including the
+    /// [initial values](AgentTrainer::with_initial_value) it gets when training first arrives at
+    /// it. If not, [best_action()](AgentTrainer::best_action) knows nothing about the state: it
+    /// returns `None`, or an action picked by the
+    /// [default value](AgentTrainer::with_default_value) alone.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = char;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<char> {
+    ///         vec!['a', 'b']
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new().with_default_value(|_, _| 0.0);
+    /// trainer.import_state([(MyState(0), [('a', 1.0)].into_iter().collect())].into_iter().collect());
+    /// assert!(trainer.is_known(&MyState(0)));
+    /// // The default value gives a best action for any state, known or not
+    /// assert!(!trainer.is_known(&MyState(1)));
+    /// assert_eq!(trainer.best_action(&MyState(1)), Some('a'));
+    /// ```
+    pub fn is_known(&self, state: &S) -> bool {
+        self.q
+            .values(state)
+            .is_some_and(|values| !values.is_empty())
     }
 
-    /// Imports a state, completely replacing any learned progress
-    pub fn import_state(&mut self, q: HashMap<S, HashMap<S::A, f64>>) {
-        self.q = q;
+    /// Returns the lowest and the highest learned value among the actions of the given `State`,
+    /// or `None` if no value was learned for it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = char;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<char> {
+    ///         vec!['a', 'b', 'c']
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new().with_initial_value(0.0);
+    /// trainer.import_state(
+    ///     [(MyState(0), [('a', 0.5), ('b', 2.0), ('c', -1.0)].into_iter().collect())]
+    ///         .into_iter()
+    ///         .collect(),
+    /// );
+    /// assert_eq!(trainer.action_value_range(&MyState(0)), Some((-1.0, 2.0)));
+    /// assert_eq!(trainer.action_value_range(&MyState(1)), None);
+    /// assert_eq!(trainer.action_value(&MyState(0), &'b'), Some(2.0));
+    /// // Only the expected value falls back to the initial value
+    /// assert_eq!(trainer.action_value(&MyState(1), &'b'), None);
+    /// assert_eq!(trainer.expected_value(&MyState(1), &'b'), Some(0.0));
+    /// ```
+    pub fn action_value_range(&self, state: &S) -> Option<(V, V)> {
+        let values = self.q.values(state)?;
+        let mut values = values.values().copied();
+        let first = values.next()?;
+        Some(values.fold((first, first), |(min, max), v| {
+            let min = if v.to_f64() < min.to_f64() { v } else { min };
+            let max = if v.to_f64() > max.to_f64() { v } else { max };
+            (min, max)
+        }))
+    }
+
+    /// Returns the value of the actions of `state` without a learned value, given the values
+    /// `learned` for it, or `None` if these actions are ignored.
+    fn unseen_value(&self, state: &S, learned: Option<&HashMap<S::A, V>>) -> Option<V> {
+        match &self.default_value {
+            Some(default_value) => Some(match learned {
+                Some(learned) => default_value(state, learned),
+                None => default_value(state, &HashMap::new()),
+            }),
+            None => self.initial_value,
+        }
     }
 
     /// Returns the best action for the given `State`, or `None` if no values were learned.
+    ///
+    /// With an [initial value](AgentTrainer::with_initial_value) or a
+    /// [default value](AgentTrainer::with_default_value), all actions of the state are taken
+    /// into account, with that value for the ones without a learned value.
+    ///
+    /// Ties between equally valued actions are broken as set with
+    /// [with_tie_breaking()](AgentTrainer::with_tie_breaking).
     pub fn best_action(&self, state: &S) -> Option<S::A> {
-        self.expected_values(state)
-            .and_then(|m| {
-                m.iter()
-                    .max_by(|&(_, v1), &(_, v2)| v1.partial_cmp(v2).unwrap())
+        self.best_action_with_value(state).map(|(action, _)| action)
+    }
+
+    /// Returns the [best action](AgentTrainer::best_action) for the given `State`, or why there
+    /// is none: [RurelError::NoActions] if the state has no actions, and
+    /// [RurelError::UnknownState] if it has actions, but no values were learned for it, so that
+    /// callers can tell a finished game from a state the trainer never saw.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::{AgentTrainer, RurelError};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Pile(u32);
+    ///
+    /// impl State for Pile {
+    ///     type A = u32;
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<u32> {
+    ///         (1..=self.0.min(2)).collect()
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state([(Pile(2), [(1, 0.0), (2, 1.0)].into_iter().collect())].into());
+    /// assert!(matches!(trainer.try_best_action(&Pile(2)), Ok(2)));
+    /// assert!(matches!(trainer.try_best_action(&Pile(3)), Err(RurelError::UnknownState)));
+    /// assert!(matches!(trainer.try_best_action(&Pile(0)), Err(RurelError::NoActions)));
+    /// ```
+    pub fn try_best_action(&self, state: &S) -> Result<S::A, RurelError> {
+        match self.best_action(state) {
+            Some(action) => Ok(action),
+            None if self.actions(state).is_empty() => Err(RurelError::NoActions),
+            None => Err(RurelError::UnknownState),
+        }
+    }
+
+    /// Returns the [best action](AgentTrainer::best_action) for the given `State` together with
+    /// its value, or `None` if no values were learned. The value is the one the action was
+    /// chosen by, so there is no need to look it up again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = char;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<char> {
+    ///         vec!['a', 'b', 'c']
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     [(MyState(0), [('a', 0.5), ('b', 2.0), ('c', -1.0)].into_iter().collect())]
+    ///         .into_iter()
+    ///         .collect(),
+    /// );
+    /// assert_eq!(trainer.best_action_with_value(&MyState(0)), Some(('b', 2.0)));
+    /// assert_eq!(trainer.best_action_with_value(&MyState(1)), None);
+    /// ```
+    pub fn best_action_with_value(&self, state: &S) -> Option<(S::A, f64)> {
+        let learned = self.q.values(state);
+        let learned = learned.as_deref();
+        let unseen_value = self.unseen_value(state, learned);
+        // the candidates in the order of the actions of the state, followed by any other
+        // actions with a learned value
+        let actions = self.actions(state);
+        let mut candidates: Vec<(S::A, V)> = actions
+            .iter()
+            .filter_map(|action| {
+                let value = learned.and_then(|m| m.get(action).copied());
+                Some((action.clone(), value.or(unseen_value)?))
             })
-            .map(|t| t.0.clone())
+            .collect();
+        if let Some(learned) = learned {
+            candidates.extend(
+                learned
+                    .iter()
+                    .filter(|(action, _)| !actions.contains(action))
+                    .map(|(action, &value)| (action.clone(), value)),
+            );
+        }
+
+        let max = candidates
+            .iter()
+            .map(|(_, v)| v.to_f64())
+            .max_by(f64::total_cmp)?;
+        let mut ties = candidates
+            .into_iter()
+            .filter(|(_, v)| v.to_f64().total_cmp(&max).is_eq());
+        match &self.tie_rng {
+            None => ties.next(),
+            Some(rng) => ties
+                .collect::<Vec<_>>()
+                .choose(&mut *rng.lock().unwrap())
+                .cloned(),
+        }
+        .map(|(action, value)| (action, value.to_f64()))
+    }
+
+    /// Returns up to `k` of the actions with the highest learned values in the given `State`,
+    /// together with their values, sorted from highest to lowest value. Fewer than `k` actions
+    /// are returned if fewer values were learned, and none if the state was never visited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = char;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<char> {
+    ///         vec!['a', 'b', 'c', 'd']
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     [(MyState(0), [('a', 0.5), ('b', 2.0), ('c', -1.0)].into_iter().collect())]
+    ///         .into_iter()
+    ///         .collect(),
+    /// );
+    /// assert_eq!(trainer.top_k_actions(&MyState(0), 2), vec![('b', 2.0), ('a', 0.5)]);
+    /// assert_eq!(trainer.top_k_actions(&MyState(0), 10).len(), 3);
+    /// assert!(trainer.top_k_actions(&MyState(1), 2).is_empty());
+    /// ```
+    pub fn top_k_actions(&self, state: &S, k: usize) -> Vec<(S::A, V)> {
+        let mut actions: Vec<(S::A, V)> = match self.q.values(state) {
+            Some(m) => m.iter().map(|(a, &v)| (a.clone(), v)).collect(),
+            None => return Vec::new(),
+        };
+        actions.sort_by(|(_, v1), (_, v2)| v2.to_f64().total_cmp(&v1.to_f64()));
+        actions.truncate(k);
+        actions
+    }
+
+    /// Plays a single episode with `agent`, always taking the
+    /// [best_action()](AgentTrainer::best_action), until `termination_strategy` decides to stop.
+    /// Nothing is learned from the episode and nothing is explored, so this evaluates what was
+    /// learned so far, whichever exploration strategy it was trained with. The episode also ends
+    /// in a state without a best action, which is a state without learned values unless an
+    /// [initial value](AgentTrainer::with_initial_value) is set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::{FixedIterations, TerminationStrategy};
+    /// use rurel::AgentTrainer;
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(3)`, and is rewarded with
+    /// // 1 at 3
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         if self.0 == 3 { 1.0 } else { 0.0 }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 3));
+    /// #     }
+    /// # }
+    ///
+    /// struct ReachedGoal;
+    ///
+    /// impl TerminationStrategy<Position> for ReachedGoal {
+    ///     fn should_stop(&mut self, state: &Position) -> bool {
+    ///         state.0 == 3
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.train(
+    ///     &mut Walker(Position(0)),
+    ///     &QLearning::new(0.2, 0.9, 0.0),
+    ///     &mut FixedIterations::new(2000),
+    ///     &RandomExploration,
+    /// );
+    ///
+    /// let result = trainer.rollout(&mut Walker(Position(0)), &mut ReachedGoal);
+    /// assert_eq!(result.steps, 3);
+    /// assert_eq!(result.total_reward, 1.0);
+    /// let path: Vec<i32> = result.trajectory.iter().map(|(state, _)| state.0).collect();
+    /// assert_eq!(path, [0, 1, 2]);
+    /// ```
+    pub fn rollout(
+        &self,
+        agent: &mut dyn Agent<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+    ) -> RolloutResult<S> {
+        rollout::rollout(agent, termination_strategy, |state| self.best_action(state))
+    }
+
+    /// Plays `episodes` episodes like [rollout()](AgentTrainer::rollout), each with a fresh agent
+    /// from `make_agent`, and returns the mean and standard deviation of their total rewards.
+    /// Nothing is learned from the episodes. This evaluates what was learned so far in
+    /// stochastic environments, where a single episode says little about how good the learned
+    /// actions are.
+    ///
+    /// Every episode also gets a fresh termination strategy from `make_termination`, since
+    /// strategies such as [FixedIterations](strategy::terminate::FixedIterations) count the steps
+    /// of all episodes they are used for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::Rng;
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Pull {
+    ///     paid: bool,
+    /// }
+    ///
+    /// impl State for Pull {
+    ///     type A = &'static str;
+    ///     fn reward(&self) -> f64 {
+    ///         if self.paid { 1.0 } else { 0.0 }
+    ///     }
+    ///     fn actions(&self) -> Vec<&'static str> {
+    ///         vec!["safe", "risky"]
+    ///     }
+    /// }
+    ///
+    /// // The risky arm pays half of the time, the safe arm never does
+    /// struct Gambler(Pull);
+    ///
+    /// impl Agent<Pull> for Gambler {
+    ///     fn current_state(&self) -> &Pull {
+    ///         &self.0
+    ///     }
+    ///     fn take_action(&mut self, arm: &&'static str) {
+    ///         self.0 = Pull { paid: *arm == "risky" && rand::thread_rng().gen_bool(0.5) };
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     [(Pull { paid: false }, [("safe", 0.0), ("risky", 0.5)].into_iter().collect())]
+    ///         .into_iter()
+    ///         .collect(),
+    /// );
+    ///
+    /// // Every episode is a single pull
+    /// let stats = trainer.evaluate(
+    ///     || Gambler(Pull { paid: false }),
+    ///     || FixedIterations::new(0),
+    ///     1000,
+    /// );
+    /// assert_eq!(stats.mean_steps, 1.0);
+    /// assert!((stats.mean_reward - 0.5).abs() < 0.1);
+    /// assert!((stats.std_reward - 0.5).abs() < 0.1);
+    /// ```
+    pub fn evaluate<G, T>(
+        &self,
+        make_agent: impl FnMut() -> G,
+        make_termination: impl FnMut() -> T,
+        episodes: usize,
+    ) -> EvalStats
+    where
+        G: Agent<S>,
+        T: TerminationStrategy<S>,
+    {
+        rollout::evaluate(make_agent, make_termination, episodes, |state| {
+            self.best_action(state)
+        })
+    }
+
+    /// Trains like [train()](AgentTrainer::train) for up to `max_episodes` episodes, each with a
+    /// fresh agent from `make_agent` and a fresh termination strategy from `make_termination`,
+    /// and stops early once greedy evaluations stop improving, as set by `early_stopping`. Every
+    /// evaluation is made like [evaluate()](AgentTrainer::evaluate), with agents and termination
+    /// strategies from the same functions.
+    ///
+    /// The trainer is left with the values of the best evaluation, rather than with those it
+    /// learned after it, which didn't improve. Returns what happened during training. This saves
+    /// the episodes that problems which converge early would waste after converging.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::{AgentTrainer, EarlyStopping};
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(3)`, and ends at 3 with a
+    /// // reward of 1
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         if self.0 == 3 { 1.0 } else { 0.0 }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// #     fn is_terminal(&self) -> bool {
+    /// #         self.0 == 3
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 3));
+    /// #     }
+    /// # }
+    ///
+    /// // Evaluate with a single greedy episode after every 10 episodes, and stop after 3
+    /// // evaluations in a row that are no better than the best one
+    /// let mut trainer = AgentTrainer::new();
+    /// let report = trainer.train_with_early_stopping(
+    ///     || Walker(Position(0)),
+    ///     &QLearning::new(0.2, 0.9, 0.0),
+    ///     || FixedIterations::new(20),
+    ///     &RandomExploration,
+    ///     100_000,
+    ///     &EarlyStopping::new(10, 1, 3),
+    /// );
+    ///
+    /// // The goal is reached long before the largest number of episodes
+    /// assert!(report.stopped_early);
+    /// assert!(report.episodes < 100_000);
+    /// assert_eq!(report.evaluations.len(), report.episodes / 10);
+    /// assert_eq!(report.best.unwrap().mean_reward, 1.0);
+    /// assert_eq!(trainer.best_action(&Position(2)), Some(1));
+    /// ```
+    pub fn train_with_early_stopping<G, T>(
+        &mut self,
+        mut make_agent: impl FnMut() -> G,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        mut make_termination: impl FnMut() -> T,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+        max_episodes: usize,
+        early_stopping: &EarlyStopping,
+    ) -> EarlyStoppingReport
+    where
+        G: Agent<S>,
+        T: TerminationStrategy<S>,
+        Q: Clone,
+    {
+        let mut plateau = Plateau::new(early_stopping);
+        let mut best = None;
+        for _ in 0..max_episodes {
+            self.train(
+                &mut make_agent(),
+                learning_strategy,
+                &mut make_termination(),
+                exploration_strategy,
+            );
+            if !plateau.trained() {
+                continue;
+            }
+            let stats = self.evaluate(
+                &mut make_agent,
+                &mut make_termination,
+                early_stopping.rollouts(),
+            );
+            match plateau.evaluated(stats) {
+                Check::Improved => best = Some(self.q.clone()),
+                Check::Stale => {}
+                Check::Stop => break,
+            }
+        }
+        if let Some(best) = best {
+            self.q = best;
+        }
+        plateau.into_report()
+    }
+
+    /// Returns the action to take in the current state of `agent`, found by searching the states
+    /// reachable from it with clones of `agent`, as set by `lookahead`, and scoring the states
+    /// at the end of the search by their best learned value. This plays better than
+    /// [best_action()](AgentTrainer::best_action) where the values were learned from too few
+    /// episodes. Returns `None` if the state has no actions, and no values were learned for it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::{AgentTrainer, Lookahead, Search};
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(2)`, and ends at 2 with a
+    /// // reward of 1
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         if self.0 == 2 { 1.0 } else { 0.0 }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// #     fn is_terminal(&self) -> bool {
+    /// #         self.0 == 2
+    /// #     }
+    /// # }
+    /// #
+    /// # #[derive(Clone)]
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 2));
+    /// #     }
+    /// # }
+    ///
+    /// // Nothing was learned, but the goal is two steps away
+    /// let trainer: AgentTrainer<Position> = AgentTrainer::new();
+    /// let deterministic = Search::Expectimax { samples: 1 };
+    /// assert_eq!(trainer.plan(&Walker(Position(0)), &Lookahead::new(1, 0.9, deterministic)), Some(-1));
+    /// assert_eq!(trainer.plan(&Walker(Position(0)), &Lookahead::new(2, 0.9, deterministic)), Some(1));
+    ///
+    /// // Searching one step ahead takes 2 nodes, and two steps ahead 6 more
+    /// let lookahead = Lookahead::new(2, 0.9, deterministic).with_node_budget(7);
+    /// assert_eq!(trainer.plan(&Walker(Position(0)), &lookahead), Some(-1));
+    /// ```
+    pub fn plan<G>(&self, agent: &G, lookahead: &Lookahead) -> Option<S::A>
+    where
+        G: Agent<S> + Clone,
+    {
+        plan::plan(
+            agent,
+            lookahead,
+            |state| self.actions(state),
+            |state| self.best_action_with_value(state),
+        )
+    }
+
+    /// Returns the highest learned value of every state in `states`, in the same order, or
+    /// `None` for the states for which no value was learned. This is the value of a state when
+    /// the best action is taken in it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = char;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<char> {
+    ///         vec!['a', 'b']
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     [
+    ///         (MyState(0), [('a', 0.5), ('b', 2.0)].into_iter().collect()),
+    ///         (MyState(1), [('a', -1.0)].into_iter().collect()),
+    ///     ]
+    ///     .into_iter()
+    ///     .collect(),
+    /// );
+    /// let positions = [MyState(1), MyState(2), MyState(0)];
+    /// assert_eq!(trainer.state_values(&positions), vec![Some(-1.0), None, Some(2.0)]);
+    /// ```
+    pub fn state_values(&self, states: &[S]) -> Vec<Option<V>> {
+        states
+            .iter()
+            .map(|state| {
+                self.q.values(state).and_then(|m| {
+                    m.values()
+                        .copied()
+                        .max_by(|v1, v2| v1.to_f64().total_cmp(&v2.to_f64()))
+                })
+            })
+            .collect()
     }
 
     /// Trains this [AgentTrainer] using the given [ExplorationStrategy], [LearningStrategy] and
-    /// [Agent] until the [TerminationStrategy] decides to stop.
+    /// [Agent] until the [TerminationStrategy] decides to stop, or the agent arrives at a
+    /// [terminal](State::is_terminal) state.
+    ///
+    /// The [LearningStrategy] learns from every transition once the action taken next is known,
+    /// so that on-policy strategies can take it into account. The last transition is learned
+    /// from without a next action.
+    ///
+    /// The exploration strategy is only used for training. To act on what was learned without
+    /// exploring, use [best_action()](AgentTrainer::best_action),
+    /// [rollout()](AgentTrainer::rollout) or
+    /// [GreedyExploration](strategy::explore::GreedyExploration).
+    ///
+    /// To train many episodes, each starting with an agent drawn from a sampler, use
+    /// [train_sampled()](AgentTrainer::train_sampled).
     pub fn train(
         &mut self,
         agent: &mut dyn Agent<S>,
-        learning_strategy: &dyn LearningStrategy<S>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
         termination_strategy: &mut dyn TerminationStrategy<S>,
-        exploration_strategy: &dyn ExplorationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
     ) {
+        self.train_with_observer(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            exploration_strategy,
+            &mut |_| {},
+        );
+    }
+
+    /// Trains this [AgentTrainer] like [train()](AgentTrainer::train), which trains a single
+    /// episode, and calls `observer` with the statistics of the episode once it ends.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::{AgentTrainer, EpisodeStats};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Counter(i32);
+    ///
+    /// impl State for Counter {
+    ///     type A = ();
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<()> {
+    ///         vec![()]
+    ///     }
+    /// }
+    ///
+    /// struct Incrementer(Counter);
+    ///
+    /// impl Agent<Counter> for Incrementer {
+    ///     fn current_state(&self) -> &Counter {
+    ///         &self.0
+    ///     }
+    ///     fn take_action(&mut self, _: &()) {
+    ///         self.0 = Counter(self.0 .0 + 1);
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut curve = Vec::new();
+    /// for _ in 0..3 {
+    ///     trainer.train_with_observer(
+    ///         &mut Incrementer(Counter(0)),
+    ///         &QLearning::new(0.2, 0.9, 0.0),
+    ///         &mut FixedIterations::new(4),
+    ///         &RandomExploration,
+    ///         &mut |stats| curve.push(stats),
+    ///     );
+    /// }
+    ///
+    /// assert_eq!(curve.len(), 3);
+    /// assert_eq!(
+    ///     curve[2],
+    ///     EpisodeStats {
+    ///         episode: 2,
+    ///         steps: 5,
+    ///         total_reward: 15.0,
+    ///         states: Some(5),
+    ///         entries: Some(5),
+    ///     }
+    /// );
+    /// ```
+    pub fn train_with_observer(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+        observer: &mut dyn FnMut(EpisodeStats),
+    ) {
+        self.train_with_observers(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            exploration_strategy,
+            observer,
+            &mut NullObserver,
+        );
+    }
+
+    /// Trains this [AgentTrainer] for `episodes` episodes like [train()](AgentTrainer::train),
+    /// which trains a single episode with a given agent, each with a fresh agent drawn from
+    /// `sampler`, and a fresh termination strategy from `make_termination`. This makes episodes
+    /// start in different states, such as with rare start states oversampled with
+    /// [WeightedStarts], or with a curriculum whose start states get harder as training goes on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::{AgentTrainer, WeightedStarts};
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(10)`, and ends at 10 with
+    /// // a reward of 1
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         if self.0 == 10 { 1.0 } else { 0.0 }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// #     fn is_terminal(&self) -> bool {
+    /// #         self.0 == 10
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 10));
+    /// #     }
+    /// # }
+    ///
+    /// // Random walks from 0 rarely reach the goal, so start near it most of the time
+    /// let starts = WeightedStarts::new([(0, 1.0), (5, 2.0), (8, 4.0)], |x| Walker(Position(x)));
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.train_sampled(
+    ///     starts.with_seed(0),
+    ///     &QLearning::new(0.2, 0.9, 0.0),
+    ///     || FixedIterations::new(30),
+    ///     &RandomExploration,
+    ///     500,
+    /// );
+    /// assert_eq!(trainer.episodes(), 500);
+    /// assert_eq!(trainer.best_action(&Position(9)), Some(1));
+    ///
+    /// // A curriculum: closures are samplers too, and every tenth episode starts one further
+    /// // from the goal
+    /// let mut episode = 0;
+    /// trainer.train_sampled(
+    ///     || {
+    ///         episode += 1;
+    ///         Walker(Position((10 - episode / 10).max(0)))
+    ///     },
+    ///     &QLearning::new(0.2, 0.9, 0.0),
+    ///     || FixedIterations::new(30),
+    ///     &RandomExploration,
+    ///     100,
+    /// );
+    /// assert_eq!(trainer.best_action(&Position(2)), Some(1));
+    /// ```
+    pub fn train_sampled<G, T>(
+        &mut self,
+        mut sampler: impl StartSampler<G>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        mut make_termination: impl FnMut() -> T,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+        episodes: usize,
+    ) where
+        G: Agent<S>,
+        T: TerminationStrategy<S>,
+    {
+        for _ in 0..episodes {
+            self.train(
+                &mut sampler.sample(),
+                learning_strategy,
+                &mut make_termination(),
+                exploration_strategy,
+            );
+        }
+    }
+
+    /// Trains this [AgentTrainer] through the stages of `curriculum` in order, like
+    /// [train()](AgentTrainer::train) with a fresh agent from the stage and a fresh termination
+    /// strategy from `make_termination` for every episode. A stage ends after its number of
+    /// episodes, or as soon as a greedy evaluation reaches its
+    /// [threshold](Stage::with_threshold), and the next stage goes on learning the same values.
+    /// Every evaluation is made like [evaluate()](AgentTrainer::evaluate), with agents from the
+    /// stage. Returns what happened during every stage, in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::{AgentTrainer, Curriculum, Stage};
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(10)`, and ends at 10 with
+    /// // a reward of 1
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         if self.0 == 10 { 1.0 } else { 0.0 }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// #     fn is_terminal(&self) -> bool {
+    /// #         self.0 == 10
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 10));
+    /// #     }
+    /// # }
+    ///
+    /// // Start next to the goal, then ever further from it, and move on as soon as a single
+    /// // greedy episode reaches the goal
+    /// let curriculum = [8, 5, 0].into_iter().fold(Curriculum::new(), |curriculum, start| {
+    ///     curriculum.with_stage(
+    ///         Stage::new(1000, move || Walker(Position(start))).with_threshold(1.0, 10, 1),
+    ///     )
+    /// });
+    /// let mut trainer = AgentTrainer::new();
+    /// let reports = trainer.train_curriculum(
+    ///     curriculum,
+    ///     &QLearning::new(0.2, 0.9, 0.0),
+    ///     || FixedIterations::new(30),
+    ///     &RandomExploration,
+    /// );
+    ///
+    /// assert_eq!(reports.len(), 3);
+    /// assert!(reports.iter().all(|report| report.passed));
+    /// assert_eq!(trainer.episodes(), reports.iter().map(|r| r.episodes).sum::<usize>());
+    /// assert_eq!(trainer.best_action(&Position(0)), Some(1));
+    /// ```
+    pub fn train_curriculum<G, T>(
+        &mut self,
+        curriculum: Curriculum<G>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        mut make_termination: impl FnMut() -> T,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+    ) -> Vec<StageReport>
+    where
+        G: Agent<S>,
+        T: TerminationStrategy<S>,
+    {
+        let mut reports = Vec::with_capacity(curriculum.len());
+        for mut stage in curriculum.stages {
+            let mut report = StageReport {
+                episodes: 0,
+                passed: false,
+                evaluation: None,
+            };
+            while report.episodes < stage.episodes {
+                self.train(
+                    &mut (stage.make_agent)(),
+                    learning_strategy,
+                    &mut make_termination(),
+                    exploration_strategy,
+                );
+                report.episodes += 1;
+                let Some(threshold) = stage.threshold else {
+                    continue;
+                };
+                if !report.episodes.is_multiple_of(threshold.every) {
+                    continue;
+                }
+                let stats = self.evaluate(
+                    &mut stage.make_agent,
+                    &mut make_termination,
+                    threshold.rollouts,
+                );
+                report.passed = stats.mean_reward >= threshold.mean_reward;
+                report.evaluation = Some(stats);
+                if report.passed {
+                    break;
+                }
+            }
+            reports.push(report);
+        }
+        reports
+    }
+
+    /// Trains this [AgentTrainer] like
+    /// [train_with_observer()](AgentTrainer::train_with_observer), and additionally tells
+    /// `transition_observer` about every transition of the episode, including the last one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State, Transition};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    /// struct Counter(i32);
+    ///
+    /// impl State for Counter {
+    ///     type A = ();
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<()> {
+    ///         vec![()]
+    ///     }
+    /// }
+    ///
+    /// struct Incrementer(Counter);
+    ///
+    /// impl Agent<Counter> for Incrementer {
+    ///     fn current_state(&self) -> &Counter {
+    ///         &self.0
+    ///     }
+    ///     fn take_action(&mut self, _: &()) {
+    ///         self.0 = Counter(self.0 .0 + 1);
+    ///     }
+    /// }
+    ///
+    /// let mut trajectory = Vec::new();
+    /// AgentTrainer::new().train_with_observers(
+    ///     &mut Incrementer(Counter(0)),
+    ///     &QLearning::new(0.2, 0.9, 0.0),
+    ///     &mut FixedIterations::new(2),
+    ///     &RandomExploration,
+    ///     &mut |_| {},
+    ///     &mut |t: &Transition<Counter>| {
+    ///         trajectory.push((t.state.clone(), t.reward, t.next_action.is_some()))
+    ///     },
+    /// );
+    ///
+    /// assert_eq!(
+    ///     trajectory,
+    ///     vec![
+    ///         (Counter(0), 1.0, true),
+    ///         (Counter(1), 2.0, true),
+    ///         (Counter(2), 3.0, false),
+    ///     ]
+    /// );
+    /// ```
+    pub fn train_with_observers(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+        observer: &mut dyn FnMut(EpisodeStats),
+        transition_observer: &mut dyn TransitionObserver<S>,
+    ) {
+        termination_strategy.start_episode();
+        let mut steps = 0;
+        let mut total_reward = 0.0;
+        // state, action and reward of the transition that is not learned from yet
+        let mut pending: Option<(S, S::A, f64)> = None;
         loop {
             let s_t = agent.current_state().clone();
-            let action = exploration_strategy.pick_action(agent);
+            self.initialize(&s_t);
+            let action = exploration_strategy.pick_action_with_values(agent, &self.q);
+
+            if let Some((s, a, r)) = pending.take() {
+                let transition = Transition {
+                    state: &s,
+                    action: &a,
+                    reward: r,
+                    next_state: &s_t,
+                    next_action: Some(&action),
+                };
+                let learned = self.visit(&transition);
+                let change = self.learn(learning_strategy, &learned);
+                transition_observer.observe(&transition);
+                termination_strategy.observe_update(change);
+            }
 
             // current action value
             let s_t_next = agent.current_state();
-            let r_t_next = s_t_next.reward();
+            let r_t_next = agent.step_reward(&action) - s_t.cost(&action) - self.step_penalty;
+            steps += 1;
+            total_reward += r_t_next;
 
-            let v = {
-                let old_value = self.q.get(&s_t).and_then(|m| m.get(&action));
-                learning_strategy.value(&self.q.get(s_t_next), &old_value, r_t_next)
-            };
+            if termination_strategy.should_stop(s_t_next) || s_t_next.is_terminal() {
+                self.initialize(s_t_next);
+                let transition = Transition {
+                    state: &s_t,
+                    action: &action,
+                    reward: r_t_next,
+                    next_state: s_t_next,
+                    next_action: None,
+                };
+                let learned = self.visit(&transition);
+                for _ in 0..self.updates_into(s_t_next) {
+                    let change = self.learn(learning_strategy, &learned);
+                    termination_strategy.observe_update(change);
+                }
+                transition_observer.observe(&transition);
+                break;
+            }
+
+            pending = Some((s_t, action, r_t_next));
+        }
+
+        observer(EpisodeStats {
+            episode: self.episodes,
+            steps,
+            total_reward,
+            states: self.q.state_count(),
+            entries: self.q.entry_count(),
+        });
+        self.episodes += 1;
+        self.checkpoint(1, learning_strategy, exploration_strategy);
+    }
+
+    /// Trains this [AgentTrainer] on a single episode of a two-player game, played by the model
+    /// against itself. The players take turns in `agent`, which holds the game, and both pick
+    /// their actions with `exploration_strategy` and learn into the same values, each from its
+    /// own point of view. As the opponent explores too, the values are those of playing against
+    /// an opponent which sometimes makes a random move, so a mostly greedy exploration strategy
+    /// learns values closer to those of perfect play.
+    ///
+    /// The reward of a state is the reward of the player who moved into it, such as `1` for
+    /// winning the game with that move. The game is taken to be zero-sum: whatever the opponent
+    /// gains with its reply is lost by the player, so the player learns from the difference of
+    /// the rewards of its move and of the reply, and from the state it is to move in next. The
+    /// values of a state are therefore the values of the actions of the player to move in it.
+    /// If the players can be in the same position, such as with the same pieces on a board, the
+    /// state has to tell whose turn it is, unless the game is the same for both players.
+    ///
+    /// The episode ends when a [terminal](State::is_terminal) state is reached, which ends the
+    /// game for both players, or when `termination_strategy` decides to stop. In the latter case,
+    /// the last move isn't learned from, as the reply to it isn't known.
+    ///
+    /// The transitions of both players are learned from in turns, so learning strategies which
+    /// keep state between the transitions of an episode, such as
+    /// [MonteCarlo](strategy::learn::MonteCarlo), would mix up the moves of both players; see
+    /// [LearningStrategy::keeps_episode_state()].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `learning_strategy` keeps state between the transitions of an episode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::panic;
+    ///
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::{GreedyExploration, SeededRandomExploration};
+    /// use rurel::strategy::learn::{MonteCarlo, QLearning, VisitMode};
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // Players take one or two sticks in turn, and whoever takes the last stick wins
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Sticks(u32);
+    ///
+    /// impl State for Sticks {
+    ///     type A = u32;
+    ///     fn reward(&self) -> f64 {
+    ///         if self.0 == 0 { 1.0 } else { 0.0 }
+    ///     }
+    ///     fn actions(&self) -> Vec<u32> {
+    ///         (1..=self.0.min(2)).collect()
+    ///     }
+    ///     fn is_terminal(&self) -> bool {
+    ///         self.0 == 0
+    ///     }
+    /// }
+    ///
+    /// struct Game(Sticks);
+    ///
+    /// impl Agent<Sticks> for Game {
+    ///     fn current_state(&self) -> &Sticks {
+    ///         &self.0
+    ///     }
+    ///     fn take_action(&mut self, taken: &u32) {
+    ///         self.0 = Sticks(self.0 .0 - taken);
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// // Seeded, so that the same games are played every time
+    /// let exploration = SeededRandomExploration::new(0);
+    /// for _ in 0..2000 {
+    ///     trainer.train_self_play(
+    ///         &mut Game(Sticks(7)),
+    ///         &QLearning::new(0.2, 1.0, 0.0),
+    ///         &mut FixedIterations::new(100),
+    ///         &exploration,
+    ///     );
+    /// }
+    ///
+    /// // The winning move always leaves a multiple of three sticks
+    /// assert_eq!(trainer.best_action(&Sticks(7)), Some(1));
+    /// assert_eq!(trainer.best_action(&Sticks(5)), Some(2));
+    /// assert_eq!(trainer.best_action(&Sticks(4)), Some(1));
+    /// // With three sticks left, the player to move loses
+    /// assert!(trainer.expected_value(&Sticks(3), &1).unwrap() < -0.5);
+    ///
+    /// // Playing what was learned, the player who moves first wins
+    /// let mut first_player_reward = 0.0;
+    /// trainer.train_self_play_with_observer(
+    ///     &mut Game(Sticks(7)),
+    ///     &QLearning::new(0.2, 1.0, 0.0),
+    ///     &mut FixedIterations::new(100),
+    ///     &GreedyExploration,
+    ///     &mut |stats| first_player_reward = stats.total_reward,
+    /// );
+    /// assert_eq!(first_player_reward, 1.0);
+    ///
+    /// // Monte Carlo learning would mix up the returns of both players
+    /// let refused = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+    ///     trainer.train_self_play(
+    ///         &mut Game(Sticks(7)),
+    ///         &MonteCarlo::new(0.2, 1.0, 0.0, VisitMode::EveryVisit),
+    ///         &mut FixedIterations::new(100),
+    ///         &exploration,
+    ///     )
+    /// }));
+    /// assert!(refused.is_err());
+    /// ```
+    pub fn train_self_play(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+    ) {
+        self.train_self_play_with_observer(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            exploration_strategy,
+            &mut |_| {},
+        );
+    }
+
+    /// Trains this [AgentTrainer] like [train_self_play()](AgentTrainer::train_self_play), which
+    /// trains a single game, and calls `observer` with the statistics of the game once it ends.
+    /// The steps are the moves of both players, and the total reward is that of the player who
+    /// moved first: the rewards of its moves, minus those of the moves of its opponent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `learning_strategy` keeps state between the transitions of an episode.
+    pub fn train_self_play_with_observer(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+        observer: &mut dyn FnMut(EpisodeStats),
+    ) {
+        assert!(
+            !learning_strategy.keeps_episode_state(),
+            "self-play can't learn with a strategy which keeps state between the transitions of \
+             an episode"
+        );
+        let mut steps = 0;
+        let mut total_reward = 0.0;
+        // for both players: the state, action and reward of its last move, which is learned
+        // from once the reply of the opponent is known
+        let mut pending: [Option<(S, S::A, f64)>; 2] = [None, None];
+        termination_strategy.start_episode();
+        let mut player = 0;
+        // the reward of the last move, which the player to move lost
+        let mut last_reward = 0.0;
+        loop {
+            let s_t = agent.current_state().clone();
+            self.initialize(&s_t);
+            let action = exploration_strategy.pick_action_with_values(agent, &self.q);
+
+            if let Some((s, a, r)) = pending[player].take() {
+                let transition = Transition {
+                    state: &s,
+                    action: &a,
+                    reward: r - last_reward,
+                    next_state: &s_t,
+                    next_action: Some(&action),
+                };
+                let learned = self.visit(&transition);
+                let change = self.learn(learning_strategy, &learned);
+                termination_strategy.observe_update(change);
+            }
 
-            self.q.entry(s_t).or_default().insert(action, v);
+            let s_t_next = agent.current_state();
+            let r_t_next = agent.step_reward(&action) - s_t.cost(&action) - self.step_penalty;
+            steps += 1;
+            total_reward += if player == 0 { r_t_next } else { -r_t_next };
 
-            if termination_strategy.should_stop(s_t_next) {
+            if termination_strategy.should_stop(s_t_next) || s_t_next.is_terminal() {
+                self.initialize(s_t_next);
+                if s_t_next.is_terminal() {
+                    let transition = Transition {
+                        state: &s_t,
+                        action: &action,
+                        reward: r_t_next,
+                        next_state: s_t_next,
+                        next_action: None,
+                    };
+                    let learned = self.visit(&transition);
+                    for _ in 0..self.terminal_updates {
+                        let change = self.learn(learning_strategy, &learned);
+                        termination_strategy.observe_update(change);
+                    }
+                }
+                // the opponent is to move in the state the episode ended in
+                if let Some((s, a, r)) = pending[1 - player].take() {
+                    let transition = Transition {
+                        state: &s,
+                        action: &a,
+                        reward: r - r_t_next,
+                        next_state: s_t_next,
+                        next_action: None,
+                    };
+                    let learned = self.visit(&transition);
+                    for _ in 0..self.updates_into(s_t_next) {
+                        let change = self.learn(learning_strategy, &learned);
+                        termination_strategy.observe_update(change);
+                    }
+                }
                 break;
             }
+
+            pending[player] = Some((s_t, action, r_t_next));
+            last_reward = r_t_next;
+            player = 1 - player;
         }
+
+        observer(EpisodeStats {
+            episode: self.episodes,
+            steps,
+            total_reward,
+            states: self.q.state_count(),
+            entries: self.q.entry_count(),
+        });
+        self.episodes += 1;
+        self.checkpoint(1, learning_strategy, exploration_strategy);
     }
 }
 
-impl<S: State> Default for AgentTrainer<S> {
+impl<S, V> AgentTrainer<S, V>
+where
+    S: State,
+    V: QValue,
+{
+    /// Fetches the learned values for the given state, by `Action`, or `None` if no value was
+    /// learned.
+    pub fn expected_values(&self, state: &S) -> Option<&HashMap<S::A, V>> {
+        // XXX: make associated const with empty map and remove Option?
+        self.q.get(state)
+    }
+
+    /// Iterates over every learned value, with the state and action it was learned for, in no
+    /// particular order. Unlike [export_learned_values()](AgentTrainer::export_learned_values),
+    /// this borrows the values instead of cloning them, so they can be streamed elsewhere
+    /// without doubling the memory they take.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // Positions on a line, rewarded with their number, with the steps -1 and 1
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         self.0 as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     (0..3)
+    ///         .map(|x| (Position(x), [(-1, -1.0), (1, x as f64)].into_iter().collect()))
+    ///         .collect(),
+    /// );
+    ///
+    /// let mut lines: Vec<String> = trainer
+    ///     .iter_values()
+    ///     .map(|(state, action, value)| format!("{},{},{}", state.0, action, value))
+    ///     .collect();
+    /// lines.sort();
+    /// assert_eq!(lines, ["0,-1,-1", "0,1,0", "1,-1,-1", "1,1,1", "2,-1,-1", "2,1,2"]);
+    /// ```
+    pub fn iter_values(&self) -> impl Iterator<Item = (&S, &S::A, V)> {
+        self.q.iter().flat_map(|(state, values)| {
+            values
+                .iter()
+                .map(move |(action, &value)| (state, action, value))
+        })
+    }
+
+    /// Returns the number of states values were learned for, without cloning the values like
+    /// [export_learned_values()](AgentTrainer::export_learned_values), for example to follow how
+    /// the table grows during long runs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // Positions on a line, rewarded with their number, with the steps -1 and 1
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         self.0 as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// assert_eq!((trainer.state_count(), trainer.entry_count()), (0, 0));
+    /// trainer.import_state(
+    ///     [
+    ///         (Position(0), [(1, 0.5)].into_iter().collect()),
+    ///         (Position(1), [(-1, 0.0), (1, 1.0)].into_iter().collect()),
+    ///     ]
+    ///     .into_iter()
+    ///     .collect(),
+    /// );
+    /// assert_eq!(trainer.state_count(), 2);
+    /// assert_eq!(trainer.entry_count(), 3);
+    /// ```
+    pub fn state_count(&self) -> usize {
+        self.q.len()
+    }
+
+    /// Returns the number of values learned, one for every state and action. See
+    /// [state_count()](AgentTrainer::state_count) for an example.
+    pub fn entry_count(&self) -> usize {
+        self.q.values().map(HashMap::len).sum()
+    }
+
+    /// Returns the mean of all learned values, or `None` if no value was learned, for example to
+    /// follow how the learned values develop during training.
+    pub fn mean_value(&self) -> Option<f64> {
+        let (sum, count) = self
+            .iter_values()
+            .fold((0.0, 0), |(sum, count), (_, _, v)| {
+                (sum + v.to_f64(), count + 1)
+            });
+        if count == 0 {
+            None
+        } else {
+            Some(sum / count as f64)
+        }
+    }
+
+    /// Returns a clone of the entire learned state to be saved or used elsewhere.
+    pub fn export_learned_values(&self) -> HashMap<S, HashMap<S::A, V>> {
+        self.q.clone()
+    }
+
+    /// Returns a clone of the entire learned state like
+    /// [export_learned_values()](AgentTrainer::export_learned_values), with the states and
+    /// actions in order, so that dumps of it are the same every time, such as for comparing them
+    /// with golden files.
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // Positions on a line, rewarded with their number, with the steps -1 and 1
+    /// # #[derive(PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Debug)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         self.0 as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     (0..3)
+    ///         .map(|x| (Position(x), [(1, x as f64), (-1, -1.0)].into_iter().collect()))
+    ///         .collect(),
+    /// );
+    ///
+    /// let dump = format!("{:?}", trainer.export_learned_values_sorted());
+    /// assert_eq!(
+    ///     dump,
+    ///     "{Position(0): {-1: -1.0, 1: 0.0}, \
+    ///       Position(1): {-1: -1.0, 1: 1.0}, \
+    ///       Position(2): {-1: -1.0, 1: 2.0}}"
+    /// );
+    /// ```
+    pub fn export_learned_values_sorted(&self) -> BTreeMap<S, BTreeMap<S::A, V>>
+    where
+        S: Ord,
+        S::A: Ord,
+    {
+        self.q
+            .iter()
+            .map(|(state, values)| {
+                let values = values.iter().map(|(a, v)| (a.clone(), *v)).collect();
+                (state.clone(), values)
+            })
+            .collect()
+    }
+
+    // Returns a reference to the learned state.
+    pub fn learned_values(&self) -> &HashMap<S, HashMap<S::A, V>> {
+        &self.q
+    }
+
+    /// Imports a state, completely replacing any learned progress
+    pub fn import_state(&mut self, q: HashMap<S, HashMap<S::A, V>>) {
+        self.q = q;
+    }
+
+    /// Merges the values learned by `other` into this `AgentTrainer`. Values for actions that
+    /// were only learned by one of the trainers are kept as they are. For actions that were
+    /// learned by both, the value with the larger magnitude is kept. Use
+    /// [merge_with()](AgentTrainer::merge_with) to combine them differently.
+    pub fn merge(&mut self, other: &AgentTrainer<S, V>) {
+        self.merge_with(other, |a, b| {
+            if b.to_f64().abs() > a.to_f64().abs() {
+                b
+            } else {
+                a
+            }
+        });
+    }
+
+    /// Merges the values learned by `other` into this `AgentTrainer`, like
+    /// [merge()](AgentTrainer::merge). For actions that were learned by both trainers,
+    /// `combine` is called with the value of this trainer and the value of `other`, and its result
+    /// is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = bool;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<bool> {
+    ///         vec![false, true]
+    ///     }
+    /// }
+    ///
+    /// let mut left = AgentTrainer::new();
+    /// left.import_state(
+    ///     [
+    ///         (MyState(0), [(false, 1.0)].into_iter().collect()),
+    ///         (MyState(1), [(false, 2.0), (true, -4.0)].into_iter().collect()),
+    ///     ]
+    ///     .into_iter()
+    ///     .collect(),
+    /// );
+    /// let mut right = AgentTrainer::new();
+    /// right.import_state(
+    ///     [
+    ///         (MyState(1), [(true, 3.0)].into_iter().collect()),
+    ///         (MyState(2), [(true, 5.0)].into_iter().collect()),
+    ///     ]
+    ///     .into_iter()
+    ///     .collect(),
+    /// );
+    ///
+    /// let mut mean = AgentTrainer::new();
+    /// mean.import_state(left.export_learned_values());
+    /// mean.merge_with(&right, |a, b| (a + b) / 2.0);
+    /// assert_eq!(mean.expected_value(&MyState(0), &false), Some(1.0));
+    /// assert_eq!(mean.expected_value(&MyState(1), &false), Some(2.0));
+    /// assert_eq!(mean.expected_value(&MyState(1), &true), Some(-0.5));
+    /// assert_eq!(mean.expected_value(&MyState(2), &true), Some(5.0));
+    ///
+    /// // By default, the value with the larger magnitude is kept
+    /// left.merge(&right);
+    /// assert_eq!(left.expected_value(&MyState(1), &true), Some(-4.0));
+    /// assert_eq!(left.expected_value(&MyState(2), &true), Some(5.0));
+    /// ```
+    pub fn merge_with<F>(&mut self, other: &AgentTrainer<S, V>, mut combine: F)
+    where
+        F: FnMut(V, V) -> V,
+    {
+        for (state, other_values) in &other.q {
+            let values = self.q.entry(state.clone()).or_default();
+            for (action, &other_value) in other_values {
+                values
+                    .entry(action.clone())
+                    .and_modify(|value| *value = combine(*value, other_value))
+                    .or_insert(other_value);
+            }
+        }
+    }
+
+    /// Returns the [Policy] which takes the [best_action()](AgentTrainer::best_action) in every
+    /// state for which values were learned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = char;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<char> {
+    ///         vec!['a', 'b']
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     [
+    ///         (MyState(0), [('a', 0.5), ('b', 2.0)].into_iter().collect()),
+    ///         (MyState(1), [('a', -1.0)].into_iter().collect()),
+    ///     ]
+    ///     .into_iter()
+    ///     .collect(),
+    /// );
+    /// let policy = trainer.extract_policy();
+    /// assert_eq!(policy.len(), 2);
+    /// assert_eq!(policy.action(&MyState(0)), Some('b'));
+    /// assert_eq!(policy.action(&MyState(1)), Some('a'));
+    /// assert_eq!(policy.action(&MyState(2)), None);
+    /// ```
+    pub fn extract_policy(&self) -> Policy<S> {
+        Policy {
+            actions: self
+                .q
+                .keys()
+                .filter_map(|state| Some((state.clone(), self.best_action(state)?)))
+                .collect(),
+        }
+    }
+}
+
+impl<S, V, Q> Default for AgentTrainer<S, V, Q>
+where
+    S: State,
+    V: QValue,
+    Q: ValueStore<S, V> + Default,
+{
     fn default() -> Self {
-        Self::new()
+        AgentTrainer::with_store(Q::default())
     }
 }
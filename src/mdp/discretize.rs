@@ -0,0 +1,271 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for learning tabular values of states with continuous observations.
+
+use std::hash::{Hash, Hasher};
+
+use crate::mdp::wrap::{forward_agent, forward_state};
+use crate::mdp::{Agent, State};
+
+/// Maps a continuous observation of `N` values onto a discrete key, so that the values of states
+/// with the same key are learned together.
+pub trait Discretizer<const N: usize> {
+    /// The discrete key of an observation.
+    type Key: Eq + Hash + Clone;
+
+    /// Returns the discrete key of `observation`.
+    fn discretize(&self, observation: &[f32; N]) -> Self::Key;
+}
+
+/// A [Discretizer] which divides the range of every dimension of the observation into `tiles`
+/// tiles of equal width, which together form a grid. With more than one tiling, every tiling is
+/// a copy of this grid shifted by a fraction of a tile, and the key of an observation is its tile
+/// in every tiling. Observations are then only learned together if they share the tile in every
+/// tiling, which gives a finer grid whose tiles are all the same width, but can start anywhere.
+///
+/// Observations outside of the range fall into the tiles at its edges.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Discretizer, TileCoding};
+///
+/// // Two tiles from 0 to 1, and a second tiling shifted by half a tile
+/// let tiling = TileCoding::new([0.0], [1.0], 2, 2);
+/// assert_eq!(tiling.discretize(&[0.1]), vec![0, 0]);
+/// assert_eq!(tiling.discretize(&[0.3]), vec![0, 1]);
+/// assert_eq!(tiling.discretize(&[0.6]), vec![1, 1]);
+/// assert_eq!(tiling.discretize(&[7.0]), vec![2, 2]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileCoding<const N: usize> {
+    low: [f32; N],
+    high: [f32; N],
+    tiles: usize,
+    tilings: usize,
+}
+
+impl<const N: usize> TileCoding<N> {
+    /// Constructs the tile coding of the observations from `low` to `high` in every dimension,
+    /// with `tiles` tiles per dimension in each of the `tilings` tilings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tiles` or `tilings` is `0`, or if `high` isn't greater than `low` in every
+    /// dimension.
+    pub fn new(low: [f32; N], high: [f32; N], tiles: usize, tilings: usize) -> TileCoding<N> {
+        assert!(
+            tiles > 0 && tilings > 0,
+            "the number of tiles and tilings must be positive"
+        );
+        assert!(
+            low.iter().zip(&high).all(|(low, high)| high > low),
+            "the upper bound of every dimension must be greater than its lower bound"
+        );
+        TileCoding {
+            low,
+            high,
+            tiles,
+            tilings,
+        }
+    }
+}
+
+impl<const N: usize> Discretizer<N> for TileCoding<N> {
+    /// The index of the tile of the observation in every tiling, in the order of the tilings.
+    type Key = Vec<usize>;
+
+    fn discretize(&self, observation: &[f32; N]) -> Vec<usize> {
+        // shifted tilings need one more tile per dimension to cover the range
+        let tiles = self.tiles + 1;
+        (0..self.tilings)
+            .map(|tiling| {
+                let offset = tiling as f32 / self.tilings as f32;
+                (0..N).rev().fold(0, |index, d| {
+                    let width = (self.high[d] - self.low[d]) / self.tiles as f32;
+                    let tile = ((observation[d] - self.low[d]) / width + offset).floor();
+                    index * tiles + (tile.max(0.0) as usize).min(self.tiles)
+                })
+            })
+            .collect()
+    }
+}
+
+/// A state which wraps a state with a continuous observation, and is compared and hashed by the
+/// discrete key of that observation alone. All other methods are forwarded to the wrapped state.
+///
+/// This allows learning tabular values of environments with continuous observations, such as
+/// positions and velocities, without the DQN. The observation of the wrapped state is what it
+/// converts into, like the input of the DQN. Train with a [DiscretizedAgent], which wraps the
+/// agent, so that all states it arrives at are discretized.
+///
+/// # Example
+///
+/// ```
+/// use std::hash::{Hash, Hasher};
+///
+/// use rurel::mdp::{Agent, Discretized, DiscretizedAgent, State, TileCoding};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// #[derive(PartialEq, Clone)]
+/// struct Position(f32);
+///
+/// impl Eq for Position {}
+/// impl Hash for Position {
+///     fn hash<H: Hasher>(&self, hasher: &mut H) {
+///         self.0.to_bits().hash(hasher);
+///     }
+/// }
+///
+/// impl From<Position> for [f32; 1] {
+///     fn from(position: Position) -> [f32; 1] {
+///         [position.0]
+///     }
+/// }
+///
+/// impl State for Position {
+///     type A = i32;
+///     fn reward(&self) -> f64 {
+///         if self.0 > 0.85 { 1.0 } else { 0.0 }
+///     }
+///     fn actions(&self) -> Vec<i32> {
+///         vec![-1, 1]
+///     }
+/// }
+///
+/// struct Walker(Position);
+///
+/// impl Agent<Position> for Walker {
+///     fn current_state(&self) -> &Position {
+///         &self.0
+///     }
+///     fn take_action(&mut self, step: &i32) {
+///         self.0 = Position((self.0 .0 + 0.1 * *step as f32).clamp(0.0, 1.0));
+///     }
+/// }
+///
+/// let tiling = TileCoding::new([0.0], [1.0], 10, 1);
+/// // Nearby positions share their values
+/// assert!(Discretized::new(Position(0.51), &tiling) == Discretized::new(Position(0.52), &tiling));
+///
+/// let mut trainer = AgentTrainer::new();
+/// trainer.train(
+///     &mut DiscretizedAgent::new(Walker(Position(0.0)), tiling.clone()),
+///     &QLearning::new(0.2, 0.9, 0.0),
+///     &mut FixedIterations::new(2000),
+///     &RandomExploration,
+/// );
+/// assert_eq!(trainer.best_action(&Discretized::new(Position(0.55), &tiling)), Some(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Discretized<S, K> {
+    state: S,
+    key: K,
+}
+
+impl<S, K> Discretized<S, K> {
+    /// Wraps `state`, with the key `discretizer` gives its observation.
+    pub fn new<D, const N: usize>(state: S, discretizer: &D) -> Discretized<S, K>
+    where
+        S: Clone + Into<[f32; N]>,
+        D: Discretizer<N, Key = K>,
+    {
+        let key = discretizer.discretize(&state.clone().into());
+        Discretized { state, key }
+    }
+
+    /// Returns the wrapped state.
+    pub fn inner(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns the wrapped state, consuming this state.
+    pub fn into_inner(self) -> S {
+        self.state
+    }
+
+    /// Returns the discrete key of the observation of the wrapped state.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<S, K: PartialEq> PartialEq for Discretized<S, K> {
+    fn eq(&self, other: &Discretized<S, K>) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<S, K: Eq> Eq for Discretized<S, K> {}
+
+impl<S, K: Hash> Hash for Discretized<S, K> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.key.hash(hasher);
+    }
+}
+
+forward_state!(
+    impl[S: State, K: Eq + Hash + Clone] State for Discretized<S, K>,
+    inner: S = |discretized| discretized.state,
+);
+
+/// An agent which wraps another agent, and is always in the [Discretized] version of the state
+/// of the agent it wraps. See [Discretized] for an example.
+pub struct DiscretizedAgent<G, S, D, const N: usize>
+where
+    D: Discretizer<N>,
+{
+    agent: G,
+    discretizer: D,
+    state: Discretized<S, D::Key>,
+}
+
+impl<G, S, D, const N: usize> DiscretizedAgent<G, S, D, N>
+where
+    G: Agent<S>,
+    S: State + Into<[f32; N]>,
+    D: Discretizer<N>,
+{
+    /// Wraps `agent`, discretizing the observations of its states with `discretizer`.
+    pub fn new(agent: G, discretizer: D) -> DiscretizedAgent<G, S, D, N> {
+        let state = Discretized::new(agent.current_state().clone(), &discretizer);
+        DiscretizedAgent {
+            agent,
+            discretizer,
+            state,
+        }
+    }
+
+    /// Returns the wrapped agent.
+    pub fn inner(&self) -> &G {
+        &self.agent
+    }
+
+    /// Returns the wrapped agent, consuming this agent.
+    pub fn into_inner(self) -> G {
+        self.agent
+    }
+
+    /// Returns the discretizer of the observations.
+    pub fn discretizer(&self) -> &D {
+        &self.discretizer
+    }
+
+    /// Discretizes the state the wrapped agent arrived at.
+    fn update_state(&mut self) {
+        self.state = Discretized::new(self.agent.current_state().clone(), &self.discretizer);
+    }
+}
+
+forward_agent!(
+    impl[G: Agent<S>, S: State + Into<[f32; N]>, D: Discretizer<N>, const N: usize]
+        Agent<Discretized<S, D::Key>> for DiscretizedAgent<G, S, D, N>,
+    agent: agent,
+    state: state,
+    update: update_state,
+);
@@ -0,0 +1,196 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for training on environments defined in the style of Gym, which are reset and stepped.
+
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::mdp::{Agent, State};
+
+/// An environment in the style of Gym: it is [reset](Environment::reset) at the start of every
+/// episode, and [stepped](Environment::step) with an action, which returns what is observed
+/// after it, its reward, and whether the episode is over. Train on it with a [GymAgent], which
+/// turns it into an [Agent] and the observations into [State]s.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, Environment, GymAgent};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// // A corridor of four cells, with the exit at the right end
+/// struct Corridor {
+///     position: i32,
+/// }
+///
+/// impl Environment for Corridor {
+///     type Obs = i32;
+///     type Action = i32;
+///     fn reset(&mut self) -> i32 {
+///         self.position = 0;
+///         self.position
+///     }
+///     fn step(&mut self, step: &i32) -> (i32, f64, bool) {
+///         self.position = (self.position + step).clamp(0, 3);
+///         let done = self.position == 3;
+///         (self.position, if done { 1.0 } else { 0.0 }, done)
+///     }
+///     fn action_space(&self) -> Vec<i32> {
+///         vec![-1, 1]
+///     }
+/// }
+///
+/// let mut agent = GymAgent::new(Corridor { position: 0 });
+/// let mut trainer = AgentTrainer::new();
+/// for _ in 0..100 {
+///     agent.reset();
+///     trainer.train(
+///         &mut agent,
+///         &QLearning::new(0.2, 0.9, 0.0),
+///         &mut FixedIterations::new(100),
+///         &RandomExploration,
+///     );
+/// }
+///
+/// agent.reset();
+/// assert_eq!(trainer.best_action(agent.current_state()), Some(1));
+/// ```
+pub trait Environment {
+    /// What is observed of the environment, which the values are learned for.
+    type Obs: Eq + Hash + Clone;
+    /// An action that can be taken in the environment.
+    type Action: Eq + Hash + Clone;
+
+    /// Starts a new episode, and returns what is observed at its start.
+    fn reset(&mut self) -> Self::Obs;
+
+    /// Takes `action`, and returns what is observed after it, the reward for it, and whether the
+    /// episode is over.
+    fn step(&mut self, action: &Self::Action) -> (Self::Obs, f64, bool);
+
+    /// Returns the actions that can be taken in the environment.
+    fn action_space(&self) -> Vec<Self::Action>;
+}
+
+/// A state of an [Environment], as seen by a [GymAgent]: what was observed, the reward for the
+/// step that led to it, and whether the episode is over. It is compared and hashed by the
+/// observation and whether the episode is over, so the values of an observation are learned
+/// together, whatever the rewards of the steps that led to it. Its actions are the
+/// [action space](Environment::action_space) of the environment.
+#[derive(Debug, Clone)]
+pub struct GymState<O, A> {
+    observation: O,
+    reward: f64,
+    done: bool,
+    actions: Arc<[A]>,
+}
+
+impl<O, A> GymState<O, A> {
+    /// Returns what was observed.
+    pub fn observation(&self) -> &O {
+        &self.observation
+    }
+}
+
+impl<O: PartialEq, A> PartialEq for GymState<O, A> {
+    fn eq(&self, other: &GymState<O, A>) -> bool {
+        self.observation == other.observation && self.done == other.done
+    }
+}
+
+impl<O: Eq, A> Eq for GymState<O, A> {}
+
+impl<O: Hash, A> Hash for GymState<O, A> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.observation.hash(hasher);
+        self.done.hash(hasher);
+    }
+}
+
+impl<O, A> State for GymState<O, A>
+where
+    O: Eq + Hash + Clone,
+    A: Eq + Hash + Clone,
+{
+    type A = A;
+
+    /// The reward for the step that led to this state, or `0` at the start of an episode.
+    fn reward(&self) -> f64 {
+        self.reward
+    }
+
+    fn actions(&self) -> Vec<A> {
+        self.actions.to_vec()
+    }
+
+    /// Whether the environment said that the episode is over.
+    fn is_terminal(&self) -> bool {
+        self.done
+    }
+}
+
+/// An agent which steps an [Environment], and is always in the [GymState] of what it observed
+/// last. See [Environment] for an example.
+pub struct GymAgent<E: Environment> {
+    env: E,
+    state: GymState<E::Obs, E::Action>,
+}
+
+impl<E: Environment> GymAgent<E> {
+    /// Wraps `env`, and resets it to start the first episode.
+    pub fn new(mut env: E) -> GymAgent<E> {
+        let observation = env.reset();
+        let actions = env.action_space().into();
+        GymAgent {
+            env,
+            state: GymState {
+                observation,
+                reward: 0.0,
+                done: false,
+                actions,
+            },
+        }
+    }
+
+    /// Resets the environment to start a new episode.
+    pub fn reset(&mut self) {
+        let observation = self.env.reset();
+        self.state = GymState {
+            observation,
+            reward: 0.0,
+            done: false,
+            actions: self.state.actions.clone(),
+        };
+    }
+
+    /// Returns the wrapped environment.
+    pub fn inner(&self) -> &E {
+        &self.env
+    }
+
+    /// Returns the wrapped environment, consuming this agent.
+    pub fn into_inner(self) -> E {
+        self.env
+    }
+}
+
+impl<E: Environment> Agent<GymState<E::Obs, E::Action>> for GymAgent<E> {
+    fn current_state(&self) -> &GymState<E::Obs, E::Action> {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &E::Action) {
+        let (observation, reward, done) = self.env.step(action);
+        self.state = GymState {
+            observation,
+            reward,
+            done,
+            actions: self.state.actions.clone(),
+        };
+    }
+}
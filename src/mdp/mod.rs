@@ -5,6 +5,19 @@
 use std::hash::Hash;
 
 use rand::seq::SliceRandom;
+use rand::RngCore;
+
+pub use self::discretize::{Discretized, DiscretizedAgent, Discretizer, TileCoding};
+pub use self::gym::{Environment, GymAgent, GymState};
+pub use self::observation::{Observable, Observed, ObservedAgent};
+pub use self::shaped::{RewardShaped, RewardShaping, ShapedAgent};
+use crate::RurelError;
+
+mod discretize;
+mod gym;
+mod observation;
+mod shaped;
+pub(crate) mod wrap;
 
 /// A `State` is something which has a reward, and has a certain set of actions associated with it.
 /// The type of the actions must be defined as the associated type `A`.
@@ -13,17 +26,263 @@ pub trait State: Eq + Hash + Clone {
     type A: Eq + Hash + Clone;
 
     /// The reward for when an `Agent` arrives at this `State`.
-    /// 
+    ///
     /// Rewards are relative to each other, and are traditionally smaller integers.
     fn reward(&self) -> f64;
     /// The set of actions that can be taken from this `State`, to arrive in another `State`.
     fn actions(&self) -> Vec<Self::A>;
+    /// Whether this `State` ends an episode, such as a finished game. Training ends an episode
+    /// as soon as the `Agent` arrives at a terminal state, whether or not the termination
+    /// strategy stops it. The default implementation returns `false`, so that episodes are only
+    /// ended by termination strategies.
+    ///
+    /// Together with a step penalty, such as
+    /// [AgentTrainer::with_step_penalty()](crate::AgentTrainer::with_step_penalty), this allows
+    /// environments which only reward the outcome of an episode.
+    ///
+    /// Nothing follows a terminal state, so the learning strategies and the DQN don't bootstrap
+    /// any future value from it: the value of the action leading into it is just the reward of
+    /// arriving there. An episode that is merely stopped by a termination strategy, such as
+    /// after a fixed number of steps, still bootstraps from the state it stopped in.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use rurel::mdp::{State, Transition};
+    /// use rurel::strategy::learn::{LearningStrategy, QLearning};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Game {
+    ///     over: bool,
+    /// }
+    ///
+    /// impl State for Game {
+    ///     type A = ();
+    ///     fn reward(&self) -> f64 {
+    ///         if self.over { 1.0 } else { 0.0 }
+    ///     }
+    ///     fn actions(&self) -> Vec<()> {
+    ///         vec![()]
+    ///     }
+    ///     fn is_terminal(&self) -> bool {
+    ///         self.over
+    ///     }
+    /// }
+    ///
+    /// let playing = Game { over: false };
+    /// let over = Game { over: true };
+    /// // Whatever was learned for the finished game is ignored
+    /// let mut q = HashMap::from([
+    ///     (playing.clone(), HashMap::from([((), 0.0)])),
+    ///     (over.clone(), HashMap::from([((), 100.0)])),
+    /// ]);
+    /// let transition = Transition {
+    ///     state: &playing,
+    ///     action: &(),
+    ///     reward: over.reward(),
+    ///     next_state: &over,
+    ///     next_action: None,
+    /// };
+    /// QLearning::new(1.0, 0.9, 0.0).learn(&mut q, &transition);
+    /// assert_eq!(q[&playing][&()], 1.0);
+    /// ```
+    fn is_terminal(&self) -> bool {
+        false
+    }
+    /// The reward for when an `Agent` arrives at this `State` by taking `action`, for rewards
+    /// that depend on how the `State` was reached. The default implementation returns the
+    /// [reward](State::reward) of this `State`, whatever the action.
+    ///
+    /// The reward that is learned from for a transition from `s` to `s'` with `action` is the
+    /// [step reward](Agent::step_reward) of the `Agent`, which is `s'.reward_for(action)` unless
+    /// the `Agent` overrides it, less the [cost](State::cost) `s.cost(action)`, less the step
+    /// penalty of the trainer, such as
+    /// [AgentTrainer::with_step_penalty()](crate::AgentTrainer::with_step_penalty).
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::SeededRandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::SinkStates;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// enum Door {
+    ///     Closed,
+    ///     Open,
+    /// }
+    ///
+    /// impl State for Door {
+    ///     type A = &'static str;
+    ///     fn reward(&self) -> f64 {
+    ///         if *self == Door::Open { 1.0 } else { 0.0 }
+    ///     }
+    ///     fn actions(&self) -> Vec<&'static str> {
+    ///         vec!["kick", "push"]
+    ///     }
+    ///     fn is_terminal(&self) -> bool {
+    ///         *self == Door::Open
+    ///     }
+    ///     // either way opens the door, but kicking it open breaks it
+    ///     fn reward_for(&self, action: &&'static str) -> f64 {
+    ///         if *action == "kick" { -1.0 } else { self.reward() }
+    ///     }
+    /// }
+    ///
+    /// struct Visitor(Door);
+    ///
+    /// impl Agent<Door> for Visitor {
+    ///     fn current_state(&self) -> &Door {
+    ///         &self.0
+    ///     }
+    ///     fn take_action(&mut self, _: &&'static str) {
+    ///         self.0 = Door::Open;
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let exploration = SeededRandomExploration::new(0);
+    /// for _ in 0..20 {
+    ///     trainer.train(
+    ///         &mut Visitor(Door::Closed),
+    ///         &QLearning::new(0.5, 0.9, 0.0),
+    ///         &mut SinkStates,
+    ///         &exploration,
+    ///     );
+    /// }
+    ///
+    /// assert_eq!(trainer.best_action(&Door::Closed), Some("push"));
+    /// assert!(trainer.expected_value(&Door::Closed, &"kick").unwrap() < 0.0);
+    /// ```
+    fn reward_for(&self, _action: &Self::A) -> f64 {
+        self.reward()
+    }
+    /// The cost of taking `action` in this `State`, such as the fuel or time it takes, which
+    /// training subtracts from the reward of the `State` the `Agent` arrives at. The reward that
+    /// is learned from for a transition from `s` to `s'` with `action` is therefore
+    /// `s'.reward_for(action) - s.cost(action)`, from which the step penalty is subtracted as
+    /// well, and it is what the learning strategies use as the reward in their TD targets. The
+    /// default implementation returns `0`, so that all actions are free.
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::SeededRandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::SinkStates;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // A grid of three by two cells, whose upper row is mud
+    /// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// struct Cell(i32, i32);
+    /// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// enum Move {
+    ///     Up,
+    ///     Down,
+    ///     Right,
+    /// }
+    ///
+    /// impl State for Cell {
+    ///     type A = Move;
+    ///     // the goal is the upper right cell
+    ///     fn reward(&self) -> f64 {
+    ///         if *self == Cell(2, 0) { 10.0 } else { 0.0 }
+    ///     }
+    ///     fn actions(&self) -> Vec<Move> {
+    ///         vec![Move::Up, Move::Down, Move::Right]
+    ///     }
+    ///     fn is_terminal(&self) -> bool {
+    ///         *self == Cell(2, 0)
+    ///     }
+    ///     // walking through the mud is slow
+    ///     fn cost(&self, action: &Move) -> f64 {
+    ///         match (self.1, action) {
+    ///             (0, Move::Right) => 5.0,
+    ///             _ => 0.1,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// struct Walker(Cell);
+    ///
+    /// impl Agent<Cell> for Walker {
+    ///     fn current_state(&self) -> &Cell {
+    ///         &self.0
+    ///     }
+    ///     fn take_action(&mut self, action: &Move) {
+    ///         let Cell(x, y) = self.0;
+    ///         self.0 = match action {
+    ///             Move::Up => Cell(x, 0),
+    ///             Move::Down => Cell(x, 1),
+    ///             Move::Right => Cell((x + 1).min(2), y),
+    ///         };
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let exploration = SeededRandomExploration::new(0);
+    /// for _ in 0..200 {
+    ///     trainer.train(
+    ///         &mut Walker(Cell(0, 0)),
+    ///         &QLearning::new(0.5, 0.9, 0.0),
+    ///         &mut SinkStates,
+    ///         &exploration,
+    ///     );
+    /// }
+    ///
+    /// // Going around the mud takes longer, but costs less than going through it
+    /// assert_eq!(trainer.best_action(&Cell(0, 0)), Some(Move::Down));
+    /// assert_eq!(trainer.best_action(&Cell(1, 1)), Some(Move::Right));
+    /// ```
+    fn cost(&self, _action: &Self::A) -> f64 {
+        0.0
+    }
     /// Selects a random action that can be taken from this `State`. The default implementation
     /// takes a uniformly distributed random action from the defined set of actions. You may want
     /// to improve the performance by only generating the necessary action.
     fn random_action(&self) -> Self::A {
+        self.random_action_with(&mut rand::thread_rng())
+    }
+    /// Selects a random action that can be taken from this `State`, drawing randomness from
+    /// `rng`. The default implementation takes a uniformly distributed random action from the
+    /// defined set of actions. Override this together with
+    /// [random_action()](State::random_action) to keep seeded runs reproducible.
+    fn random_action_with(&self, rng: &mut dyn RngCore) -> Self::A {
         let actions = self.actions();
-        actions.choose(&mut rand::thread_rng()).cloned().expect("No actions available; perhaps use the SinkStates termination strategy?")
+        actions
+            .choose(rng)
+            .cloned()
+            .expect("No actions available; perhaps use the SinkStates termination strategy?")
+    }
+    /// Selects a random action that can be taken from this `State` like
+    /// [random_action_with()](State::random_action_with), but fails with
+    /// [RurelError::NoActions] instead of panicking if there are no actions.
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::RurelError;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Stuck;
+    ///
+    /// impl State for Stuck {
+    ///     type A = ();
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<()> {
+    ///         vec![]
+    ///     }
+    /// }
+    ///
+    /// let result = Stuck.try_random_action_with(&mut rand::thread_rng());
+    /// assert!(matches!(result, Err(RurelError::NoActions)));
+    /// ```
+    fn try_random_action_with(&self, rng: &mut dyn RngCore) -> Result<Self::A, RurelError> {
+        if self.actions().is_empty() {
+            Err(RurelError::NoActions)
+        } else {
+            Ok(self.random_action_with(rng))
+        }
     }
 }
 
@@ -39,9 +298,106 @@ pub trait Agent<S: State> {
     /// determine the action to be taken.
     fn pick_random_action(&mut self) -> S::A {
         let action = self.current_state().random_action();
-        
+
         self.take_action(&action);
 
         action
     }
+    /// Takes a random action from the set of possible actions from this `State`, drawing
+    /// randomness from `rng`. The default implementation uses
+    /// [State::random_action_with()](trait.State.html#method.random_action_with) to determine the
+    /// action to be taken.
+    fn pick_random_action_with(&mut self, rng: &mut dyn RngCore) -> S::A {
+        let action = self.current_state().random_action_with(rng);
+
+        self.take_action(&action);
+
+        action
+    }
+    /// Returns the reward for the last action taken, `action`, which is what training learns
+    /// from. The default implementation returns the [reward](State::reward_for) of the current
+    /// state for arriving there with `action`.
+    ///
+    /// Agents whose environment advances several ticks for every action, such as with frame
+    /// skipping, can sum up the rewards of these ticks in
+    /// [take_action()](Agent::take_action) and return the sum here, so that the rewards of
+    /// the ticks in between aren't lost.
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::GreedyExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Tick(u32);
+    ///
+    /// impl State for Tick {
+    ///     type A = ();
+    ///     // a reward of 1 on every fourth tick
+    ///     fn reward(&self) -> f64 {
+    ///         if self.0 % 4 == 0 { 1.0 } else { 0.0 }
+    ///     }
+    ///     fn actions(&self) -> Vec<()> {
+    ///         vec![()]
+    ///     }
+    /// }
+    ///
+    /// // Repeats every action for three ticks
+    /// struct FrameSkipping {
+    ///     state: Tick,
+    ///     reward: f64,
+    /// }
+    ///
+    /// impl Agent<Tick> for FrameSkipping {
+    ///     fn current_state(&self) -> &Tick {
+    ///         &self.state
+    ///     }
+    ///     fn take_action(&mut self, _: &()) {
+    ///         self.reward = 0.0;
+    ///         for _ in 0..3 {
+    ///             self.state = Tick(self.state.0 + 1);
+    ///             self.reward += self.state.reward();
+    ///         }
+    ///     }
+    ///     fn step_reward(&self, _: &()) -> f64 {
+    ///         self.reward
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = FrameSkipping { state: Tick(0), reward: 0.0 };
+    /// let mut total_reward = 0.0;
+    /// trainer.train_with_observer(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.9, 0.0),
+    ///     &mut FixedIterations::new(3),
+    ///     &GreedyExploration,
+    ///     &mut |stats| total_reward = stats.total_reward,
+    /// );
+    ///
+    /// // Four actions went through twelve ticks, three of which were rewarded, though the agent
+    /// // only stopped at one of them
+    /// assert_eq!(agent.state.0, 12);
+    /// assert_eq!(total_reward, 3.0);
+    /// ```
+    fn step_reward(&self, action: &S::A) -> f64 {
+        self.current_state().reward_for(action)
+    }
+}
+
+/// A transition from one `State` to another, caused by an `Agent` taking an action.
+pub struct Transition<'a, S: State> {
+    /// The `State` the action was taken in.
+    pub state: &'a S,
+    /// The action that was taken.
+    pub action: &'a S::A,
+    /// The reward that was received after taking the action.
+    pub reward: f64,
+    /// The `State` the `Agent` arrived at after taking the action.
+    pub next_state: &'a S,
+    /// The action that was taken next from `next_state`, or `None` if training ended after this
+    /// transition.
+    pub next_action: Option<&'a S::A>,
 }
@@ -0,0 +1,215 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for learning the values of what an agent observes of its states, rather than of the
+//! states themselves.
+//!
+//! The observation is an associated type of a separate [Observable] trait, and the trainers learn
+//! it through the [Observed] adapter, rather than an associated `type Obs` of [State] defaulting
+//! to `Self` that the trainers would key their values on. Defaults of associated types are
+//! unstable, so such a type would have to be declared by every implementor of [State], and
+//! adding it would break all of them. With the adapter, states that aren't observed are left as
+//! they are, and the trainers keep keying their values on the state.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::mdp::wrap::{forward_agent, forward_state};
+use crate::mdp::{Agent, State};
+
+/// A state of which the agent only observes part, such as a game whose full state includes the
+/// hidden cards of the opponent, or a state with details that don't matter for the task, such as
+/// a step counter. Train with an [ObservedAgent], so that the values are learned for the
+/// observations of the states rather than for the states themselves.
+pub trait Observable: State {
+    /// What the agent observes of a state.
+    type Obs: Eq + Hash + Clone;
+
+    /// Returns what the agent observes of this state.
+    fn observe(&self) -> Self::Obs;
+}
+
+/// A state which wraps an [Observable] state, and is compared and hashed by its
+/// [observation](Observable::observe) alone, so that a trainer learns a single set of values for
+/// all states with the same observation. All other methods are forwarded to the wrapped state,
+/// so the environment behaves as before. Train with an [ObservedAgent], which wraps the agent,
+/// so that all states it arrives at are observed.
+///
+/// The values are only those of the states if the observation keeps everything that the rewards
+/// and the outcomes of the actions depend on. Otherwise, the states with the same observation,
+/// which may have different rewards and lead to different states, are learned as one: their
+/// values are mixed in the proportion in which their states are visited, which depends on the
+/// exploration strategy, and the best action of an observation may be a bad action in some of
+/// its states. The states with the same observation should have the same actions, as the actions
+/// of a state are taken from whichever of them is at hand.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, Observable, Observed, ObservedAgent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// // The number of steps taken makes every state unique, but doesn't matter for the task
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Walk {
+///     position: i32,
+///     steps: u32,
+/// }
+///
+/// impl State for Walk {
+///     type A = i32;
+///     fn reward(&self) -> f64 {
+///         if self.position == 3 { 1.0 } else { 0.0 }
+///     }
+///     fn actions(&self) -> Vec<i32> {
+///         vec![-1, 1]
+///     }
+/// }
+///
+/// impl Observable for Walk {
+///     type Obs = i32;
+///     fn observe(&self) -> i32 {
+///         self.position
+///     }
+/// }
+///
+/// struct Walker(Walk);
+///
+/// impl Agent<Walk> for Walker {
+///     fn current_state(&self) -> &Walk {
+///         &self.0
+///     }
+///     fn take_action(&mut self, step: &i32) {
+///         self.0 = Walk {
+///             position: (self.0.position + step).clamp(0, 3),
+///             steps: self.0.steps + 1,
+///         };
+///     }
+/// }
+///
+/// let mut trainer = AgentTrainer::new();
+/// trainer.train(
+///     &mut ObservedAgent::new(Walker(Walk { position: 0, steps: 0 })),
+///     &QLearning::new(0.2, 0.9, 0.0),
+///     &mut FixedIterations::new(2000),
+///     &RandomExploration,
+/// );
+///
+/// // Only the four positions were learned, and what was learned holds after any number of steps
+/// assert_eq!(trainer.learned_values().len(), 4);
+/// let later = Observed::new(Walk { position: 1, steps: 5000 });
+/// assert_eq!(trainer.best_action(&later), Some(1));
+/// ```
+pub struct Observed<S: Observable> {
+    state: S,
+    observation: S::Obs,
+}
+
+impl<S: Observable> Observed<S> {
+    /// Wraps `state`, with its observation.
+    pub fn new(state: S) -> Observed<S> {
+        let observation = state.observe();
+        Observed { state, observation }
+    }
+
+    /// Returns the wrapped state.
+    pub fn inner(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns the wrapped state, consuming this state.
+    pub fn into_inner(self) -> S {
+        self.state
+    }
+
+    /// Returns the observation of the wrapped state.
+    pub fn observation(&self) -> &S::Obs {
+        &self.observation
+    }
+}
+
+impl<S: Observable> Clone for Observed<S> {
+    fn clone(&self) -> Observed<S> {
+        Observed {
+            state: self.state.clone(),
+            observation: self.observation.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for Observed<S>
+where
+    S: Observable + fmt::Debug,
+    S::Obs: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observed")
+            .field("state", &self.state)
+            .field("observation", &self.observation)
+            .finish()
+    }
+}
+
+impl<S: Observable> PartialEq for Observed<S> {
+    fn eq(&self, other: &Observed<S>) -> bool {
+        self.observation == other.observation
+    }
+}
+
+impl<S: Observable> Eq for Observed<S> {}
+
+impl<S: Observable> Hash for Observed<S> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.observation.hash(hasher);
+    }
+}
+
+forward_state!(
+    impl[S: Observable] State for Observed<S>,
+    inner: S = |observed| observed.state,
+);
+
+/// An agent which wraps another agent, and is always in the [Observed] version of the state of
+/// the agent it wraps. See [Observed] for an example.
+pub struct ObservedAgent<G, S: Observable> {
+    agent: G,
+    state: Observed<S>,
+}
+
+impl<G, S> ObservedAgent<G, S>
+where
+    G: Agent<S>,
+    S: Observable,
+{
+    /// Wraps `agent`, observing its states.
+    pub fn new(agent: G) -> ObservedAgent<G, S> {
+        let state = Observed::new(agent.current_state().clone());
+        ObservedAgent { agent, state }
+    }
+
+    /// Returns the wrapped agent.
+    pub fn inner(&self) -> &G {
+        &self.agent
+    }
+
+    /// Returns the wrapped agent, consuming this agent.
+    pub fn into_inner(self) -> G {
+        self.agent
+    }
+
+    /// Observes the state the wrapped agent arrived at.
+    fn update_state(&mut self) {
+        self.state = Observed::new(self.agent.current_state().clone());
+    }
+}
+
+forward_agent!(
+    impl[G: Agent<S>, S: Observable] Agent<Observed<S>> for ObservedAgent<G, S>,
+    agent: agent,
+    state: state,
+    update: update_state,
+);
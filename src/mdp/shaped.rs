@@ -0,0 +1,268 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for changing the rewards of a state, without changing the state itself.
+
+use std::hash::{Hash, Hasher};
+
+use crate::mdp::wrap::{forward_agent, forward_state};
+use crate::mdp::{Agent, State};
+
+/// How a [RewardShaped] state changes the rewards of the state it wraps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RewardShaping {
+    /// Clamps rewards to the range from `min` to `max`.
+    Clip {
+        /// The lowest reward.
+        min: f64,
+        /// The highest reward.
+        max: f64,
+    },
+    /// Maps rewards linearly from the range `from` onto the range `to`, after clamping them to
+    /// `from`. For example, `Normalize { from: (-20.0, 40.0), to: (-1.0, 1.0) }` turns a reward of
+    /// `40` into `1`, and one of `10` into `0`.
+    Normalize {
+        /// The lowest and highest reward of the wrapped state.
+        from: (f64, f64),
+        /// The lowest and highest reward after normalizing.
+        to: (f64, f64),
+    },
+}
+
+impl RewardShaping {
+    /// Panics unless the ranges of this shaping are valid: a clipping range whose lowest reward
+    /// is at most its highest, or a normalization from a range whose highest reward is greater
+    /// than its lowest, onto a range without NaN bounds.
+    fn check(&self) {
+        match *self {
+            RewardShaping::Clip { min, max } => assert!(
+                min <= max,
+                "the lowest reward must be at most the highest reward, but they are {} and {}",
+                min,
+                max
+            ),
+            RewardShaping::Normalize {
+                from: (from_min, from_max),
+                to: (to_min, to_max),
+            } => {
+                assert!(
+                    from_max > from_min,
+                    "the highest reward to normalize must be greater than the lowest, but they \
+                     are {} and {}",
+                    from_max,
+                    from_min
+                );
+                assert!(
+                    !to_min.is_nan() && !to_max.is_nan(),
+                    "the rewards to normalize onto must be numbers, but they are {} and {}",
+                    to_min,
+                    to_max
+                );
+            }
+        }
+    }
+
+    /// Returns the shaped `reward`.
+    pub fn apply(&self, reward: f64) -> f64 {
+        match *self {
+            RewardShaping::Clip { min, max } => reward.clamp(min, max),
+            RewardShaping::Normalize {
+                from: (from_min, from_max),
+                to: (to_min, to_max),
+            } => {
+                let progress =
+                    (reward.clamp(from_min, from_max) - from_min) / (from_max - from_min);
+                to_min + progress * (to_max - to_min)
+            }
+        }
+    }
+}
+
+/// A state whose reward is the reward of the state it wraps, changed by a [RewardShaping]. All
+/// other methods are forwarded to the wrapped state, and states are compared and hashed by the
+/// wrapped state alone.
+///
+/// This allows tuning the magnitude of rewards, which especially the DQN is sensitive to, without
+/// changing the definition of the environment. Train with a [ShapedAgent], which wraps the
+/// agent, so that all states it arrives at are shaped. With the `dqn` feature enabled, a shaped
+/// state converts into the same network inputs as the state it wraps.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, RewardShaped, RewardShaping, ShapedAgent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// // A `Walker` steps by -1 or 1 between `Position(-1)` and `Position(5)`, and is rewarded with
+/// // its position, but walking off the edge to -1 is rewarded with -1000
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         // Walking off the edge is far worse than anything else
+/// #         if self.0 < 0 { -1000.0 } else { self.0 as f64 }
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 1]
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(-1, 5));
+/// #     }
+/// # }
+///
+/// let shaping = RewardShaping::Clip { min: -10.0, max: 10.0 };
+/// assert_eq!(RewardShaped::new(Position(-1), shaping).reward(), -10.0);
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = ShapedAgent::new(Walker(Position(0)), shaping);
+/// trainer.train(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.9, 0.0),
+///     &mut FixedIterations::new(1000),
+///     &RandomExploration,
+/// );
+/// let value = trainer
+///     .expected_value(&RewardShaped::new(Position(0), shaping), &-1)
+///     .unwrap();
+/// // The clipped reward bounds how bad walking off the edge looks
+/// assert!(value > -100.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RewardShaped<S> {
+    state: S,
+    shaping: RewardShaping,
+}
+
+impl<S> RewardShaped<S> {
+    /// Wraps `state`, changing its reward with `shaping`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lowest reward of a [RewardShaping::Clip] is above the highest, if the
+    /// highest reward of the range a [RewardShaping::Normalize] maps from isn't greater than the
+    /// lowest, or if any bound is NaN, as the rewards would be meaningless.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::panic;
+    ///
+    /// use rurel::mdp::{RewardShaped, RewardShaping};
+    ///
+    /// RewardShaped::new(0, RewardShaping::Clip { min: -1.0, max: 1.0 });
+    /// for shaping in [
+    ///     RewardShaping::Clip { min: 1.0, max: -1.0 },
+    ///     RewardShaping::Clip { min: f64::NAN, max: 1.0 },
+    ///     RewardShaping::Normalize { from: (2.0, 2.0), to: (0.0, 1.0) },
+    ///     RewardShaping::Normalize { from: (0.0, 2.0), to: (0.0, f64::NAN) },
+    /// ] {
+    ///     assert!(panic::catch_unwind(|| RewardShaped::new(0, shaping)).is_err());
+    /// }
+    /// ```
+    pub fn new(state: S, shaping: RewardShaping) -> RewardShaped<S> {
+        shaping.check();
+        RewardShaped { state, shaping }
+    }
+
+    /// Returns the wrapped state.
+    pub fn inner(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns the wrapped state, consuming this state.
+    pub fn into_inner(self) -> S {
+        self.state
+    }
+
+    /// Returns how the reward of the wrapped state is changed.
+    pub fn shaping(&self) -> RewardShaping {
+        self.shaping
+    }
+}
+
+impl<S: PartialEq> PartialEq for RewardShaped<S> {
+    fn eq(&self, other: &RewardShaped<S>) -> bool {
+        self.state == other.state
+    }
+}
+
+impl<S: Eq> Eq for RewardShaped<S> {}
+
+impl<S: Hash> Hash for RewardShaped<S> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.state.hash(hasher);
+    }
+}
+
+forward_state!(
+    impl[S: State] State for RewardShaped<S>,
+    inner: S = |shaped| shaped.state,
+    reward: |shaped, reward| shaped.shaping.apply(reward),
+);
+
+#[cfg(feature = "dqn")]
+impl<S: Into<[f32; N]>, const N: usize> From<RewardShaped<S>> for [f32; N] {
+    fn from(state: RewardShaped<S>) -> [f32; N] {
+        state.state.into()
+    }
+}
+
+/// An agent which wraps another agent, and is always in the [RewardShaped] version of the state
+/// of the agent it wraps. See [RewardShaped] for an example.
+pub struct ShapedAgent<G, S> {
+    agent: G,
+    state: RewardShaped<S>,
+}
+
+impl<G, S> ShapedAgent<G, S>
+where
+    G: Agent<S>,
+    S: State,
+{
+    /// Wraps `agent`, changing the rewards of its states with `shaping`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shaping` isn't valid, like [RewardShaped::new()].
+    pub fn new(agent: G, shaping: RewardShaping) -> ShapedAgent<G, S> {
+        let state = RewardShaped::new(agent.current_state().clone(), shaping);
+        ShapedAgent { agent, state }
+    }
+
+    /// Returns the wrapped agent.
+    pub fn inner(&self) -> &G {
+        &self.agent
+    }
+
+    /// Returns the wrapped agent, consuming this agent.
+    pub fn into_inner(self) -> G {
+        self.agent
+    }
+
+    /// Shapes the state the wrapped agent arrived at.
+    fn update_state(&mut self) {
+        self.state = RewardShaped::new(self.agent.current_state().clone(), self.state.shaping);
+    }
+}
+
+forward_agent!(
+    impl[G: Agent<S>, S: State] Agent<RewardShaped<S>> for ShapedAgent<G, S>,
+    agent: agent,
+    state: state,
+    update: update_state,
+    step_reward: |shaped, reward| shaped.state.shaping.apply(reward),
+);
@@ -0,0 +1,146 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Macros for states and agents which wrap another state or agent, such as
+//! [Observed](super::Observed) and [ObservedAgent](super::ObservedAgent), and forward to it.
+
+/// Implements [State](crate::mdp::State) for a wrapper of a state of type `$inner`, forwarding
+/// every method to the wrapped state, which `$state` returns from the wrapper `$this`. With
+/// `reward`, the rewards of the wrapped state, as returned by `reward()` and `reward_for()`, are
+/// changed by `$map`, from the wrapper `$wrapper` and the `$reward` of the wrapped state.
+///
+/// The wrapper is compared and hashed by its own implementations, so that it can be learned by
+/// something other than the wrapped state.
+macro_rules! forward_state {
+    (
+        impl[$($generics:tt)*] State for $type:ty,
+        inner: $inner:ty = |$this:ident| $state:expr $(,)?
+    ) => {
+        $crate::mdp::wrap::forward_state!(
+            impl[$($generics)*] State for $type,
+            inner: $inner = |$this| $state,
+            reward: |_wrapper, reward| reward,
+        );
+    };
+    (
+        impl[$($generics:tt)*] State for $type:ty,
+        inner: $inner:ty = |$this:ident| $state:expr,
+        reward: |$wrapper:ident, $reward:ident| $map:expr $(,)?
+    ) => {
+        impl<$($generics)*> $crate::mdp::State for $type {
+            type A = <$inner as $crate::mdp::State>::A;
+
+            fn reward(&self) -> f64 {
+                let $this = self;
+                let $reward = $state.reward();
+                let $wrapper = self;
+                $map
+            }
+
+            fn actions(&self) -> Vec<Self::A> {
+                let $this = self;
+                $state.actions()
+            }
+
+            fn is_terminal(&self) -> bool {
+                let $this = self;
+                $state.is_terminal()
+            }
+
+            fn reward_for(&self, action: &Self::A) -> f64 {
+                let $this = self;
+                let $reward = $state.reward_for(action);
+                let $wrapper = self;
+                $map
+            }
+
+            fn cost(&self, action: &Self::A) -> f64 {
+                let $this = self;
+                $state.cost(action)
+            }
+
+            fn random_action(&self) -> Self::A {
+                let $this = self;
+                $state.random_action()
+            }
+
+            fn random_action_with(&self, rng: &mut dyn ::rand::RngCore) -> Self::A {
+                let $this = self;
+                $state.random_action_with(rng)
+            }
+
+            fn try_random_action_with(
+                &self,
+                rng: &mut dyn ::rand::RngCore,
+            ) -> Result<Self::A, $crate::RurelError> {
+                let $this = self;
+                $state.try_random_action_with(rng)
+            }
+        }
+    };
+}
+
+/// Implements [Agent](crate::mdp::Agent) for a wrapper of an agent, which keeps the wrapped
+/// agent in the field `$agent` and its own state, of type `$state`, in the field `$current`.
+/// Every action is taken by the wrapped agent, after which the method `$update` of the wrapper
+/// brings its state up to date. With `step_reward`, the step rewards of the wrapped agent are
+/// changed by `$map`, from the wrapper `$wrapper` and the `$reward` of the wrapped agent.
+macro_rules! forward_agent {
+    (
+        impl[$($generics:tt)*] Agent<$state:ty> for $type:ty,
+        agent: $agent:ident,
+        state: $current:ident,
+        update: $update:ident $(,)?
+    ) => {
+        $crate::mdp::wrap::forward_agent!(
+            impl[$($generics)*] Agent<$state> for $type,
+            agent: $agent,
+            state: $current,
+            update: $update,
+            step_reward: |_wrapper, reward| reward,
+        );
+    };
+    (
+        impl[$($generics:tt)*] Agent<$state:ty> for $type:ty,
+        agent: $agent:ident,
+        state: $current:ident,
+        update: $update:ident,
+        step_reward: |$wrapper:ident, $reward:ident| $map:expr $(,)?
+    ) => {
+        impl<$($generics)*> $crate::mdp::Agent<$state> for $type {
+            fn current_state(&self) -> &$state {
+                &self.$current
+            }
+
+            fn take_action(&mut self, action: &<$state as $crate::mdp::State>::A) {
+                self.$agent.take_action(action);
+                self.$update();
+            }
+
+            fn pick_random_action(&mut self) -> <$state as $crate::mdp::State>::A {
+                let action = self.$agent.pick_random_action();
+                self.$update();
+                action
+            }
+
+            fn pick_random_action_with(
+                &mut self,
+                rng: &mut dyn ::rand::RngCore,
+            ) -> <$state as $crate::mdp::State>::A {
+                let action = self.$agent.pick_random_action_with(rng);
+                self.$update();
+                action
+            }
+
+            fn step_reward(&self, action: &<$state as $crate::mdp::State>::A) -> f64 {
+                let $reward = self.$agent.step_reward(action);
+                let $wrapper = self;
+                $map
+            }
+        }
+    };
+}
+
+pub(crate) use forward_agent;
+pub(crate) use forward_state;
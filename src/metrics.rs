@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for recording learning curves.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::{EpisodeStats, RurelError};
+
+/// Writes a learning curve to a CSV file, one row per episode, with the columns `episode`,
+/// `steps`, `total_reward`, `epsilon` and `mean_q`. The episode statistics are the ones reported
+/// by [AgentTrainer::train_with_observer()](crate::AgentTrainer::train_with_observer), and the
+/// exploration rate and the mean learned value, such as
+/// [AgentTrainer::mean_value()](crate::AgentTrainer::mean_value), are given alongside. Missing
+/// values are written as empty fields.
+///
+/// Every row is flushed to the file as soon as it is recorded, so a run that panics or is
+/// interrupted still leaves a file with every episode recorded until then.
+///
+/// # Example
+///
+/// ```
+/// use rurel::metrics::CsvLog;
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::{DecayingEpsilonGreedy, EpsilonDecay};
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(3)`, and is rewarded with 1
+/// // at 3
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         if self.0 == 3 { 1.0 } else { 0.0 }
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 1]
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(0, 3));
+/// #     }
+/// # }
+///
+/// let path = std::env::temp_dir().join("rurel_learning_curve.csv");
+/// let mut log = CsvLog::create(&path).unwrap();
+/// let mut trainer = AgentTrainer::new();
+/// let exploration = DecayingEpsilonGreedy::new(1.0, 0.1, EpsilonDecay::Exponential(0.99));
+/// for _ in 0..10 {
+///     let mut last = None;
+///     trainer.train_with_observer(
+///         &mut Walker(Position(0)),
+///         &QLearning::new(0.2, 0.9, 0.0),
+///         &mut FixedIterations::new(20),
+///         &exploration,
+///         &mut |stats| last = Some(stats),
+///     );
+///     log.record(&last.unwrap(), Some(exploration.epsilon()), trainer.mean_value())
+///         .unwrap();
+/// }
+///
+/// let csv = std::fs::read_to_string(&path).unwrap();
+/// let mut lines = csv.lines();
+/// assert_eq!(lines.next(), Some("episode,steps,total_reward,epsilon,mean_q"));
+/// assert_eq!(lines.count(), 10);
+/// assert!(csv.lines().nth(1).unwrap().starts_with("0,21,"));
+/// ```
+pub struct CsvLog {
+    writer: BufWriter<File>,
+}
+
+impl CsvLog {
+    /// Creates the CSV file at `path`, replacing any existing file, and writes the header row.
+    pub fn create(path: impl AsRef<Path>) -> Result<CsvLog, RurelError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "episode,steps,total_reward,epsilon,mean_q")?;
+        writer.flush()?;
+        Ok(CsvLog { writer })
+    }
+
+    /// Writes the row of a single episode, with the exploration rate `epsilon` and the mean
+    /// learned value `mean_q` after the episode, if they are known.
+    pub fn record(
+        &mut self,
+        stats: &EpisodeStats,
+        epsilon: Option<f64>,
+        mean_q: Option<f64>,
+    ) -> Result<(), RurelError> {
+        writeln!(
+            self.writer,
+            "{},{},{},{},{}",
+            stats.episode,
+            stats.steps,
+            stats.total_reward,
+            field(epsilon),
+            field(mean_q)
+        )?;
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Formats an optional value as a CSV field, which is empty if there is no value.
+fn field(value: Option<f64>) -> String {
+    value.map_or_else(String::new, |v| v.to_string())
+}
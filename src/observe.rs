@@ -0,0 +1,186 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing observers of the training process.
+
+use std::collections::VecDeque;
+
+use crate::mdp::{State, Transition};
+
+/// Trait for transition observers. A transition observer is told about every transition during
+/// [AgentTrainer::train_with_observers()](../struct.AgentTrainer.html#method.train_with_observers),
+/// for example to log them or to collect a trajectory.
+///
+/// Transitions are observed in the order they happen, each right after the
+/// [LearningStrategy](../strategy/learn/trait.LearningStrategy.html) learned from it. Since
+/// transitions are learned from once the next action is known, a transition is observed after
+/// that next action was chosen and taken. The last transition, after which training stops, is
+/// observed too, with no next action.
+///
+/// Closures taking a [Transition] are transition observers as well.
+pub trait TransitionObserver<S: State> {
+    /// Observes the given transition.
+    fn observe(&mut self, transition: &Transition<S>);
+}
+
+impl<S: State, F: FnMut(&Transition<S>)> TransitionObserver<S> for F {
+    fn observe(&mut self, transition: &Transition<S>) {
+        self(transition)
+    }
+}
+
+/// The transition observer that ignores all transitions.
+pub struct NullObserver;
+
+impl<S: State> TransitionObserver<S> for NullObserver {
+    fn observe(&mut self, _: &Transition<S>) {}
+}
+
+/// A step of a [Trajectory]: a state, the action taken in it, and the reward received for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrajectoryStep<S: State> {
+    /// The state the action was taken in.
+    pub state: S,
+    /// The action that was taken.
+    pub action: S::A,
+    /// The reward that was received after taking the action.
+    pub reward: f64,
+}
+
+/// The steps of an episode, in order.
+pub type Trajectory<S> = Vec<TrajectoryStep<S>>;
+
+/// A transition observer which records the trajectory of every episode it observes, keeping the
+/// `capacity` most recent ones, so that memory stays bounded over long training runs. Attach it
+/// with [AgentTrainer::train_with_observers()](crate::AgentTrainer::train_with_observers). An
+/// episode is recorded once its last transition, which has no next action, is observed.
+///
+/// The trajectories can be inspected to debug what the agent did, or used to pre-train another
+/// model on the actions taken. With the `serde` feature enabled, they can be saved with
+/// [save_json()](TrajectoryRecorder::save_json), and with the `binary` feature with
+/// [save_bincode()](TrajectoryRecorder::save_bincode).
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::observe::TrajectoryRecorder;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+/// struct Counter(i32);
+///
+/// impl State for Counter {
+///     type A = ();
+///     fn reward(&self) -> f64 {
+///         self.0 as f64
+///     }
+///     fn actions(&self) -> Vec<()> {
+///         vec![()]
+///     }
+/// }
+///
+/// struct Incrementer(Counter);
+///
+/// impl Agent<Counter> for Incrementer {
+///     fn current_state(&self) -> &Counter {
+///         &self.0
+///     }
+///     fn take_action(&mut self, _: &()) {
+///         self.0 = Counter(self.0 .0 + 1);
+///     }
+/// }
+///
+/// // Only keep the last two of the five episodes, which start further and further up
+/// let mut recorder = TrajectoryRecorder::new(2);
+/// let mut trainer = AgentTrainer::new();
+/// for start in 0..5 {
+///     trainer.train_with_observers(
+///         &mut Incrementer(Counter(start)),
+///         &QLearning::new(0.2, 0.9, 0.0),
+///         &mut FixedIterations::new(2),
+///         &RandomExploration,
+///         &mut |_| {},
+///         &mut recorder,
+///     );
+/// }
+///
+/// assert_eq!(recorder.len(), 2);
+/// let last = &recorder.trajectories()[1];
+/// let states: Vec<i32> = last.iter().map(|step| step.state.0).collect();
+/// let rewards: Vec<f64> = last.iter().map(|step| step.reward).collect();
+/// assert_eq!(states, [4, 5, 6]);
+/// assert_eq!(rewards, [5.0, 6.0, 7.0]);
+/// ```
+pub struct TrajectoryRecorder<S: State> {
+    capacity: usize,
+    trajectories: VecDeque<Trajectory<S>>,
+    /// The steps of the episode that is being observed.
+    current: Trajectory<S>,
+}
+
+impl<S: State> TrajectoryRecorder<S> {
+    /// Creates a recorder which keeps the trajectories of the `capacity` most recent episodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    pub fn new(capacity: usize) -> TrajectoryRecorder<S> {
+        assert!(
+            capacity > 0,
+            "the recorder must keep at least one trajectory"
+        );
+        TrajectoryRecorder {
+            capacity,
+            trajectories: VecDeque::new(),
+            current: Vec::new(),
+        }
+    }
+
+    /// Returns the recorded trajectories, oldest first.
+    pub fn trajectories(&self) -> &VecDeque<Trajectory<S>> {
+        &self.trajectories
+    }
+
+    /// Returns the recorded trajectories, oldest first, consuming this recorder.
+    pub fn into_trajectories(self) -> Vec<Trajectory<S>> {
+        self.trajectories.into()
+    }
+
+    /// Returns the number of recorded trajectories.
+    pub fn len(&self) -> usize {
+        self.trajectories.len()
+    }
+
+    /// Returns whether no trajectory was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.trajectories.is_empty()
+    }
+
+    /// Discards the recorded trajectories.
+    pub fn clear(&mut self) {
+        self.trajectories.clear();
+        self.current.clear();
+    }
+}
+
+impl<S: State> TransitionObserver<S> for TrajectoryRecorder<S> {
+    fn observe(&mut self, transition: &Transition<S>) {
+        self.current.push(TrajectoryStep {
+            state: transition.state.clone(),
+            action: transition.action.clone(),
+            reward: transition.reward,
+        });
+        if transition.next_action.is_none() {
+            if self.trajectories.len() == self.capacity {
+                self.trajectories.pop_front();
+            }
+            self.trajectories
+                .push_back(std::mem::take(&mut self.current));
+        }
+    }
+}
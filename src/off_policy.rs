@@ -0,0 +1,97 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for estimating the value of a policy from episodes played with another policy.
+
+use crate::mdp::State;
+
+/// Estimates the expected discounted return of the target policy from `episodes` played with
+/// the behavior policy, without playing any episodes with the target policy. Every episode is
+/// the `(state, action, reward)` of every step taken in it, in order, where the reward is the
+/// one received after taking the action. `behavior` and `target` return the probability with
+/// which their policy takes an action in a state, such as the
+/// [action_probabilities()](crate::strategy::explore::ExplorationStrategy::action_probabilities)
+/// of an exploration strategy.
+///
+/// The estimate is weighted importance sampling: the discounted return of every episode is
+/// weighted by how much more likely the target policy is to play it than the behavior policy,
+/// the product of `target / behavior` over its steps, and the weighted returns are divided by
+/// the sum of the weights. This is biased for few episodes, but has a much lower variance than
+/// dividing by the number of episodes. Returns `None` if the target policy would play none of
+/// the episodes.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::State;
+/// use rurel::off_policy_evaluate;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Start;
+///
+/// impl State for Start {
+///     type A = char;
+///     fn reward(&self) -> f64 {
+///         0.0
+///     }
+///     fn actions(&self) -> Vec<char> {
+///         vec!['a', 'b']
+///     }
+/// }
+///
+/// // The episodes were played picking either action with equal probability
+/// let behavior = |_: &Start, _: &char| 0.5;
+/// // The target policy picks 'a' with a probability of 0.8
+/// let target = |_: &Start, a: &char| if *a == 'a' { 0.8 } else { 0.2 };
+/// let episodes = vec![
+///     vec![(Start, 'a', 1.0)],
+///     vec![(Start, 'b', 0.0)],
+///     vec![(Start, 'a', 1.0), (Start, 'a', 2.0)],
+/// ];
+///
+/// let value = off_policy_evaluate(&episodes, 0.5, behavior, target).unwrap();
+/// // The weights are 0.8 / 0.5, 0.2 / 0.5 and (0.8 / 0.5)^2, and the returns 1, 0 and
+/// // 1 + 0.5 * 2
+/// let expected = (1.6 * 1.0 + 0.4 * 0.0 + 2.56 * 2.0) / (1.6 + 0.4 + 2.56);
+/// assert!((value - expected).abs() < 1e-12);
+///
+/// // A greedy target policy would never play the second episode
+/// let greedy = |_: &Start, a: &char| if *a == 'a' { 1.0 } else { 0.0 };
+/// assert_eq!(off_policy_evaluate(&episodes[1..2], 0.5, behavior, greedy), None);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `behavior` gives a probability of `0` for an action that was taken in an episode.
+pub fn off_policy_evaluate<S: State>(
+    episodes: &[Vec<(S, S::A, f64)>],
+    gamma: f64,
+    behavior: impl Fn(&S, &S::A) -> f64,
+    target: impl Fn(&S, &S::A) -> f64,
+) -> Option<f64> {
+    let mut weighted_returns = 0.0;
+    let mut total_weight = 0.0;
+    for episode in episodes {
+        let mut weight = 1.0;
+        let mut discounted_return = 0.0;
+        let mut discount = 1.0;
+        for (state, action, reward) in episode {
+            let p = behavior(state, action);
+            assert!(
+                p > 0.0,
+                "the behavior policy took an action it gives a probability of 0"
+            );
+            weight *= target(state, action) / p;
+            discounted_return += discount * reward;
+            discount *= gamma;
+        }
+        weighted_returns += weight * discounted_return;
+        total_weight += weight;
+    }
+    if total_weight > 0.0 {
+        Some(weighted_returns / total_weight)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,178 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Training an [AgentTrainer] on several episodes at once.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::cache::ActionCache;
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::learn::LearningStrategy;
+use crate::strategy::terminate::TerminationStrategy;
+use crate::{AgentTrainer, QValue};
+
+impl<S, V> AgentTrainer<S, V>
+where
+    S: State + Send + Sync,
+    S::A: Send + Sync,
+    V: QValue + Send + Sync,
+{
+    /// Trains this [AgentTrainer] on one episode per agent in `agents`, running the episodes in
+    /// parallel on the rayon thread pool. Every episode ends when its own termination strategy,
+    /// created by `termination_strategy`, decides to stop.
+    ///
+    /// Every episode is trained like with [train()](AgentTrainer::train), on its own copy of the
+    /// values learned before this call, so the episodes don't see each other's updates. Once all
+    /// episodes have ended, their updates are merged: every value is set to the average of what
+    /// the episodes that changed it found, and values that no episode changed are kept. Adding up
+    /// the changes instead would apply the learning rate once per episode, and diverge with many
+    /// agents.
+    ///
+    /// [Visit counts](AgentTrainer::with_visit_counts) of all episodes are added up. With a single
+    /// agent, this is the same as [train()](AgentTrainer::train). Every episode copies the learned
+    /// values, so this pays off when episodes are long compared to the size of the table. The
+    /// learning and exploration strategies are shared by all episodes, so they must be [Sync];
+    /// strategies with interior state, such as
+    /// [DoubleQLearning](crate::strategy::learn::DoubleQLearning), can't be used.
+    ///
+    /// The episodes are trained with the [initial value](AgentTrainer::with_initial_value), the
+    /// step penalty, the updates into terminal states and the exploration bonus of this trainer.
+    /// If it [caches actions](AgentTrainer::with_action_cache), every episode has a cache of the
+    /// same capacity of its own, whose lookups aren't counted in the
+    /// [stats](AgentTrainer::action_cache_stats) of this trainer. The
+    /// [default value](AgentTrainer::with_default_value) and the
+    /// [tie breaking](AgentTrainer::with_tie_breaking) only matter when querying the values, so
+    /// the episodes don't need them. If checkpoints are saved and one is due during the episodes,
+    /// it is saved once all of them are merged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(9)`, and is rewarded with
+    /// // 1 at 9
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         if self.0 == 9 { 1.0 } else { 0.0 }
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 9));
+    /// #     }
+    /// # }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// for _ in 0..20 {
+    ///     let mut agents: Vec<_> = (0..8).map(|_| Walker(Position(0))).collect();
+    ///     trainer.train_parallel(
+    ///         &mut agents,
+    ///         &QLearning::new(0.2, 0.9, 0.0),
+    ///         &|| FixedIterations::new(100),
+    ///         &RandomExploration,
+    ///     );
+    /// }
+    /// assert_eq!(trainer.best_action(&Position(5)), Some(1));
+    /// ```
+    pub fn train_parallel<G, T>(
+        &mut self,
+        agents: &mut [G],
+        learning_strategy: &(dyn LearningStrategy<S, V> + Sync),
+        termination_strategy: &(dyn Fn() -> T + Sync),
+        exploration_strategy: &(dyn ExplorationStrategy<S, V> + Sync),
+    ) where
+        G: Agent<S> + Send,
+        T: TerminationStrategy<S>,
+    {
+        let snapshot = &self.q;
+        let count_visits = self.visits.is_some();
+        let (tables, visits): (Vec<_>, Vec<_>) = agents
+            .par_iter_mut()
+            .map(|agent| {
+                let mut local = AgentTrainer {
+                    q: snapshot.clone(),
+                    episodes: 0,
+                    visits: None,
+                    initial_value: self.initial_value,
+                    step_penalty: self.step_penalty,
+                    terminal_updates: self.terminal_updates,
+                    exploration_bonus: self.exploration_bonus,
+                    default_value: None,
+                    tie_rng: None,
+                    action_cache: self
+                        .action_cache
+                        .as_ref()
+                        .map(|cache| ActionCache::new(cache.capacity())),
+                    checkpoints: None,
+                    checkpoint_error: None,
+                }
+                .with_visit_counts(count_visits);
+                local.train(
+                    agent,
+                    learning_strategy,
+                    &mut termination_strategy(),
+                    exploration_strategy,
+                );
+                (local.q, local.visits)
+            })
+            .unzip();
+
+        let mut changed: HashMap<S, HashMap<S::A, (f64, usize)>> = HashMap::new();
+        for table in tables {
+            for (state, values) in table {
+                let old_values = snapshot.get(&state);
+                for (action, value) in values {
+                    if old_values.and_then(|m| m.get(&action)) == Some(&value) {
+                        continue;
+                    }
+                    let (sum, count) = changed
+                        .entry(state.clone())
+                        .or_default()
+                        .entry(action)
+                        .or_insert((0.0, 0));
+                    *sum += value.to_f64();
+                    *count += 1;
+                }
+            }
+        }
+
+        for (state, values) in changed {
+            let old_values = self.q.entry(state).or_default();
+            for (action, (sum, count)) in values {
+                old_values.insert(action, V::from_f64(sum / count as f64));
+            }
+        }
+        if let Some(all_visits) = &mut self.visits {
+            for (state, counts) in visits.into_iter().flatten().flatten() {
+                let all_counts = all_visits.entry(state).or_default();
+                for (action, count) in counts {
+                    *all_counts.entry(action).or_insert(0) += count;
+                }
+            }
+        }
+        self.episodes += agents.len();
+        self.checkpoint(agents.len(), learning_strategy, exploration_strategy);
+    }
+}
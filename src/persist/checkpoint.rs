@@ -0,0 +1,307 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for saving checkpoints of training, and resuming training from them.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{entries, from_entries, invalid_json, read_json, JSON_VERSION};
+use crate::mdp::State;
+use crate::strategy::explore::random::{restore_rng, rng_state};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::learn::LearningStrategy;
+use crate::{AgentTrainer, QValue, RurelError};
+
+impl<S, V> AgentTrainer<S, V>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::A: Serialize + DeserializeOwned,
+    V: QValue + Serialize + DeserializeOwned,
+{
+    /// Saves a checkpoint to the file at `path` after every `every` episodes of training, with
+    /// [save_checkpoint()](AgentTrainer::save_checkpoint), so that training can be resumed
+    /// with [load_checkpoint()](AgentTrainer::load_checkpoint) if it is interrupted. Every
+    /// checkpoint replaces the previous one. If a checkpoint can't be saved, training goes on,
+    /// and the error is kept in [checkpoint_error()](AgentTrainer::checkpoint_error).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is `0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::{DecayingEpsilonGreedy, EpsilonDecay};
+    /// use rurel::strategy::learn::{QLearning, StepDecay};
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(10)`, rewarded by how
+    /// // close it is to 5
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -(self.0 - 5).abs() as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 10));
+    /// #     }
+    /// # }
+    ///
+    /// let path = std::env::temp_dir().join("rurel_checkpoint.json");
+    /// let _ = std::fs::remove_file(&path);
+    ///
+    /// // Starts training, or resumes it from the last checkpoint
+    /// let start = || {
+    ///     let learning = QLearning::with_schedule(StepDecay::new(0.2, 0.5, 100), 0.9, 0.0);
+    ///     let exploration = DecayingEpsilonGreedy::new(1.0, 0.0, EpsilonDecay::Linear(0.001));
+    ///     let mut trainer = AgentTrainer::new().with_checkpoints(10, &path);
+    ///     if path.exists() {
+    ///         trainer.load_checkpoint(&path, &learning, &exploration).unwrap();
+    ///     }
+    ///     (trainer, learning, exploration)
+    /// };
+    /// let train = |trainer: &mut AgentTrainer<Position>, learning, exploration, episodes| {
+    ///     for _ in trainer.episodes()..episodes {
+    ///         trainer.train(
+    ///             &mut Walker(Position(0)),
+    ///             learning,
+    ///             &mut FixedIterations::new(9),
+    ///             exploration,
+    ///         );
+    ///     }
+    /// };
+    ///
+    /// // The first run is interrupted after 35 of 100 episodes
+    /// let (mut trainer, learning, exploration) = start();
+    /// train(&mut trainer, &learning, &exploration, 35);
+    ///
+    /// // The second run resumes after 30 episodes of 10 actions, with epsilon and the learning
+    /// // rate decayed as far
+    /// let (mut trainer, learning, exploration) = start();
+    /// assert_eq!(trainer.episodes(), 30);
+    /// assert!((exploration.epsilon() - 0.7).abs() < 1e-9);
+    /// assert!(learning.alpha() < 0.2);
+    /// train(&mut trainer, &learning, &exploration, 100);
+    /// assert_eq!(trainer.best_action(&Position(2)), Some(1));
+    /// ```
+    pub fn with_checkpoints(mut self, every: usize, path: impl AsRef<Path>) -> Self {
+        assert!(every > 0, "checkpoints must be at least one episode apart");
+        let path = path.as_ref().to_path_buf();
+        self.checkpoints = Some((
+            every,
+            Box::new(
+                move |trainer: &AgentTrainer<S, V>, learning: &[u64], exploration: &[u64]| {
+                    trainer.write_checkpoint(&path, learning, exploration)
+                },
+            ),
+        ));
+        self
+    }
+
+    /// Returns the error of the last checkpoint saved during training, if it couldn't be saved,
+    /// or `None` if it was saved or no checkpoint was due yet. A failed checkpoint doesn't stop
+    /// training, and the previous checkpoint, if any, is left as it was.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::{AgentTrainer, RurelError};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(5)`, and is rewarded with
+    /// // its position
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         self.0 as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 5));
+    /// #     }
+    /// # }
+    ///
+    /// // The directory of the checkpoints doesn't exist
+    /// let path = std::env::temp_dir().join("rurel_missing").join("checkpoint.json");
+    /// let mut trainer = AgentTrainer::new().with_checkpoints(2, &path);
+    /// let train = |trainer: &mut AgentTrainer<Position>| {
+    ///     trainer.train(
+    ///         &mut Walker(Position(0)),
+    ///         &QLearning::new(0.2, 0.9, 0.0),
+    ///         &mut FixedIterations::new(9),
+    ///         &RandomExploration,
+    ///     );
+    /// };
+    ///
+    /// train(&mut trainer);
+    /// assert!(trainer.checkpoint_error().is_none());
+    /// train(&mut trainer);
+    /// assert!(matches!(trainer.checkpoint_error(), Some(RurelError::Io(_))));
+    /// assert_eq!(trainer.episodes(), 2);
+    /// ```
+    pub fn checkpoint_error(&self) -> Option<&RurelError> {
+        self.checkpoint_error.as_ref()
+    }
+
+    /// Saves a checkpoint of training to the file at `path`, which can be loaded with
+    /// [load_checkpoint()](AgentTrainer::load_checkpoint) to continue training where it was.
+    /// Besides the learned values, the checkpoint holds the number of
+    /// [episodes](AgentTrainer::episodes) trained so far, the state of `learning_strategy`, such
+    /// as how far the [learning rate schedule](crate::strategy::learn::LearningRateSchedule) of
+    /// [QLearning](crate::strategy::learn::QLearning) has advanced, the state of
+    /// `exploration_strategy`, such as the epsilon of a
+    /// [DecayingEpsilonGreedy](crate::strategy::explore::DecayingEpsilonGreedy) strategy, the
+    /// generator of [random tie breaking](crate::TieBreaking::Random), and the
+    /// [visit counts](AgentTrainer::with_visit_counts), if they are counted.
+    ///
+    /// The checkpoint is a JSON file in the format of [save_json()](AgentTrainer::save_json),
+    /// with these additional fields, so its values can also be loaded with
+    /// [load_json()](AgentTrainer::load_json). It is written to a temporary file first, which
+    /// then replaces the file at `path`, so an interrupted save doesn't corrupt the previous
+    /// checkpoint.
+    pub fn save_checkpoint(
+        &self,
+        path: impl AsRef<Path>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+    ) -> Result<(), RurelError> {
+        self.write_checkpoint(
+            path.as_ref(),
+            &learning_strategy.save_state(),
+            &exploration_strategy.save_state(),
+        )
+    }
+
+    /// Loads a checkpoint saved with [save_checkpoint()](AgentTrainer::save_checkpoint) from the
+    /// file at `path`, completely replacing any learned progress, and restores the states of
+    /// `learning_strategy` and `exploration_strategy`, which have to be strategies of the same
+    /// kinds as those the checkpoint was saved with. See
+    /// [with_checkpoints()](AgentTrainer::with_checkpoints) for an example.
+    ///
+    /// Fails with [RurelError::UnsupportedVersion] if the file was saved in a newer version of
+    /// the format, and with [RurelError::InvalidFormat] if the file isn't a checkpoint, doesn't
+    /// contain values of the right types, or holds the state of another kind of learning or
+    /// exploration strategy. Nothing is restored if loading fails.
+    pub fn load_checkpoint(
+        &mut self,
+        path: impl AsRef<Path>,
+        learning_strategy: &dyn LearningStrategy<S, V>,
+        exploration_strategy: &dyn ExplorationStrategy<S, V>,
+    ) -> Result<(), RurelError> {
+        let mut file = read_json(path)?;
+        let episodes = file
+            .get("episodes")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| invalid_json("the file is not a checkpoint"))?;
+        let values = file
+            .remove("values")
+            .ok_or_else(|| invalid_json("the file has no values"))?;
+        let values = from_entries(values)?;
+        let visits = file.remove("visits").map(from_entries).transpose()?;
+        let tie_rng = match file.remove("tie_breaking") {
+            Some(state) => Some(restore_rng(&serde_json::from_value::<Vec<u64>>(state)?)?),
+            None => None,
+        };
+        let strategy_state = |file: &mut serde_json::Map<_, _>, key| match file.remove(key) {
+            Some(state) => serde_json::from_value::<Vec<u64>>(state),
+            None => Ok(Vec::new()),
+        };
+        let learning = strategy_state(&mut file, "learning")?;
+        let exploration = strategy_state(&mut file, "exploration")?;
+        let previous_learning = learning_strategy.save_state();
+        learning_strategy.restore_state(&learning)?;
+        if let Err(e) = exploration_strategy.restore_state(&exploration) {
+            learning_strategy.restore_state(&previous_learning)?;
+            return Err(e);
+        }
+
+        self.q = values;
+        self.episodes = episodes as usize;
+        if visits.is_some() {
+            self.visits = visits;
+        }
+        if let Some(rng) = tie_rng {
+            self.tie_rng = Some(Mutex::new(rng));
+        }
+        Ok(())
+    }
+
+    /// Saves a checkpoint to the file at `path`, with the given states of the learning and
+    /// exploration strategies.
+    fn write_checkpoint(
+        &self,
+        path: &Path,
+        learning: &[u64],
+        exploration: &[u64],
+    ) -> Result<(), RurelError> {
+        let mut temporary = OsString::from(path);
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        write!(
+            writer,
+            "{{\"version\":{},\"episodes\":{},\"learning\":",
+            JSON_VERSION, self.episodes
+        )?;
+        serde_json::to_writer(&mut writer, learning)?;
+        writer.write_all(b",\"exploration\":")?;
+        serde_json::to_writer(&mut writer, exploration)?;
+        if let Some(rng) = &self.tie_rng {
+            writer.write_all(b",\"tie_breaking\":")?;
+            serde_json::to_writer(&mut writer, &rng_state(&rng.lock().unwrap()))?;
+        }
+        if let Some(visits) = &self.visits {
+            writer.write_all(b",\"visits\":")?;
+            serde_json::to_writer(&mut writer, &entries(visits))?;
+        }
+        writer.write_all(b",\"values\":")?;
+        serde_json::to_writer(&mut writer, &entries(&self.q))?;
+        writer.write_all(b"}")?;
+        writer.flush()?;
+        drop(writer);
+        Ok(std::fs::rename(temporary, path)?)
+    }
+}
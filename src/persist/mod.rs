@@ -0,0 +1,514 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Saving and loading the values learned by an [AgentTrainer].
+
+#[cfg(feature = "serde")]
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::mdp::State;
+use crate::{AgentTrainer, Policy, QValue, RurelError};
+
+#[cfg(feature = "binary")]
+mod binary;
+#[cfg(feature = "serde")]
+mod checkpoint;
+mod trajectory;
+
+/// The learned values as they are stored in JSON: a list of states with their action values.
+#[cfg(feature = "serde")]
+type Entries<S, A, V> = Vec<(S, Vec<(A, V)>)>;
+
+/// The version of the JSON file format. Version 1 files hold just the [Entries], without a
+/// version.
+#[cfg(feature = "serde")]
+const JSON_VERSION: u32 = 2;
+
+/// The bytes every file written by [save_bincode()](AgentTrainer::save_bincode) starts with.
+#[cfg(feature = "binary")]
+const MAGIC: &[u8; 4] = b"RURL";
+
+/// The version of the binary file format, stored right after [MAGIC].
+#[cfg(feature = "binary")]
+const BINARY_VERSION: u32 = 1;
+
+#[cfg(feature = "serde")]
+impl<S, V> AgentTrainer<S, V>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::A: Serialize + DeserializeOwned,
+    V: QValue + Serialize + DeserializeOwned,
+{
+    /// Saves the learned values to the file at `path` as JSON, so they can be loaded later with
+    /// [load_json()](AgentTrainer::load_json).
+    ///
+    /// The file is an object with the `version` of the format, and the `values`, which are
+    /// stored as a list of `[state, [[action, value], ...]]` entries, since JSON objects only
+    /// support strings as keys. Values round-trip exactly, but JSON can't represent infinite or
+    /// NaN values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::AgentTrainer;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = bool;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<bool> {
+    ///         vec![false, true]
+    ///     }
+    /// }
+    ///
+    /// let mut trained = AgentTrainer::new();
+    /// trained.import_state(
+    ///     [(MyState(1), [(true, 0.1 + 0.2), (false, -1.5e-300)].into_iter().collect())]
+    ///         .into_iter()
+    ///         .collect(),
+    /// );
+    /// let path = std::env::temp_dir().join("rurel_save_json.json");
+    /// trained.save_json(&path).unwrap();
+    ///
+    /// let mut loaded = AgentTrainer::new();
+    /// loaded.load_json(&path).unwrap();
+    /// assert_eq!(loaded.learned_values(), trained.learned_values());
+    /// ```
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<(), RurelError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "{{\"version\":{},\"values\":", JSON_VERSION)?;
+        serde_json::to_writer(&mut writer, &entries(&self.q))?;
+        writer.write_all(b"}")?;
+        Ok(writer.flush()?)
+    }
+
+    /// Loads values saved with [save_json()](AgentTrainer::save_json) from the file at `path`,
+    /// completely replacing any learned progress. Files saved by earlier versions of this crate,
+    /// which hold just the list of entries, are loaded as well.
+    ///
+    /// Fails with [RurelError::UnsupportedVersion] if the file was saved in a newer version of
+    /// the format, and with [RurelError::InvalidFormat] if the file isn't valid JSON, or doesn't
+    /// contain values of the right types.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::{AgentTrainer, RurelError};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = bool;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<bool> {
+    ///         vec![false, true]
+    ///     }
+    /// }
+    ///
+    /// let path = std::env::temp_dir().join("rurel_load_json.json");
+    /// let mut trainer = AgentTrainer::new();
+    ///
+    /// // A file saved before the format had a version
+    /// std::fs::write(&path, "[[1, [[true, 0.5]]]]").unwrap();
+    /// trainer.load_json(&path).unwrap();
+    /// assert_eq!(trainer.expected_value(&MyState(1), &true), Some(0.5));
+    ///
+    /// std::fs::write(&path, r#"{"version": 99, "values": []}"#).unwrap();
+    /// assert!(matches!(
+    ///     trainer.load_json(&path),
+    ///     Err(RurelError::UnsupportedVersion { found: 99, supported: 2 })
+    /// ));
+    /// ```
+    pub fn load_json(&mut self, path: impl AsRef<Path>) -> Result<(), RurelError> {
+        let mut file = read_json(path)?;
+        let values = file
+            .remove("values")
+            .ok_or_else(|| invalid_json("the file has no values"))?;
+        self.q = from_entries(values)?;
+        Ok(())
+    }
+}
+
+/// Returns the entries of `table`, for storing it in JSON.
+#[cfg(feature = "serde")]
+fn entries<S: State, V: Copy>(table: &HashMap<S, HashMap<S::A, V>>) -> Entries<&S, &S::A, V> {
+    table
+        .iter()
+        .map(|(s, m)| (s, m.iter().map(|(a, &v)| (a, v)).collect()))
+        .collect()
+}
+
+/// Reads the table stored as `entries` in JSON.
+#[cfg(feature = "serde")]
+fn from_entries<S, V>(
+    entries: serde_json::Value,
+) -> Result<HashMap<S, HashMap<S::A, V>>, RurelError>
+where
+    S: State + DeserializeOwned,
+    S::A: DeserializeOwned,
+    V: DeserializeOwned,
+{
+    let entries: Entries<S, S::A, V> = serde_json::from_value(entries)?;
+    Ok(entries
+        .into_iter()
+        .map(|(s, m)| (s, m.into_iter().collect()))
+        .collect())
+}
+
+/// Reads the JSON file at `path` as an object, after checking its format version. Version 1
+/// files, which hold just the entries, are read as an object with only their `values`.
+#[cfg(feature = "serde")]
+fn read_json(
+    path: impl AsRef<Path>,
+) -> Result<serde_json::Map<String, serde_json::Value>, RurelError> {
+    let reader = BufReader::new(File::open(path)?);
+    let file: serde_json::Value = serde_json::from_reader(reader)?;
+    match file {
+        // version 1 files hold just the entries
+        serde_json::Value::Array(_) => Ok([("values".to_string(), file)].into_iter().collect()),
+        serde_json::Value::Object(file) => {
+            let version = file
+                .get("version")
+                .and_then(serde_json::Value::as_u64)
+                .ok_or_else(|| invalid_json("the file has no format version"))?;
+            if version > JSON_VERSION as u64 {
+                return Err(RurelError::UnsupportedVersion {
+                    found: version.try_into().unwrap_or(u32::MAX),
+                    supported: JSON_VERSION,
+                });
+            }
+            Ok(file)
+        }
+        _ => Err(invalid_json("the file holds no values")),
+    }
+}
+
+/// A policy is serialized as a list of `[state, action]` entries, like the learned values of
+/// [save_json()](AgentTrainer::save_json), so that it can be stored in JSON.
+impl<S> Serialize for Policy<S>
+where
+    S: State + Serialize,
+    S::A: Serialize,
+{
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        serializer.collect_seq(&self.actions)
+    }
+}
+
+impl<'de, S> Deserialize<'de> for Policy<S>
+where
+    S: State + Deserialize<'de>,
+    S::A: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Policy<S>, D::Error> {
+        let entries: Vec<(S, S::A)> = Vec::deserialize(deserializer)?;
+        Ok(Policy {
+            actions: entries.into_iter().collect(),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S> Policy<S>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::A: Serialize + DeserializeOwned,
+{
+    /// Saves the policy to the file at `path` as JSON, so it can be loaded later with
+    /// [load_json()](Policy::load_json).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::{AgentTrainer, Policy};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = bool;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<bool> {
+    ///         vec![false, true]
+    ///     }
+    /// }
+    ///
+    /// let mut trained = AgentTrainer::new();
+    /// trained.import_state(
+    ///     (0..100)
+    ///         .map(|i| (MyState(i), [(false, 0.0), (true, i as f64 - 50.0)].into_iter().collect()))
+    ///         .collect(),
+    /// );
+    /// let path = std::env::temp_dir().join("rurel_save_policy.json");
+    /// trained.extract_policy().save_json(&path).unwrap();
+    ///
+    /// // The deployed agent only needs the policy
+    /// let policy = Policy::<MyState>::load_json(&path).unwrap();
+    /// assert_eq!(policy.len(), 100);
+    /// assert_eq!(policy.action(&MyState(10)), Some(false));
+    /// assert_eq!(policy.action(&MyState(90)), Some(true));
+    /// ```
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<(), RurelError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Loads a policy saved with [save_json()](Policy::save_json) from the file at `path`.
+    ///
+    /// Fails with [RurelError::InvalidFormat] if the file isn't valid JSON, or doesn't contain a
+    /// policy of the right types.
+    pub fn load_json(path: impl AsRef<Path>) -> Result<Policy<S>, RurelError> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+#[cfg(feature = "binary")]
+impl<S, V> AgentTrainer<S, V>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::A: Serialize + DeserializeOwned,
+    V: QValue + Serialize + DeserializeOwned,
+{
+    /// Saves the learned values to the file at `path` in a compact binary format, so they can be
+    /// loaded later with [load_bincode()](AgentTrainer::load_bincode). This is much smaller and
+    /// faster than [save_json()](AgentTrainer::save_json) for large tables.
+    ///
+    /// The file starts with a magic number and a format version, followed by the learned values
+    /// in the default encoding of bincode 1. Values are stored as their exact bits, so infinite
+    /// and NaN values are supported too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    /// use rurel::{AgentTrainer, RurelError};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = bool;
+    ///     fn reward(&self) -> f64 {
+    ///         self.0 as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<bool> {
+    ///         vec![false, true]
+    ///     }
+    /// }
+    ///
+    /// let mut trained = AgentTrainer::new();
+    /// trained.import_state(
+    ///     (0..5000)
+    ///         .map(|i| {
+    ///             let values = [(false, 1.0 / (i as f64 - 2500.5)), (true, (i as f64).sin())];
+    ///             (MyState(i), values.into_iter().collect())
+    ///         })
+    ///         .collect(),
+    /// );
+    /// let path = std::env::temp_dir().join("rurel_save_bincode.bin");
+    /// trained.save_bincode(&path).unwrap();
+    ///
+    /// let mut loaded = AgentTrainer::new();
+    /// loaded.load_bincode(&path).unwrap();
+    /// assert_eq!(loaded.learned_values().len(), 5000);
+    /// for (state, values) in trained.learned_values() {
+    ///     for (action, value) in values {
+    ///         let loaded_value = loaded.expected_value(state, action).unwrap();
+    ///         assert_eq!(loaded_value.to_bits(), value.to_bits());
+    ///     }
+    /// }
+    ///
+    /// // Other files are rejected instead of being read as garbage
+    /// std::fs::write(&path, b"[1, 2, 3]").unwrap();
+    /// assert!(matches!(loaded.load_bincode(&path), Err(RurelError::InvalidFormat(_))));
+    /// ```
+    pub fn save_bincode(&self, path: impl AsRef<Path>) -> Result<(), RurelError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+        binary::to_writer(&mut writer, &self.q)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Loads values saved with [save_bincode()](AgentTrainer::save_bincode) from the file at
+    /// `path`, completely replacing any learned progress.
+    ///
+    /// Fails with [RurelError::UnsupportedVersion] if the file was written in another version of
+    /// the format, and with [RurelError::InvalidFormat] if the file wasn't written by
+    /// [save_bincode()](AgentTrainer::save_bincode), or doesn't contain values of the right
+    /// types.
+    ///
+    /// # Example
+    ///
+    /// States and actions can be of any type that serde can write, except those that need a
+    /// self-describing format, such as untagged enums:
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use rurel::mdp::State;
+    /// use rurel::{AgentTrainer, RurelError};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+    /// struct Marker;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+    /// struct Id(u16);
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+    /// enum Cell {
+    ///     Empty,
+    ///     Letter(char),
+    ///     Word(String),
+    ///     Pair(i8, Option<u64>),
+    ///     Piece {
+    ///         owner: bool,
+    ///         marker: Marker,
+    ///         moves: Vec<(Id, i128)>,
+    ///         counts: BTreeMap<String, u8>,
+    ///         bytes: Vec<u8>,
+    ///         inside: Option<Box<Cell>>,
+    ///     },
+    /// }
+    ///
+    /// impl State for Cell {
+    ///     type A = Option<(char, Id)>;
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<Self::A> {
+    ///         vec![None, Some(('a', Id(1)))]
+    ///     }
+    /// }
+    ///
+    /// let piece = Cell::Piece {
+    ///     owner: true,
+    ///     marker: Marker,
+    ///     moves: vec![(Id(3), -1), (Id(u16::MAX), i128::MAX)],
+    ///     counts: BTreeMap::from([("é".to_string(), 2), (String::new(), 0)]),
+    ///     bytes: vec![0, 255],
+    ///     inside: Some(Box::new(Cell::Letter('\u{1F980}'))),
+    /// };
+    /// let states = [
+    ///     Cell::Empty,
+    ///     Cell::Letter('x'),
+    ///     Cell::Letter('ß'),
+    ///     Cell::Word("grüße".to_string()),
+    ///     Cell::Pair(-128, None),
+    ///     Cell::Pair(5, Some(u64::MAX)),
+    ///     piece,
+    /// ];
+    /// let mut trained = AgentTrainer::new();
+    /// trained.import_state(
+    ///     states
+    ///         .iter()
+    ///         .enumerate()
+    ///         .map(|(i, state)| {
+    ///             let values = [(None, i as f64), (Some(('€', Id(i as u16))), -(i as f64))];
+    ///             (state.clone(), values.into_iter().collect())
+    ///         })
+    ///         .collect(),
+    /// );
+    /// let path = std::env::temp_dir().join("rurel_load_bincode.bin");
+    /// trained.save_bincode(&path).unwrap();
+    ///
+    /// let mut loaded = AgentTrainer::new();
+    /// loaded.load_bincode(&path).unwrap();
+    /// assert_eq!(loaded.learned_values(), trained.learned_values());
+    ///
+    /// // Files that were cut short, or have bytes appended, are rejected
+    /// let bytes = std::fs::read(&path).unwrap();
+    /// for len in 0..bytes.len() {
+    ///     std::fs::write(&path, &bytes[..len]).unwrap();
+    ///     assert!(matches!(loaded.load_bincode(&path), Err(RurelError::InvalidFormat(_))));
+    /// }
+    /// std::fs::write(&path, [&bytes[..], &[0]].concat()).unwrap();
+    /// assert!(matches!(loaded.load_bincode(&path), Err(RurelError::InvalidFormat(_))));
+    ///
+    /// // So are corrupt lengths, variants and characters, without allocating what they claim
+    /// let mut corrupt = bytes.clone();
+    /// corrupt[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+    /// std::fs::write(&path, &corrupt).unwrap();
+    /// assert!(matches!(loaded.load_bincode(&path), Err(RurelError::InvalidFormat(_))));
+    /// let mut corrupt = bytes.clone();
+    /// corrupt[16..20].copy_from_slice(&99u32.to_le_bytes());
+    /// std::fs::write(&path, &corrupt).unwrap();
+    /// assert!(matches!(loaded.load_bincode(&path), Err(RurelError::InvalidFormat(_))));
+    ///
+    /// let mut letter = AgentTrainer::new();
+    /// letter.import_state([(Cell::Letter('x'), [(None, 1.0)].into())].into());
+    /// letter.save_bincode(&path).unwrap();
+    /// let mut corrupt = std::fs::read(&path).unwrap();
+    /// corrupt[20] = 0xff;
+    /// std::fs::write(&path, &corrupt).unwrap();
+    /// assert!(matches!(letter.load_bincode(&path), Err(RurelError::InvalidFormat(_))));
+    /// ```
+    pub fn load_bincode(&mut self, path: impl AsRef<Path>) -> Result<(), RurelError> {
+        let reader = read_bincode_header(path, MAGIC)?;
+        self.q = binary::from_reader(reader)?;
+        Ok(())
+    }
+}
+
+/// Opens the binary file at `path`, and reads its header, which has to start with `magic`,
+/// followed by the [BINARY_VERSION]. Returns the reader of the rest of the file.
+#[cfg(feature = "binary")]
+fn read_bincode_header(
+    path: impl AsRef<Path>,
+    magic: &[u8; 4],
+) -> Result<BufReader<File>, RurelError> {
+    use std::io::Read;
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = [0; 8];
+    reader.read_exact(&mut header).map_err(|_| not_bincode())?;
+    if &header[..4] != magic {
+        return Err(not_bincode());
+    }
+    let version = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    if version != BINARY_VERSION {
+        return Err(RurelError::UnsupportedVersion {
+            found: version,
+            supported: BINARY_VERSION,
+        });
+    }
+    Ok(reader)
+}
+
+/// The error for JSON files that don't hold learned values, because of `reason`.
+#[cfg(feature = "serde")]
+fn invalid_json(reason: &str) -> RurelError {
+    RurelError::InvalidFormat(reason.to_string())
+}
+
+/// The error for files that weren't written by [save_bincode()](AgentTrainer::save_bincode).
+#[cfg(feature = "binary")]
+fn not_bincode() -> RurelError {
+    RurelError::InvalidFormat("not a rurel binary file".to_string())
+}
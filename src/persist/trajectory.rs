@@ -0,0 +1,165 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Saving and loading the trajectories of a [TrajectoryRecorder].
+
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::BufReader;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "binary")]
+use super::{binary, read_bincode_header, BINARY_VERSION};
+use crate::mdp::State;
+use crate::observe::{Trajectory, TrajectoryRecorder, TrajectoryStep};
+use crate::RurelError;
+
+/// The bytes every file written by
+/// [save_bincode()](TrajectoryRecorder::save_bincode) starts with.
+#[cfg(feature = "binary")]
+const TRAJECTORY_MAGIC: &[u8; 4] = b"RURT";
+
+/// A step is serialized as a `[state, action, reward]` triple, which is shorter in JSON than an
+/// object.
+impl<S> Serialize for TrajectoryStep<S>
+where
+    S: State + Serialize,
+    S::A: Serialize,
+{
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        (&self.state, &self.action, self.reward).serialize(serializer)
+    }
+}
+
+impl<'de, S> Deserialize<'de> for TrajectoryStep<S>
+where
+    S: State + Deserialize<'de>,
+    S::A: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TrajectoryStep<S>, D::Error> {
+        let (state, action, reward) = <(S, S::A, f64)>::deserialize(deserializer)?;
+        Ok(TrajectoryStep {
+            state,
+            action,
+            reward,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S> TrajectoryRecorder<S>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::A: Serialize + DeserializeOwned,
+{
+    /// Saves the recorded trajectories to the file at `path` as JSON, oldest first, so they can
+    /// be loaded later with [load_json()](TrajectoryRecorder::load_json). The file is a list of
+    /// trajectories, each of which is a list of `[state, action, reward]` steps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::observe::TrajectoryRecorder;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// // A `Walker` steps by -1 or 1 along a line, and is rewarded by how close it is to 0
+    /// # #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -(self.0 as f64).abs()
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position(self.0 .0 + step);
+    /// #     }
+    /// # }
+    ///
+    /// let mut recorder = TrajectoryRecorder::new(10);
+    /// let mut trainer = AgentTrainer::new();
+    /// for _ in 0..3 {
+    ///     trainer.train_with_observers(
+    ///         &mut Walker(Position(0)),
+    ///         &QLearning::new(0.2, 0.9, 0.0),
+    ///         &mut FixedIterations::new(5),
+    ///         &RandomExploration,
+    ///         &mut |_| {},
+    ///         &mut recorder,
+    ///     );
+    /// }
+    ///
+    /// let path = std::env::temp_dir().join("rurel_trajectories.json");
+    /// recorder.save_json(&path).unwrap();
+    /// let loaded = TrajectoryRecorder::<Position>::load_json(&path).unwrap();
+    /// assert_eq!(loaded.len(), 3);
+    /// assert_eq!(loaded, recorder.into_trajectories());
+    /// ```
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<(), RurelError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self.trajectories())?;
+        Ok(writer.flush()?)
+    }
+
+    /// Loads the trajectories saved with [save_json()](TrajectoryRecorder::save_json) from the
+    /// file at `path`, oldest first.
+    ///
+    /// Fails with [RurelError::InvalidFormat] if the file isn't valid JSON, or doesn't contain
+    /// trajectories of the right types.
+    pub fn load_json(path: impl AsRef<Path>) -> Result<Vec<Trajectory<S>>, RurelError> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+#[cfg(feature = "binary")]
+impl<S> TrajectoryRecorder<S>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::A: Serialize + DeserializeOwned,
+{
+    /// Saves the recorded trajectories to the file at `path` in the compact binary format of
+    /// [AgentTrainer::save_bincode()](crate::AgentTrainer::save_bincode), oldest first, so they
+    /// can be loaded later with [load_bincode()](TrajectoryRecorder::load_bincode). Rewards are
+    /// stored as their exact bits.
+    pub fn save_bincode(&self, path: impl AsRef<Path>) -> Result<(), RurelError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(TRAJECTORY_MAGIC)?;
+        writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+        binary::to_writer(&mut writer, self.trajectories())?;
+        Ok(writer.flush()?)
+    }
+
+    /// Loads the trajectories saved with [save_bincode()](TrajectoryRecorder::save_bincode) from
+    /// the file at `path`, oldest first.
+    ///
+    /// Fails with [RurelError::UnsupportedVersion] if the file was written in another version of
+    /// the format, and with [RurelError::InvalidFormat] if the file wasn't written by
+    /// [save_bincode()](TrajectoryRecorder::save_bincode), or doesn't contain trajectories of
+    /// the right types.
+    pub fn load_bincode(path: impl AsRef<Path>) -> Result<Vec<Trajectory<S>>, RurelError> {
+        let reader = read_bincode_header(path, TRAJECTORY_MAGIC)?;
+        Ok(binary::from_reader(reader)?)
+    }
+}
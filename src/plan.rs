@@ -0,0 +1,205 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for picking actions by searching a few steps ahead, scoring the leaves with what was
+//! learned.
+
+use crate::mdp::{Agent, State};
+
+/// How a [Lookahead] search combines the values of the actions taken after the first one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Search {
+    /// A single agent picks every action. The value of an action is the mean over `samples`
+    /// clones of the agent taking it, so that the outcomes of a stochastic environment are
+    /// averaged; a single sample suffices for a deterministic one.
+    Expectimax {
+        /// The number of times every action is tried.
+        samples: usize,
+    },
+    /// Two players take turns in a zero-sum game, as in
+    /// [AgentTrainer::train_self_play()](crate::AgentTrainer::train_self_play): the value of an
+    /// action is its reward less the discounted value of the state for the opponent, who is
+    /// to move in it. The values of the leaves are those of the player to move in them.
+    Minimax,
+}
+
+/// The settings of a search that picks an action by expanding the tree of the states reachable
+/// within `depth` actions, by taking every action on clones of the agent, and scoring the leaves
+/// by their highest learned value. Terminal states are worth nothing beyond their reward.
+///
+/// The search deepens one action at a time, up to `depth`, and picks the best action of the
+/// deepest search that fit in the [node budget](Lookahead::with_node_budget), if there is one.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::{AgentTrainer, Lookahead, Search};
+///
+/// // Players take one or two sticks in turn, and whoever takes the last stick wins
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Sticks(u32);
+///
+/// impl State for Sticks {
+///     type A = u32;
+///     fn reward(&self) -> f64 {
+///         if self.0 == 0 { 1.0 } else { 0.0 }
+///     }
+///     fn actions(&self) -> Vec<u32> {
+///         (1..=self.0.min(2)).collect()
+///     }
+///     fn is_terminal(&self) -> bool {
+///         self.0 == 0
+///     }
+/// }
+///
+/// #[derive(Clone)]
+/// struct Game(Sticks);
+///
+/// impl Agent<Sticks> for Game {
+///     fn current_state(&self) -> &Sticks {
+///         &self.0
+///     }
+///     fn take_action(&mut self, taken: &u32) {
+///         self.0 = Sticks(self.0 .0 - taken);
+///     }
+/// }
+///
+/// // Nothing was learned, but searching to the end of the game finds the winning moves, which
+/// // leave a multiple of three sticks
+/// let trainer: AgentTrainer<Sticks> = AgentTrainer::new();
+/// let lookahead = Lookahead::new(5, 1.0, Search::Minimax);
+/// assert_eq!(trainer.plan(&Game(Sticks(4)), &lookahead), Some(1));
+/// assert_eq!(trainer.plan(&Game(Sticks(5)), &lookahead), Some(2));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lookahead {
+    depth: usize,
+    gamma: f64,
+    search: Search,
+    node_budget: Option<usize>,
+}
+
+impl Lookahead {
+    /// Creates the settings of a search `depth` actions deep, which discounts the values of later
+    /// steps by `gamma`, like the learning strategy the values were learned with. A depth of `0`
+    /// picks the best learned action.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `search` is [Search::Expectimax] with `0` samples.
+    pub fn new(depth: usize, gamma: f64, search: Search) -> Lookahead {
+        if let Search::Expectimax { samples } = search {
+            assert!(samples > 0, "every action has to be tried at least once");
+        }
+        Lookahead {
+            depth,
+            gamma,
+            search,
+            node_budget: None,
+        }
+    }
+
+    /// Limits the search to taking `nodes` actions on clones of the agent, over all the depths it
+    /// searches to, since the number of states within reach grows exponentially with the depth.
+    /// By default, the search isn't limited.
+    pub fn with_node_budget(mut self, nodes: usize) -> Self {
+        self.node_budget = Some(nodes);
+        self
+    }
+
+    /// Returns the largest number of actions the search looks ahead.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// The node budget was used up before a search finished.
+struct OutOfNodes;
+
+/// A search of the settings `lookahead`, which expands states with the actions returned by
+/// `actions`, and scores leaves with `best`, the best learned action and its value.
+struct Searcher<'l, F, B> {
+    lookahead: &'l Lookahead,
+    actions: F,
+    best: B,
+    /// The number of actions taken on clones of the agent so far.
+    nodes: usize,
+}
+
+impl<F, B> Searcher<'_, F, B> {
+    /// Returns the best action in the current state of `agent`, with its value, searching
+    /// `depth` actions ahead, or `None` if the state has no action or no value.
+    fn search<S, G>(&mut self, agent: &G, depth: usize) -> Result<Option<(S::A, f64)>, OutOfNodes>
+    where
+        S: State,
+        G: Agent<S> + Clone,
+        F: Fn(&S) -> Vec<S::A>,
+        B: Fn(&S) -> Option<(S::A, f64)>,
+    {
+        let state = agent.current_state();
+        if depth == 0 {
+            return Ok((self.best)(state));
+        }
+        let (samples, sign) = match self.lookahead.search {
+            Search::Expectimax { samples } => (samples, 1.0),
+            Search::Minimax => (1, -1.0),
+        };
+        let mut best: Option<(S::A, f64)> = None;
+        for action in (self.actions)(state) {
+            let mut total = 0.0;
+            for _ in 0..samples {
+                if self.lookahead.node_budget == Some(self.nodes) {
+                    return Err(OutOfNodes);
+                }
+                self.nodes += 1;
+                let mut next = agent.clone();
+                next.take_action(&action);
+                let reward = next.step_reward(&action) - state.cost(&action);
+                let later = if next.current_state().is_terminal() {
+                    0.0
+                } else {
+                    self.search(&next, depth - 1)?
+                        .map_or(0.0, |(_, value)| value)
+                };
+                total += reward + sign * self.lookahead.gamma * later;
+            }
+            let value = total / samples as f64;
+            if best.as_ref().is_none_or(|(_, best)| value > *best) {
+                best = Some((action, value));
+            }
+        }
+        Ok(best)
+    }
+}
+
+/// Returns the action to take in the current state of `agent`, searching as set by `lookahead`,
+/// with the actions of states returned by `actions`, and the best learned action of a state and
+/// its value returned by `best`. Falls back to the best learned action if even a search one
+/// action deep exceeds the node budget.
+pub(crate) fn plan<S, G>(
+    agent: &G,
+    lookahead: &Lookahead,
+    actions: impl Fn(&S) -> Vec<S::A>,
+    best: impl Fn(&S) -> Option<(S::A, f64)>,
+) -> Option<S::A>
+where
+    S: State,
+    G: Agent<S> + Clone,
+{
+    let mut searcher = Searcher {
+        lookahead,
+        actions,
+        best,
+        nodes: 0,
+    };
+    let mut planned = (searcher.best)(agent.current_state());
+    for depth in 1..=lookahead.depth {
+        match searcher.search(agent, depth) {
+            Ok(searched) => planned = searched,
+            Err(OutOfNodes) => break,
+        }
+    }
+    planned.map(|(action, _)| action)
+}
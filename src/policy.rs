@@ -0,0 +1,37 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The greedy policy learned by an [AgentTrainer](crate::AgentTrainer).
+
+use std::collections::HashMap;
+
+use crate::mdp::State;
+
+/// The best action of every state for which an [AgentTrainer](crate::AgentTrainer) learned
+/// values, as extracted by [extract_policy()](crate::AgentTrainer::extract_policy).
+///
+/// A policy is all that is needed to act greedily, and is much smaller than the learned values,
+/// as it only holds one action per state. With the `serde` feature enabled, it can be saved with
+/// [save_json()](Policy::save_json), and loaded with [load_json()](Policy::load_json).
+#[derive(Clone, PartialEq)]
+pub struct Policy<S: State> {
+    pub(crate) actions: HashMap<S, S::A>,
+}
+
+impl<S: State> Policy<S> {
+    /// Returns the action to take in `state`, or `None` if no values were learned for it.
+    pub fn action(&self, state: &S) -> Option<S::A> {
+        self.actions.get(state).cloned()
+    }
+
+    /// Returns the number of states the policy has an action for.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Returns whether the policy has no action for any state.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
@@ -0,0 +1,100 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for playing episodes greedily with what was learned, without learning from them.
+
+use crate::mdp::{Agent, State};
+use crate::strategy::terminate::TerminationStrategy;
+
+/// The outcome of playing a single episode greedily, as returned by
+/// [AgentTrainer::rollout()](crate::AgentTrainer::rollout).
+#[derive(Clone, PartialEq)]
+pub struct RolloutResult<S: State> {
+    /// The sum of the rewards received during the episode, less the [costs](State::cost) of the
+    /// actions taken.
+    pub total_reward: f64,
+    /// The number of actions taken during the episode.
+    pub steps: usize,
+    /// Every state the agent was in, with the action it took in that state, in order. The state
+    /// the episode ended in is the current state of the agent.
+    pub trajectory: Vec<(S, S::A)>,
+}
+
+/// Statistics about greedy episodes played from fresh agents, as returned by
+/// [AgentTrainer::evaluate()](crate::AgentTrainer::evaluate).
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalStats {
+    /// The number of episodes played.
+    pub episodes: usize,
+    /// The mean of the total rewards of the episodes.
+    pub mean_reward: f64,
+    /// The standard deviation of the total rewards of the episodes, over all episodes played.
+    pub std_reward: f64,
+    /// The mean number of actions taken per episode.
+    pub mean_steps: f64,
+}
+
+/// Plays an episode with `agent`, always taking the action returned by `best_action`, until
+/// `termination_strategy` decides to stop, the agent arrives at a terminal state, or
+/// `best_action` has no action for the current state.
+pub(crate) fn rollout<S: State>(
+    agent: &mut dyn Agent<S>,
+    termination_strategy: &mut dyn TerminationStrategy<S>,
+    best_action: impl Fn(&S) -> Option<S::A>,
+) -> RolloutResult<S> {
+    let mut result = RolloutResult {
+        total_reward: 0.0,
+        steps: 0,
+        trajectory: Vec::new(),
+    };
+    termination_strategy.start_episode();
+    loop {
+        let state = agent.current_state().clone();
+        let action = match best_action(&state) {
+            Some(action) => action,
+            None => break,
+        };
+        agent.take_action(&action);
+        result.total_reward += agent.step_reward(&action) - state.cost(&action);
+        result.trajectory.push((state, action));
+
+        let next_state = agent.current_state();
+        result.steps += 1;
+        if termination_strategy.should_stop(next_state) || next_state.is_terminal() {
+            break;
+        }
+    }
+    result
+}
+
+/// Plays `episodes` episodes like [rollout()], each with a fresh agent from `make_agent` and a
+/// fresh termination strategy from `make_termination`, and returns their statistics.
+pub(crate) fn evaluate<S, G, T>(
+    mut make_agent: impl FnMut() -> G,
+    mut make_termination: impl FnMut() -> T,
+    episodes: usize,
+    best_action: impl Fn(&S) -> Option<S::A>,
+) -> EvalStats
+where
+    S: State,
+    G: Agent<S>,
+    T: TerminationStrategy<S>,
+{
+    let results: Vec<RolloutResult<S>> = (0..episodes)
+        .map(|_| rollout(&mut make_agent(), &mut make_termination(), &best_action))
+        .collect();
+    let n = episodes.max(1) as f64;
+    let mean_reward = results.iter().map(|r| r.total_reward).sum::<f64>() / n;
+    let variance = results
+        .iter()
+        .map(|r| (r.total_reward - mean_reward).powi(2))
+        .sum::<f64>()
+        / n;
+    EvalStats {
+        episodes,
+        mean_reward,
+        std_reward: variance.sqrt(),
+        mean_steps: results.iter().map(|r| r.steps as f64).sum::<f64>() / n,
+    }
+}
@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for drawing the agents that training episodes start with.
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+/// Draws a fresh agent for every episode of
+/// [AgentTrainer::train_sampled()](crate::AgentTrainer::train_sampled), so that episodes can
+/// start in different states. Closures returning an agent are samplers, which can change what
+/// they return as training goes on, such as for a curriculum that starts ever further from the
+/// goal. [WeightedStarts] draws a start state with a weight for every state.
+pub trait StartSampler<G> {
+    /// Returns the agent the next episode starts with.
+    fn sample(&mut self) -> G;
+}
+
+impl<G, F: FnMut() -> G> StartSampler<G> for F {
+    fn sample(&mut self) -> G {
+        self()
+    }
+}
+
+/// A [StartSampler] which draws every start state with a probability in proportion to its
+/// weight, and makes the agent of the episode from it, so that rare start states can be
+/// oversampled, or every stratum of the start states can be given a share of the episodes.
+///
+/// # Example
+///
+/// ```
+/// use rurel::{StartSampler, WeightedStarts};
+///
+/// // Start three times as often at 3 as at 0
+/// let mut sampler = WeightedStarts::new([(0, 1.0), (3, 3.0)], |start: i32| start).with_seed(7);
+/// let threes = (0..10_000).filter(|_| sampler.sample() == 3).count();
+/// assert!((threes as f64 / 10_000.0 - 0.75).abs() < 0.02);
+/// ```
+pub struct WeightedStarts<S, F> {
+    starts: Vec<S>,
+    weights: WeightedIndex<f64>,
+    make_agent: F,
+    rng: ChaCha12Rng,
+}
+
+impl<S, F> WeightedStarts<S, F> {
+    /// Creates a sampler which draws among `starts`, pairs of a start state and its weight, and
+    /// makes the agent of every episode from the state drawn with `make_agent`. The draws are
+    /// seeded from the entropy of the system.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no start states, or if a weight is negative or not finite, or if all
+    /// weights are `0`.
+    pub fn new(starts: impl IntoIterator<Item = (S, f64)>, make_agent: F) -> WeightedStarts<S, F> {
+        let (starts, weights): (Vec<S>, Vec<f64>) = starts.into_iter().unzip();
+        assert!(
+            weights.iter().all(|w| w.is_finite()),
+            "the weights of the start states must be finite"
+        );
+        let weights = WeightedIndex::new(weights)
+            .expect("there must be start states with non-negative weights, not all of them 0");
+        WeightedStarts {
+            starts,
+            weights,
+            make_agent,
+            rng: ChaCha12Rng::from_entropy(),
+        }
+    }
+
+    /// Seeds the draws with `seed`, so that the same start states are drawn in the same order
+    /// every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl<S, G, F> StartSampler<G> for WeightedStarts<S, F>
+where
+    S: Clone,
+    F: FnMut(S) -> G,
+{
+    fn sample(&mut self) -> G {
+        let start = self.starts[self.weights.sample(&mut self.rng)].clone();
+        (self.make_agent)(start)
+    }
+}
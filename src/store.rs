@@ -0,0 +1,322 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the storage of learned values.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::mdp::State;
+use crate::QValue;
+
+/// Storage for the values learned by an [AgentTrainer](crate::AgentTrainer), which the learning
+/// and exploration strategies read and update the values through.
+///
+/// By default, values are stored in a `HashMap` from states to the values of their actions, which
+/// is the reference implementation of this trait. Other implementations can keep values in a
+/// database, a memory-mapped file, or a dense array, and are used with
+/// [AgentTrainer::with_store()](crate::AgentTrainer::with_store). [KeyedStore] keeps them under a
+/// compact key of every state, rather than under the state itself. Some methods of the trainer,
+/// such as saving and merging, need to iterate over all values, and are only available for the
+/// default storage.
+///
+/// # Example
+///
+/// ```
+/// use std::borrow::Cow;
+/// use std::collections::HashMap;
+///
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::{AgentTrainer, ValueStore};
+///
+/// // A `Walker` steps right with `true` and left with `false` between `Position(0)` and
+/// // `Position(4)`, and is rewarded with 1 at 4
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(usize);
+/// #
+/// # impl State for Position {
+/// #     type A = bool;
+/// #     fn reward(&self) -> f64 {
+/// #         if self.0 == 4 { 1.0 } else { 0.0 }
+/// #     }
+/// #     fn actions(&self) -> Vec<bool> {
+/// #         vec![false, true]
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, right: &bool) {
+/// #         let x = self.0 .0;
+/// #         self.0 = Position(if *right { (x + 1).min(4) } else { x.saturating_sub(1) });
+/// #     }
+/// # }
+///
+/// /// Stores the values of both actions of every position next to each other.
+/// #[derive(Default)]
+/// struct Dense(Vec<[Option<f64>; 2]>);
+///
+/// impl ValueStore<Position> for Dense {
+///     fn get(&self, state: &Position, right: &bool) -> Option<f64> {
+///         self.0.get(state.0)?[*right as usize]
+///     }
+///     fn set(&mut self, state: &Position, right: &bool, value: f64) -> Option<f64> {
+///         if self.0.len() <= state.0 {
+///             self.0.resize(state.0 + 1, [None; 2]);
+///         }
+///         self.0[state.0][*right as usize].replace(value)
+///     }
+///     fn values(&self, state: &Position) -> Option<Cow<'_, HashMap<bool, f64>>> {
+///         let values: HashMap<bool, f64> = [false, true]
+///             .into_iter()
+///             .filter_map(|right| Some((right, self.get(state, &right)?)))
+///             .collect();
+///         if values.is_empty() { None } else { Some(Cow::Owned(values)) }
+///     }
+/// }
+///
+/// let mut trainer = AgentTrainer::with_store(Dense::default());
+/// trainer.train(
+///     &mut Walker(Position(0)),
+///     &QLearning::new(0.2, 0.9, 0.0),
+///     &mut FixedIterations::new(2000),
+///     &RandomExploration,
+/// );
+/// assert_eq!(trainer.best_action(&Position(2)), Some(true));
+/// assert_eq!(trainer.store().0.len(), 5);
+/// ```
+pub trait ValueStore<S: State, V: QValue = f64> {
+    /// Returns the value learned for `action` in `state`, or `None` if no value was learned.
+    fn get(&self, state: &S, action: &S::A) -> Option<V>;
+
+    /// Stores `value` as the value learned for `action` in `state`, returning the value it
+    /// replaces, if any.
+    fn set(&mut self, state: &S, action: &S::A, value: V) -> Option<V>;
+
+    /// Returns the values learned for the actions of `state`, by action, or `None` if no value
+    /// was learned for `state`. Storage which doesn't keep these maps can return an owned map.
+    fn values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, V>>>;
+
+    /// Returns the action with the highest learned value in `state`, together with that value,
+    /// or `None` if no value was learned for `state`. Ties are broken arbitrarily.
+    fn best(&self, state: &S) -> Option<(S::A, V)> {
+        self.values(state)?
+            .iter()
+            .max_by(|(_, v1), (_, v2)| v1.partial_cmp(v2).unwrap())
+            .map(|(a, &v)| (a.clone(), v))
+    }
+
+    /// Returns the highest value learned in `state`, or `None` if no value was learned for
+    /// `state`.
+    fn max(&self, state: &S) -> Option<V> {
+        self.best(state).map(|(_, v)| v)
+    }
+
+    /// Returns the number of states values were learned for, or `None` if the storage can't
+    /// tell, which is the default.
+    fn state_count(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the number of values learned, one for every state and action, or `None` if the
+    /// storage can't tell, which is the default.
+    fn entry_count(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<S: State, V: QValue> ValueStore<S, V> for HashMap<S, HashMap<S::A, V>> {
+    fn get(&self, state: &S, action: &S::A) -> Option<V> {
+        self.get(state)?.get(action).copied()
+    }
+
+    fn set(&mut self, state: &S, action: &S::A, value: V) -> Option<V> {
+        self.entry(state.clone())
+            .or_default()
+            .insert(action.clone(), value)
+    }
+
+    fn values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, V>>> {
+        self.get(state).map(Cow::Borrowed)
+    }
+
+    fn max(&self, state: &S) -> Option<V> {
+        self.get(state)?
+            .values()
+            .copied()
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+    }
+
+    fn state_count(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn entry_count(&self) -> Option<usize> {
+        Some(self.values().map(HashMap::len).sum())
+    }
+}
+
+/// Storage which keeps the values of every state under a compact key computed from the state,
+/// such as a `u64` [Zobrist hash](https://en.wikipedia.org/wiki/Zobrist_hashing) of a board,
+/// instead of under a clone of the state. For large composite states, this takes much less
+/// memory than the default storage, and lookups hash a small key rather than the whole state.
+///
+/// Distinct states with the same key share their values: the trainer can't tell them apart, so
+/// it learns a mix of their values, and its best action in one may be a bad action in the
+/// other. A good key is therefore injective over the states that are actually visited. A key
+/// that keeps all of the state, packed into fewer bits, can't collide. A hash, such as a Zobrist
+/// hash, collides rarely if it has enough bits for the number of states: with 64 bits, a collision
+/// among a billion states is still unlikely, while with 32 bits it is likely after a hundred
+/// thousand. Leave out of the key only what neither the rewards nor the outcomes of the actions
+/// depend on.
+///
+/// The learned values can't be iterated by state, as the states aren't kept, so the methods of
+/// the trainer which need to, such as saving, are only available for the default storage. The
+/// visit counts of [AgentTrainer::with_visit_counts()](crate::AgentTrainer::with_visit_counts)
+/// are still kept by state.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::{AgentTrainer, KeyedStore};
+///
+/// // Four lights, which are toggled one at a time until they are all on
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Lights([bool; 4]);
+///
+/// impl State for Lights {
+///     type A = usize;
+///     fn reward(&self) -> f64 {
+///         if self.is_terminal() { 1.0 } else { 0.0 }
+///     }
+///     fn actions(&self) -> Vec<usize> {
+///         (0..4).collect()
+///     }
+///     fn is_terminal(&self) -> bool {
+///         self.0.iter().all(|&on| on)
+///     }
+/// }
+///
+/// struct Switcher(Lights);
+///
+/// impl Agent<Lights> for Switcher {
+///     fn current_state(&self) -> &Lights {
+///         &self.0
+///     }
+///     fn take_action(&mut self, light: &usize) {
+///         self.0 .0[*light] = !self.0 .0[*light];
+///     }
+/// }
+///
+/// // A Zobrist hash: the XOR of a random number for every light that is on
+/// const ZOBRIST: [u64; 4] = [
+///     0x9e37_79b9_7f4a_7c15,
+///     0xbf58_476d_1ce4_e5b9,
+///     0x94d0_49bb_1331_11eb,
+///     0x2545_f491_4f6c_dd1d,
+/// ];
+/// let zobrist = |lights: &Lights| {
+///     (0..4)
+///         .filter(|&i| lights.0[i])
+///         .fold(0u64, |hash, i| hash ^ ZOBRIST[i])
+/// };
+///
+/// let mut trainer: AgentTrainer<Lights, f64, _> =
+///     AgentTrainer::with_store(KeyedStore::new(zobrist));
+/// for _ in 0..200 {
+///     trainer.train(
+///         &mut Switcher(Lights([false; 4])),
+///         &QLearning::new(0.2, 0.9, 0.0),
+///         &mut FixedIterations::new(50),
+///         &RandomExploration,
+///     );
+/// }
+///
+/// assert_eq!(trainer.best_action(&Lights([true, true, false, true])), Some(2));
+/// // Every light switched off can be switched on, and the last state is terminal
+/// assert_eq!(trainer.store().len(), 15);
+/// ```
+pub struct KeyedStore<S, K, F, V = f64>
+where
+    S: State,
+    F: Fn(&S) -> K,
+{
+    key: F,
+    values: HashMap<K, HashMap<S::A, V>>,
+}
+
+impl<S, K, F, V> KeyedStore<S, K, F, V>
+where
+    S: State,
+    K: Eq + Hash,
+    F: Fn(&S) -> K,
+    V: QValue,
+{
+    /// Creates empty storage, which keeps the values of every state under `key(state)`.
+    pub fn new(key: F) -> KeyedStore<S, K, F, V> {
+        KeyedStore {
+            key,
+            values: HashMap::new(),
+        }
+    }
+
+    /// Returns the key the values of `state` are kept under.
+    pub fn key(&self, state: &S) -> K {
+        (self.key)(state)
+    }
+
+    /// Returns the number of keys values were learned for.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns whether no value was learned.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl<S, K, F, V> ValueStore<S, V> for KeyedStore<S, K, F, V>
+where
+    S: State,
+    K: Eq + Hash,
+    F: Fn(&S) -> K,
+    V: QValue,
+{
+    fn get(&self, state: &S, action: &S::A) -> Option<V> {
+        self.values.get(&self.key(state))?.get(action).copied()
+    }
+
+    fn set(&mut self, state: &S, action: &S::A, value: V) -> Option<V> {
+        self.values
+            .entry(self.key(state))
+            .or_default()
+            .insert(action.clone(), value)
+    }
+
+    fn values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, V>>> {
+        self.values.get(&self.key(state)).map(Cow::Borrowed)
+    }
+
+    /// The number of keys, which is that of the states unless keys collide.
+    fn state_count(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn entry_count(&self) -> Option<usize> {
+        Some(self.values.values().map(HashMap::len).sum())
+    }
+}
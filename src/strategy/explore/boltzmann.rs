@@ -0,0 +1,105 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the Boltzmann (softmax) exploration strategy.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::distributions::{Distribution, WeightedIndex};
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::{uniform_probabilities, ExplorationStrategy};
+use crate::{QValue, ValueStore};
+
+/// The Boltzmann exploration strategy. This strategy picks an action from the set of actions in
+/// the current state with a probability proportional to `exp(q / temperature)`, where `q` is the
+/// learned value of the action. Actions without a learned value are assumed to have value `0`.
+///
+/// A high temperature makes all actions almost equally likely, while a temperature approaching
+/// zero makes this strategy pick the highest valued action (breaking ties uniformly at random).
+/// If no values were learned for the current state yet, or when used through
+/// [pick_action()](ExplorationStrategy::pick_action), a random action is taken instead.
+pub struct BoltzmannExploration {
+    temperature: f64,
+}
+
+impl BoltzmannExploration {
+    /// Constructs the Boltzmann exploration strategy with the given `temperature`.
+    pub fn new(temperature: f64) -> BoltzmannExploration {
+        BoltzmannExploration { temperature }
+    }
+
+    /// Returns the (unnormalized) weight of each of the given actions.
+    fn weights<A: Eq + Hash, V: QValue>(
+        &self,
+        actions: &[A],
+        action_values: &HashMap<A, V>,
+    ) -> Vec<f64> {
+        let qs: Vec<f64> = actions
+            .iter()
+            .map(|a| action_values.get(a).map_or(0.0, |v| v.to_f64()))
+            .collect();
+        // Subtract the maximum before exponentiating, so that low temperatures don't overflow
+        let max = qs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        qs.iter()
+            .map(|&q| {
+                if q == max {
+                    1.0
+                } else {
+                    ((q - max) / self.temperature).exp()
+                }
+            })
+            .collect()
+    }
+}
+
+impl<S: State, V: QValue> ExplorationStrategy<S, V> for BoltzmannExploration {
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        agent.pick_random_action()
+    }
+
+    fn pick_action_with_values(
+        &self,
+        agent: &mut dyn Agent<S>,
+        values: &dyn ValueStore<S, V>,
+    ) -> S::A {
+        let state = agent.current_state();
+        let action_values = match values.values(state) {
+            Some(action_values) => action_values,
+            None => return agent.pick_random_action(),
+        };
+        let actions = state.actions();
+        let weights = self.weights(&actions, &action_values);
+        match WeightedIndex::new(weights) {
+            Ok(dist) => {
+                let action = actions[dist.sample(&mut rand::thread_rng())].clone();
+                agent.take_action(&action);
+                action
+            }
+            Err(_) => agent.pick_random_action(),
+        }
+    }
+
+    fn action_probabilities(
+        &self,
+        state: &S,
+        values: &dyn ValueStore<S, V>,
+    ) -> Option<HashMap<S::A, f64>> {
+        let actions = state.actions();
+        let action_values = match values.values(state) {
+            Some(action_values) => action_values,
+            None => return Some(uniform_probabilities(state)),
+        };
+        let weights = self.weights(&actions, &action_values);
+        let total: f64 = weights.iter().sum();
+        Some(
+            actions
+                .into_iter()
+                .zip(weights)
+                .map(|(a, w)| (a, w / total))
+                .collect(),
+        )
+    }
+}
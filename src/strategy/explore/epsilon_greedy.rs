@@ -0,0 +1,420 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the epsilon-greedy exploration strategies, and the greedy strategy which never
+//! explores.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::random::{restore_rng, rng_state};
+use crate::strategy::explore::{greedy_actions, uniform_probabilities, ExplorationStrategy};
+use crate::{QValue, RurelError, ValueStore};
+
+/// The epsilon-greedy exploration strategy. With probability `epsilon` this strategy takes a
+/// random action, as defined for the Agent by
+/// [Agent::pick_random_action()](../../../mdp/trait.Agent.html#method.pick_random_action).
+/// Otherwise it takes the action with the highest learned value in the current state.
+///
+/// If multiple actions share the highest value, one of them is picked uniformly at random. If no
+/// values were learned for the current state yet, a random action is taken. When used through
+/// [pick_action()](ExplorationStrategy::pick_action), without access to learned values, this
+/// strategy always takes a random action.
+///
+/// The random choices are made with the thread's random generator, unless the strategy is seeded
+/// with [with_seed()](EpsilonGreedy::with_seed), which makes training reproducible.
+pub struct EpsilonGreedy {
+    epsilon: f64,
+    rng: Option<RefCell<ChaCha12Rng>>,
+}
+
+impl EpsilonGreedy {
+    /// Constructs the epsilon-greedy strategy, which explores with probability `epsilon`.
+    pub fn new(epsilon: f64) -> EpsilonGreedy {
+        EpsilonGreedy { epsilon, rng: None }
+    }
+
+    /// Makes all random choices with a generator seeded with `seed`, so that the same actions
+    /// are picked every time for the same learned values. Random actions are then picked with
+    /// [Agent::pick_random_action_with()](../../../mdp/trait.Agent.html#method.pick_random_action_with).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::EpsilonGreedy;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    /// use rurel::AgentTrainer;
+    ///
+    /// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(10)`, rewarded by how
+    /// // close it is to 5
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct Position(i32);
+    /// #
+    /// # impl State for Position {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -(self.0 - 5).abs() as f64
+    /// #     }
+    /// #     fn actions(&self) -> Vec<i32> {
+    /// #         vec![-1, 1]
+    /// #     }
+    /// # }
+    /// #
+    /// # struct Walker(Position);
+    /// #
+    /// # impl Agent<Position> for Walker {
+    /// #     fn current_state(&self) -> &Position {
+    /// #         &self.0
+    /// #     }
+    /// #     fn take_action(&mut self, step: &i32) {
+    /// #         self.0 = Position((self.0 .0 + step).clamp(0, 10));
+    /// #     }
+    /// # }
+    ///
+    /// let train = |seed| {
+    ///     let mut trainer = AgentTrainer::new();
+    ///     trainer.train(
+    ///         &mut Walker(Position(0)),
+    ///         &QLearning::new(0.2, 0.9, 0.0),
+    ///         &mut FixedIterations::new(5000),
+    ///         &EpsilonGreedy::new(0.2).with_seed(seed),
+    ///     );
+    ///     trainer.export_learned_values()
+    /// };
+    ///
+    /// assert!(train(42) == train(42));
+    /// assert!(train(42) != train(43));
+    /// ```
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Some(RefCell::new(ChaCha12Rng::seed_from_u64(seed)));
+        self
+    }
+}
+
+impl<S: State, V: QValue> ExplorationStrategy<S, V> for EpsilonGreedy {
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        match &self.rng {
+            Some(rng) => agent.pick_random_action_with(&mut *rng.borrow_mut()),
+            None => agent.pick_random_action(),
+        }
+    }
+
+    fn pick_action_with_values(
+        &self,
+        agent: &mut dyn Agent<S>,
+        values: &dyn ValueStore<S, V>,
+    ) -> S::A {
+        match &self.rng {
+            Some(rng) => {
+                pick_epsilon_greedy_with(agent, values, self.epsilon, &mut *rng.borrow_mut())
+            }
+            None => pick_epsilon_greedy(agent, values, self.epsilon),
+        }
+    }
+
+    fn action_probabilities(
+        &self,
+        state: &S,
+        values: &dyn ValueStore<S, V>,
+    ) -> Option<HashMap<S::A, f64>> {
+        Some(epsilon_greedy_probabilities(state, values, self.epsilon))
+    }
+
+    /// The seed of the generator and how far it has advanced, or no state if it isn't seeded.
+    fn save_state(&self) -> Vec<u64> {
+        self.rng
+            .as_ref()
+            .map_or_else(Vec::new, |rng| rng_state(&rng.borrow()))
+    }
+
+    fn restore_state(&self, state: &[u64]) -> Result<(), RurelError> {
+        match &self.rng {
+            Some(rng) => *rng.borrow_mut() = restore_rng(state)?,
+            None if !state.is_empty() => {
+                return Err(RurelError::InvalidFormat(
+                    "the epsilon-greedy strategy isn't seeded".to_string(),
+                ))
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// The greedy strategy, which never explores: it always takes the action with the highest
+/// learned value in the current state, like an [EpsilonGreedy] strategy with an epsilon of `0`.
+/// Use it when deploying or evaluating a trained model through an [ExplorationStrategy], so that
+/// the agent stops exploring once training is over.
+///
+/// If multiple actions share the highest value, one of them is picked uniformly at random. If no
+/// values were learned for the current state, there is nothing to be greedy about, and a random
+/// action is taken, also when used through [pick_action()](ExplorationStrategy::pick_action).
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::{EpsilonGreedy, ExplorationStrategy, GreedyExploration};
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(10)`, rewarded by how close
+/// // it is to 5
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         -(self.0 - 5).abs() as f64
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 1]
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(0, 10));
+/// #     }
+/// # }
+///
+/// // Train while exploring...
+/// let mut trainer = AgentTrainer::new();
+/// for _ in 0..200 {
+///     trainer.train(
+///         &mut Walker(Position(0)),
+///         &QLearning::new(0.2, 0.9, 0.0),
+///         &mut FixedIterations::new(20),
+///         &EpsilonGreedy::new(0.3),
+///     );
+/// }
+///
+/// // ...then follow what was learned, without exploring
+/// let mut agent = Walker(Position(0));
+/// for _ in 0..5 {
+///     GreedyExploration.pick_action_with_values(&mut agent, trainer.store());
+/// }
+/// assert_eq!(agent.current_state().0, 5);
+/// ```
+pub struct GreedyExploration;
+
+impl Default for GreedyExploration {
+    fn default() -> Self {
+        Self
+    }
+}
+
+impl<S: State, V: QValue> ExplorationStrategy<S, V> for GreedyExploration {
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        agent.pick_random_action()
+    }
+
+    fn pick_action_with_values(
+        &self,
+        agent: &mut dyn Agent<S>,
+        values: &dyn ValueStore<S, V>,
+    ) -> S::A {
+        pick_epsilon_greedy(agent, values, 0.0)
+    }
+
+    fn action_probabilities(
+        &self,
+        state: &S,
+        values: &dyn ValueStore<S, V>,
+    ) -> Option<HashMap<S::A, f64>> {
+        Some(epsilon_greedy_probabilities(state, values, 0.0))
+    }
+}
+
+/// How the epsilon of a [DecayingEpsilonGreedy] strategy decreases after every action.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EpsilonDecay {
+    /// Subtracts the given amount from epsilon after every action.
+    Linear(f64),
+    /// Multiplies epsilon by the given factor after every action.
+    Exponential(f64),
+}
+
+/// The epsilon-greedy exploration strategy, with an epsilon that decays every time an action is
+/// picked. Epsilon starts at `start` and decreases according to the [EpsilonDecay] until it
+/// reaches `end`, after which it stays at `end`.
+///
+/// Actions are picked in the same way as by the [EpsilonGreedy] strategy.
+pub struct DecayingEpsilonGreedy {
+    epsilon: Cell<f64>,
+    end: f64,
+    decay: EpsilonDecay,
+}
+
+impl DecayingEpsilonGreedy {
+    /// Constructs the decaying epsilon-greedy strategy, with epsilon starting at `start` and
+    /// decaying according to `decay` until it reaches `end`.
+    pub fn new(start: f64, end: f64, decay: EpsilonDecay) -> DecayingEpsilonGreedy {
+        DecayingEpsilonGreedy {
+            epsilon: Cell::new(start.max(end)),
+            end,
+            decay,
+        }
+    }
+
+    /// Returns the epsilon that will be used for picking the next action.
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon.get()
+    }
+
+    /// Returns the current epsilon, and decays it for the next action.
+    fn next_epsilon(&self) -> f64 {
+        let epsilon = self.epsilon.get();
+        let decayed = match self.decay {
+            EpsilonDecay::Linear(step) => epsilon - step,
+            EpsilonDecay::Exponential(factor) => epsilon * factor,
+        };
+        self.epsilon.set(decayed.max(self.end));
+        epsilon
+    }
+}
+
+impl<S: State, V: QValue> ExplorationStrategy<S, V> for DecayingEpsilonGreedy {
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        self.next_epsilon();
+        agent.pick_random_action()
+    }
+
+    fn pick_action_with_values(
+        &self,
+        agent: &mut dyn Agent<S>,
+        values: &dyn ValueStore<S, V>,
+    ) -> S::A {
+        pick_epsilon_greedy(agent, values, self.next_epsilon())
+    }
+
+    /// Returns the probabilities for the current epsilon.
+    fn action_probabilities(
+        &self,
+        state: &S,
+        values: &dyn ValueStore<S, V>,
+    ) -> Option<HashMap<S::A, f64>> {
+        Some(epsilon_greedy_probabilities(state, values, self.epsilon()))
+    }
+
+    /// The current epsilon.
+    fn save_state(&self) -> Vec<u64> {
+        vec![self.epsilon().to_bits()]
+    }
+
+    fn restore_state(&self, state: &[u64]) -> Result<(), RurelError> {
+        match state {
+            [epsilon] => {
+                self.epsilon.set(f64::from_bits(*epsilon));
+                Ok(())
+            }
+            _ => Err(RurelError::InvalidFormat(
+                "invalid state of a decaying epsilon-greedy strategy".to_string(),
+            )),
+        }
+    }
+}
+
+/// Takes a random action with probability `epsilon`, and the best known action otherwise.
+fn pick_epsilon_greedy<S: State, V: QValue>(
+    agent: &mut dyn Agent<S>,
+    values: &dyn ValueStore<S, V>,
+    epsilon: f64,
+) -> S::A {
+    if rand::random::<f64>() < epsilon {
+        return agent.pick_random_action();
+    }
+    match best_action(agent.current_state(), values, &mut rand::thread_rng()) {
+        Some(action) => {
+            agent.take_action(&action);
+            action
+        }
+        None => agent.pick_random_action(),
+    }
+}
+
+/// Like [pick_epsilon_greedy()], but makes all random choices with `rng`.
+fn pick_epsilon_greedy_with<S: State, V: QValue>(
+    agent: &mut dyn Agent<S>,
+    values: &dyn ValueStore<S, V>,
+    epsilon: f64,
+    rng: &mut dyn RngCore,
+) -> S::A {
+    if rng.gen::<f64>() < epsilon {
+        return agent.pick_random_action_with(rng);
+    }
+    match best_action(agent.current_state(), values, rng) {
+        Some(action) => {
+            agent.take_action(&action);
+            action
+        }
+        None => agent.pick_random_action_with(rng),
+    }
+}
+
+/// Returns the action with the highest value in `state`, or `None` if there are no values.
+/// Ties between equally valued actions are broken with `rng`, among the tied actions in the
+/// order of [State::actions()], so that a seeded `rng` breaks them the same way every time.
+fn best_action<S: State, V: QValue>(
+    state: &S,
+    values: &dyn ValueStore<S, V>,
+    rng: &mut dyn RngCore,
+) -> Option<S::A> {
+    let action_values = values.values(state)?;
+    match &*greedy_actions(&action_values) {
+        [] => None,
+        [action] => Some((*action).clone()),
+        tied => {
+            let ordered: Vec<S::A> = state
+                .actions()
+                .into_iter()
+                .filter(|a| tied.contains(&a))
+                .collect();
+            match ordered.choose(rng) {
+                Some(action) => Some(action.clone()),
+                None => tied.choose(rng).map(|&a| a.clone()),
+            }
+        }
+    }
+}
+
+/// Returns the probabilities with which [pick_epsilon_greedy()] picks the actions of `state`,
+/// assuming that [State::random_action()] picks actions uniformly at random.
+fn epsilon_greedy_probabilities<S: State, V: QValue>(
+    state: &S,
+    values: &dyn ValueStore<S, V>,
+    epsilon: f64,
+) -> HashMap<S::A, f64> {
+    let mut probabilities = uniform_probabilities(state);
+    let action_values = values.values(state);
+    let best = action_values
+        .as_deref()
+        .map(greedy_actions)
+        .unwrap_or_default();
+    if best.is_empty() {
+        return probabilities;
+    }
+    for p in probabilities.values_mut() {
+        *p *= epsilon;
+    }
+    let p_greedy = (1.0 - epsilon) / best.len() as f64;
+    for action in best {
+        *probabilities.entry(action.clone()).or_insert(0.0) += p_greedy;
+    }
+    probabilities
+}
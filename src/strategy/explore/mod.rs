@@ -3,15 +3,132 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 //! Module containing exploration strategies.
+//!
+//! Exploring is only meant for training: [GreedyExploration] always takes the best known action,
+//! and is the strategy to evaluate or deploy a trained model with.
 
-pub use self::random::RandomExploration;
+use std::collections::HashMap;
+
+pub use self::boltzmann::BoltzmannExploration;
+pub use self::epsilon_greedy::{
+    DecayingEpsilonGreedy, EpsilonDecay, EpsilonGreedy, GreedyExploration,
+};
+pub use self::random::{RandomExploration, SeededRandomExploration};
+pub use self::ucb1::UCB1;
 use crate::mdp::{Agent, State};
+use crate::{QValue, RurelError, ValueStore};
 
+pub mod boltzmann;
+pub mod epsilon_greedy;
 pub mod random;
+pub mod ucb1;
 
 /// Trait for exploration strategies. An exploration strategy decides, based on an `Agent`, which
 /// action to take next.
-pub trait ExplorationStrategy<S: State> {
+pub trait ExplorationStrategy<S: State, V: QValue = f64> {
     /// Selects the next action to take for this `Agent`.
     fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A;
+
+    /// Selects the next action to take for this `Agent`, given the values learned so far
+    /// (`values`). The default implementation ignores the values and calls
+    /// [pick_action()](ExplorationStrategy::pick_action).
+    ///
+    /// [AgentTrainer::train()](../../struct.AgentTrainer.html#method.train) calls this method
+    /// with its learned values, so value-aware strategies should override it. For example, a
+    /// strategy which always tries the action it knows the least about:
+    ///
+    /// ```
+    /// use rurel::mdp::{Agent, State};
+    /// use rurel::strategy::explore::ExplorationStrategy;
+    /// use rurel::ValueStore;
+    ///
+    /// struct Pessimist;
+    ///
+    /// impl<S: State> ExplorationStrategy<S> for Pessimist {
+    ///     fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+    ///         agent.pick_random_action()
+    ///     }
+    ///
+    ///     fn pick_action_with_values(
+    ///         &self,
+    ///         agent: &mut dyn Agent<S>,
+    ///         values: &dyn ValueStore<S>,
+    ///     ) -> S::A {
+    ///         let worst = values.values(agent.current_state()).and_then(|m| {
+    ///             m.iter()
+    ///                 .min_by(|(_, v1), (_, v2)| v1.partial_cmp(v2).unwrap())
+    ///                 .map(|(a, _)| a.clone())
+    ///         });
+    ///         match worst {
+    ///             Some(action) => {
+    ///                 agent.take_action(&action);
+    ///                 action
+    ///             }
+    ///             None => agent.pick_random_action(),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn pick_action_with_values(
+        &self,
+        agent: &mut dyn Agent<S>,
+        _values: &dyn ValueStore<S, V>,
+    ) -> S::A {
+        self.pick_action(agent)
+    }
+
+    /// Returns the probability with which every action in `state` would be picked, given the
+    /// values learned so far (`values`), or `None` if these probabilities are not known. The
+    /// default implementation returns `None`.
+    fn action_probabilities(
+        &self,
+        _state: &S,
+        _values: &dyn ValueStore<S, V>,
+    ) -> Option<HashMap<S::A, f64>> {
+        None
+    }
+
+    /// Returns the state of this strategy which changes as it picks actions, such as a decaying
+    /// epsilon or the position of its random generator, so that a checkpoint of training can
+    /// hold it. The default implementation returns no state.
+    fn save_state(&self) -> Vec<u64> {
+        Vec::new()
+    }
+
+    /// Restores the state returned by [save_state()](ExplorationStrategy::save_state), so that
+    /// the strategy continues where it was. Fails with [RurelError::InvalidFormat] if `state`
+    /// isn't a state of this strategy. The default implementation only accepts no state.
+    fn restore_state(&self, state: &[u64]) -> Result<(), RurelError> {
+        if state.is_empty() {
+            Ok(())
+        } else {
+            Err(RurelError::InvalidFormat(
+                "the exploration strategy has no state to restore".to_string(),
+            ))
+        }
+    }
+}
+
+/// Returns the actions of `state` with equal probability.
+fn uniform_probabilities<S: State>(state: &S) -> HashMap<S::A, f64> {
+    let actions = state.actions();
+    let p = 1.0 / actions.len() as f64;
+    actions.into_iter().map(|a| (a, p)).collect()
+}
+
+/// Returns the actions with the highest value, or an empty `Vec` if there are no values.
+fn greedy_actions<A, V: QValue>(action_values: &HashMap<A, V>) -> Vec<&A> {
+    let max = match action_values
+        .values()
+        .copied()
+        .max_by(|a, b| a.partial_cmp(b).unwrap())
+    {
+        Some(max) => max,
+        None => return Vec::new(),
+    };
+    action_values
+        .iter()
+        .filter(|&(_, &v)| v == max)
+        .map(|(a, _)| a)
+        .collect()
 }
@@ -2,8 +2,15 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
 use crate::mdp::{Agent, State};
-use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::explore::{uniform_probabilities, ExplorationStrategy};
+use crate::{QValue, RurelError, ValueStore};
 
 /// The random exploration strategy. This strategy always takes a random action, as defined for the
 /// Agent by
@@ -16,8 +23,140 @@ impl Default for RandomExploration {
     }
 }
 
-impl<S: State> ExplorationStrategy<S> for RandomExploration {
+impl<S: State, V: QValue> ExplorationStrategy<S, V> for RandomExploration {
     fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
         agent.pick_random_action()
     }
+
+    /// Assumes that [State::random_action()] picks actions uniformly at random.
+    fn action_probabilities(
+        &self,
+        state: &S,
+        _values: &dyn ValueStore<S, V>,
+    ) -> Option<HashMap<S::A, f64>> {
+        Some(uniform_probabilities(state))
+    }
+}
+
+/// The seeded random exploration strategy. Like [RandomExploration], this strategy always takes a
+/// random action, but it draws randomness from its own generator, seeded with a fixed value,
+/// through
+/// [Agent::pick_random_action_with()](../../../mdp/trait.Agent.html#method.pick_random_action_with).
+/// Training with the same seed, agent and deterministic learning strategy therefore always
+/// results in the same learned values.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::SeededRandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// // A `Walker` steps by -1, 0 or 1 between `Position(-10)` and `Position(10)`, and is rewarded by
+/// // how close it is to 0
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         -(self.0 as f64).abs()
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 0, 1]
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(-10, 10));
+/// #     }
+/// # }
+///
+/// let train = |seed| {
+///     let mut trainer = AgentTrainer::new();
+///     trainer.train(
+///         &mut Walker(Position(0)),
+///         &QLearning::new(0.2, 0.9, 0.0),
+///         &mut FixedIterations::new(5000),
+///         &SeededRandomExploration::new(seed),
+///     );
+///     trainer.export_learned_values()
+/// };
+///
+/// assert!(train(42) == train(42));
+/// assert!(train(42) != train(43));
+/// ```
+pub struct SeededRandomExploration {
+    rng: RefCell<ChaCha12Rng>,
+}
+
+impl SeededRandomExploration {
+    /// Constructs the seeded random exploration strategy, with its generator seeded with `seed`.
+    pub fn new(seed: u64) -> SeededRandomExploration {
+        SeededRandomExploration {
+            rng: RefCell::new(ChaCha12Rng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl<S: State, V: QValue> ExplorationStrategy<S, V> for SeededRandomExploration {
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        agent.pick_random_action_with(&mut *self.rng.borrow_mut())
+    }
+
+    /// Assumes that [State::random_action_with()] picks actions uniformly at random.
+    fn action_probabilities(
+        &self,
+        state: &S,
+        _values: &dyn ValueStore<S, V>,
+    ) -> Option<HashMap<S::A, f64>> {
+        Some(uniform_probabilities(state))
+    }
+
+    /// The seed of the generator, and how far it has advanced.
+    fn save_state(&self) -> Vec<u64> {
+        rng_state(&self.rng.borrow())
+    }
+
+    fn restore_state(&self, state: &[u64]) -> Result<(), RurelError> {
+        *self.rng.borrow_mut() = restore_rng(state)?;
+        Ok(())
+    }
+}
+
+/// Returns the state of `rng` as words: its seed, its stream, and its position in the stream.
+pub(crate) fn rng_state(rng: &ChaCha12Rng) -> Vec<u64> {
+    let mut state: Vec<u64> = rng
+        .get_seed()
+        .chunks(8)
+        .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+        .collect();
+    let position = rng.get_word_pos();
+    state.extend([rng.get_stream(), position as u64, (position >> 64) as u64]);
+    state
+}
+
+/// Returns the generator with the state returned by [rng_state()].
+pub(crate) fn restore_rng(state: &[u64]) -> Result<ChaCha12Rng, RurelError> {
+    if state.len() != 7 {
+        return Err(RurelError::InvalidFormat(
+            "invalid state of a random generator".to_string(),
+        ));
+    }
+    let mut seed = [0; 32];
+    for (bytes, word) in seed.chunks_mut(8).zip(&state[..4]) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    let mut rng = ChaCha12Rng::from_seed(seed);
+    rng.set_stream(state[4]);
+    rng.set_word_pos(state[5] as u128 | (state[6] as u128) << 64);
+    Ok(rng)
 }
@@ -0,0 +1,106 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the UCB1 exploration strategy.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::{QValue, ValueStore};
+
+/// The UCB1 (upper confidence bound) exploration strategy. This strategy keeps track of how often
+/// it picked every action in every state, and picks the action maximizing
+/// `q + c * sqrt(ln(total_visits) / action_visits)`, where `q` is the learned value of the action,
+/// `total_visits` the number of times an action was picked in the current state, and
+/// `action_visits` the number of times this action was picked in the current state.
+///
+/// Actions that were never picked in the current state take priority over all others, so every
+/// action is tried once first, in the order returned by [State::actions()]. Actions without a
+/// learned value are assumed to have value `0`.
+pub struct UCB1<S: State> {
+    c: f64,
+    counts: RefCell<HashMap<S, HashMap<S::A, u64>>>,
+}
+
+impl<S: State> UCB1<S> {
+    /// Constructs the UCB1 strategy with exploration constant `c`.
+    pub fn new(c: f64) -> UCB1<S> {
+        UCB1 {
+            c,
+            counts: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the number of times `action` was picked in `state` by this strategy.
+    pub fn visits(&self, state: &S, action: &S::A) -> u64 {
+        self.counts
+            .borrow()
+            .get(state)
+            .and_then(|m| m.get(action))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn pick<V: QValue>(
+        &self,
+        agent: &mut dyn Agent<S>,
+        action_values: Option<&HashMap<S::A, V>>,
+    ) -> S::A {
+        let state = agent.current_state().clone();
+        let action = {
+            let counts = self.counts.borrow();
+            let state_counts = counts.get(&state);
+            let actions = state.actions();
+            let count = |a: &S::A| state_counts.and_then(|m| m.get(a)).copied().unwrap_or(0);
+            match actions.iter().find(|a| count(a) == 0) {
+                Some(unvisited) => Some(unvisited.clone()),
+                None => {
+                    let total = actions.iter().map(count).sum::<u64>() as f64;
+                    let priority = |a: &S::A| {
+                        let q = action_values
+                            .and_then(|m| m.get(a))
+                            .map_or(0.0, |v| v.to_f64());
+                        q + self.c * (total.ln() / count(a) as f64).sqrt()
+                    };
+                    actions
+                        .iter()
+                        .max_by(|a, b| priority(a).partial_cmp(&priority(b)).unwrap())
+                        .cloned()
+                }
+            }
+        };
+        let action = match action {
+            Some(action) => {
+                agent.take_action(&action);
+                action
+            }
+            None => agent.pick_random_action(),
+        };
+        *self
+            .counts
+            .borrow_mut()
+            .entry(state)
+            .or_default()
+            .entry(action.clone())
+            .or_insert(0) += 1;
+        action
+    }
+}
+
+impl<S: State, V: QValue> ExplorationStrategy<S, V> for UCB1<S> {
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        self.pick::<V>(agent, None)
+    }
+
+    fn pick_action_with_values(
+        &self,
+        agent: &mut dyn Agent<S>,
+        values: &dyn ValueStore<S, V>,
+    ) -> S::A {
+        let action_values = values.values(agent.current_state());
+        self.pick(agent, action_values.as_deref())
+    }
+}
@@ -0,0 +1,106 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for schedules of the discount factor.
+
+use crate::strategy::learn::check_gamma;
+
+/// A schedule which gives the discount factor to train every episode with, for example to value
+/// the near future more at first, and the far future more as training goes on.
+///
+/// Schedules are applied by setting the discount factor before every episode, such as with
+/// [QLearning::set_gamma()](crate::strategy::learn::QLearning::set_gamma).
+///
+/// # Examples
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::{DiscountSchedule, LinearDiscount, QLearning};
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(9)`, and is rewarded with 1
+/// // at 9
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         if self.0 == 9 { 1.0 } else { 0.0 }
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 1]
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(0, 9));
+/// #     }
+/// # }
+///
+/// let schedule = LinearDiscount::new(0.9, 0.99, 100);
+/// let mut learning = QLearning::new(0.2, schedule.gamma(0), 0.0);
+/// let mut trainer = AgentTrainer::new();
+/// for episode in 0..200 {
+///     learning.set_gamma(schedule.gamma(episode));
+///     let mut agent = Walker(Position(0));
+///     trainer.train(
+///         &mut agent,
+///         &learning,
+///         &mut FixedIterations::new(100),
+///         &RandomExploration,
+///     );
+/// }
+/// assert_eq!(learning.gamma(), 0.99);
+/// assert_eq!(trainer.best_action(&Position(5)), Some(1));
+/// ```
+pub trait DiscountSchedule {
+    /// Returns the discount factor for the episode with index `episode`, counting from `0`.
+    fn gamma(&self, episode: usize) -> f64;
+}
+
+/// A [DiscountSchedule] which ramps the discount factor linearly from `start` to `end` over the
+/// given number of episodes, after which it stays at `end`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearDiscount {
+    start: f64,
+    end: f64,
+    episodes: usize,
+}
+
+impl LinearDiscount {
+    /// Constructs the schedule, which starts at `start` and reaches `end` at episode `episodes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` or `end` isn't between `0` and `1`.
+    pub fn new(start: f64, end: f64, episodes: usize) -> LinearDiscount {
+        check_gamma(start);
+        check_gamma(end);
+        LinearDiscount {
+            start,
+            end,
+            episodes,
+        }
+    }
+}
+
+impl DiscountSchedule for LinearDiscount {
+    fn gamma(&self, episode: usize) -> f64 {
+        if episode >= self.episodes {
+            self.end
+        } else {
+            let progress = episode as f64 / self.episodes as f64;
+            self.start + (self.end - self.start) * progress
+        }
+    }
+}
@@ -0,0 +1,231 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the Double Q Learning strategy.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::mdp::{State, Transition};
+use crate::strategy::explore::random::{restore_rng, rng_state};
+use crate::strategy::learn::{check_alpha, check_gamma, max_value, store, LearningStrategy};
+use crate::{QValue, RurelError, ValueStore};
+
+type ValueTable<S> = HashMap<S, HashMap<<S as State>::A, f64>>;
+
+/// The Double Q Learning strategy. Q learning tends to overestimate values in stochastic
+/// processes, because it uses the same estimates to both pick and evaluate the best next action.
+/// This strategy maintains two independent tables of values instead. After every transition, one
+/// of them is picked at random and updated, using the other table to evaluate the best next
+/// action according to the updated table.
+///
+/// Both tables are owned by this strategy. The values stored in the
+/// [AgentTrainer](../../../struct.AgentTrainer.html) are the averages of both tables, so
+/// [AgentTrainer::best_action()](../../../struct.AgentTrainer.html#method.best_action) and
+/// [AgentTrainer::expected_value()](../../../struct.AgentTrainer.html#method.expected_value)
+/// evaluate the average.
+///
+/// The table to update is picked with a generator seeded from the entropy of the system, unless
+/// it is seeded with [with_seed()](DoubleQLearning::with_seed). Ties between the best next
+/// actions are broken in the order of [State::actions()].
+///
+/// # Example
+///
+/// The maximization bias example from Sutton & Barto (example 6.7): from `A`, going right ends
+/// the episode, while going left leads to `B`, from which all actions end the episode with a
+/// reward averaging -0.1. Q learning overestimates the value of going left, Double Q learning
+/// much less so:
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use rand::{Rng, SeedableRng};
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::SeededRandomExploration;
+/// use rurel::strategy::learn::{DoubleQLearning, LearningStrategy, QLearning};
+/// use rurel::strategy::terminate::SinkStates;
+/// use rurel::AgentTrainer;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// enum Bias {
+///     A,
+///     B,
+///     // Terminal state, with the received reward in hundredths
+///     End(i32),
+/// }
+///
+/// impl State for Bias {
+///     type A = u8;
+///     fn reward(&self) -> f64 {
+///         match self {
+///             Bias::End(r) => *r as f64 / 100.0,
+///             _ => 0.0,
+///         }
+///     }
+///     fn actions(&self) -> Vec<u8> {
+///         match self {
+///             Bias::A => vec![0, 1], // left, right
+///             Bias::B => (0..10).collect(),
+///             Bias::End(_) => vec![],
+///         }
+///     }
+/// }
+///
+/// struct BiasAgent(Bias, StdRng);
+///
+/// impl Agent<Bias> for BiasAgent {
+///     fn current_state(&self) -> &Bias {
+///         &self.0
+///     }
+///     fn take_action(&mut self, action: &u8) {
+///         self.0 = match (&self.0, action) {
+///             (Bias::A, 0) => Bias::B,
+///             (Bias::A, _) => Bias::End(0),
+///             _ => Bias::End(self.1.gen_range(-110..=90)),
+///         };
+///     }
+/// }
+///
+/// // The mean learned value of going left over a few seeded runs, each with fresh strategies
+/// let value_of_left = |make_learning: &dyn Fn(u64) -> Box<dyn LearningStrategy<Bias>>| {
+///     let mut total = 0.0;
+///     for seed in 0..20 {
+///         let learning = make_learning(seed);
+///         let exploration = SeededRandomExploration::new(seed);
+///         let mut rewards = StdRng::seed_from_u64(seed);
+///         let mut trainer = AgentTrainer::new();
+///         for _ in 0..300 {
+///             let mut agent = BiasAgent(Bias::A, StdRng::seed_from_u64(rewards.gen()));
+///             trainer.train(&mut agent, &*learning, &mut SinkStates, &exploration);
+///         }
+///         total += trainer.expected_value(&Bias::A, &0).unwrap();
+///     }
+///     total / 20.0
+/// };
+///
+/// let q = value_of_left(&|_| Box::new(QLearning::new(0.1, 1.0, 0.0)));
+/// let double_q =
+///     value_of_left(&|seed| Box::new(DoubleQLearning::new(0.1, 1.0, 0.0).with_seed(seed)));
+/// assert!(double_q < q);
+/// ```
+pub struct DoubleQLearning<S: State> {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+    tables: RefCell<[ValueTable<S>; 2]>,
+    rng: RefCell<ChaCha12Rng>,
+}
+
+impl<S: State> DoubleQLearning<S> {
+    /// Constructs the Double Q Learning strategy, with learning rate `alpha`, discount factor
+    /// `gamma` and the initial value for Q `initial_value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, or if `gamma` isn't between `0` and `1`.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64) -> DoubleQLearning<S> {
+        check_alpha(alpha);
+        check_gamma(gamma);
+        DoubleQLearning {
+            alpha,
+            gamma,
+            initial_value,
+            tables: RefCell::new([HashMap::new(), HashMap::new()]),
+            rng: RefCell::new(ChaCha12Rng::from_entropy()),
+        }
+    }
+
+    /// Seeds the picking of the table to update with `seed`, so that the same tables are
+    /// updated in the same order every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = RefCell::new(ChaCha12Rng::seed_from_u64(seed));
+        self
+    }
+}
+
+impl<S: State, V: QValue> LearningStrategy<S, V> for DoubleQLearning<S> {
+    /// Without knowing the states involved, the second table can't be used, so this behaves like
+    /// Q learning on the averaged values.
+    fn value(
+        &self,
+        new_action_values: &Option<&HashMap<S::A, V>>,
+        old_value: &Option<&V>,
+        reward_after_action: f64,
+    ) -> f64 {
+        let max_next = max_value(*new_action_values).unwrap_or(self.initial_value);
+        old_value.map_or(self.initial_value, |x| {
+            let x = x.to_f64();
+            x + self.alpha * (reward_after_action + self.gamma * max_next - x)
+        })
+    }
+
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        let mut tables = self.tables.borrow_mut();
+        let known = tables[0]
+            .get(transition.state)
+            .is_some_and(|m| m.contains_key(transition.action));
+        let v = if known {
+            let [first, second] = &mut *tables;
+            let (updated, other) = if self.rng.borrow_mut().gen() {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            let next_value = if transition.next_state.is_terminal() {
+                0.0
+            } else {
+                updated
+                    .get(transition.next_state)
+                    .and_then(|m| best_action(transition.next_state, m))
+                    .and_then(|best| other.get(transition.next_state)?.get(&best))
+                    .copied()
+                    .unwrap_or(self.initial_value)
+            };
+            let x = updated[transition.state][transition.action];
+            let updated_value = x + self.alpha * (transition.reward + self.gamma * next_value - x);
+            updated
+                .get_mut(transition.state)
+                .unwrap()
+                .insert(transition.action.clone(), updated_value);
+            (updated_value + other[transition.state][transition.action]) / 2.0
+        } else {
+            for table in tables.iter_mut() {
+                table
+                    .entry(transition.state.clone())
+                    .or_default()
+                    .insert(transition.action.clone(), self.initial_value);
+            }
+            self.initial_value
+        };
+        store(q, transition.state, transition.action, v)
+    }
+
+    /// The seed of the generator picking the table to update, and how far it has advanced. The
+    /// two tables aren't part of the state, so a restored strategy starts with empty tables, and
+    /// learns every value as if it were unknown.
+    fn save_state(&self) -> Vec<u64> {
+        rng_state(&self.rng.borrow())
+    }
+
+    fn restore_state(&self, state: &[u64]) -> Result<(), RurelError> {
+        *self.rng.borrow_mut() = restore_rng(state)?;
+        Ok(())
+    }
+}
+
+/// Returns the first action of `state`, in the order of [State::actions()], with the highest of
+/// `action_values`, or `None` if none of its actions has a value.
+fn best_action<S: State>(state: &S, action_values: &HashMap<S::A, f64>) -> Option<S::A> {
+    state
+        .actions()
+        .into_iter()
+        .filter_map(|a| action_values.get(&a).map(|&v| (a, v)))
+        .fold(None, |best, (a, v)| match best {
+            Some((_, best_v)) if best_v >= v => best,
+            _ => Some((a, v)),
+        })
+        .map(|(a, _)| a)
+}
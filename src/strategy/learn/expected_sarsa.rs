@@ -0,0 +1,180 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the Expected SARSA strategy.
+
+use std::collections::HashMap;
+
+use crate::mdp::{State, Transition};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::learn::{check_alpha, check_gamma, max_value, store, LearningStrategy};
+use crate::{QValue, ValueStore};
+
+/// The Expected SARSA strategy. Instead of learning from the best action in the new state (like
+/// [QLearning](../q/struct.QLearning.html)) or from the action that was taken next (like
+/// [Sarsa](../sarsa/struct.Sarsa.html)), this strategy learns from the expected value of the next
+/// action under the exploration policy: the sum of `p(a) * q(a)` over the actions in the new
+/// state. Because it doesn't depend on which action happened to be sampled, its updates vary less
+/// than those of SARSA.
+///
+/// The probabilities are taken from
+/// [ExplorationStrategy::action_probabilities()](../../explore/trait.ExplorationStrategy.html#method.action_probabilities)
+/// of the given policy, which should be the exploration strategy used for training. If the policy
+/// doesn't know its probabilities, the best action is used instead, as in Q learning. Actions
+/// without a learned value are assumed to have value `initial_value`.
+///
+/// # Example
+///
+/// On a small corridor where reaching the right end is rewarded and starts the walk over,
+/// Expected SARSA learns the same policy as Q learning, and the values it learns vary much less
+/// between runs than those learned by SARSA:
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::EpsilonGreedy;
+/// use rurel::strategy::learn::{ExpectedSarsa, LearningStrategy, QLearning, Sarsa};
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Corridor(i32);
+///
+/// impl State for Corridor {
+///     type A = i32;
+///     fn reward(&self) -> f64 {
+///         if self.0 == 4 { 1.0 } else { 0.0 }
+///     }
+///     fn actions(&self) -> Vec<i32> {
+///         vec![-1, 1]
+///     }
+/// }
+///
+/// struct Walker(Corridor);
+///
+/// impl Agent<Corridor> for Walker {
+///     fn current_state(&self) -> &Corridor {
+///         &self.0
+///     }
+///     fn take_action(&mut self, step: &i32) {
+///         self.0 = match self.0 .0 {
+///             4 => Corridor(0),
+///             x => Corridor((x + step).clamp(0, 4)),
+///         };
+///     }
+/// }
+///
+/// let train = |learning: &dyn LearningStrategy<Corridor>, exploration: &EpsilonGreedy| {
+///     let mut trainer = AgentTrainer::new();
+///     trainer.train(
+///         &mut Walker(Corridor(0)),
+///         learning,
+///         &mut FixedIterations::new(20000),
+///         exploration,
+///     );
+///     trainer
+/// };
+///
+/// let exploration = EpsilonGreedy::new(0.2).with_seed(0);
+/// let q_learning = train(&QLearning::new(0.1, 0.9, 0.0), &exploration);
+/// let expected_sarsa = train(&ExpectedSarsa::new(0.1, 0.9, 0.0, &exploration), &exploration);
+/// for x in 0..4 {
+///     assert_eq!(q_learning.best_action(&Corridor(x)), Some(1));
+///     assert_eq!(expected_sarsa.best_action(&Corridor(x)), Some(1));
+/// }
+///
+/// // The standard deviation of a learned value over runs with different seeds
+/// let spread = |expected: bool| {
+///     let values: Vec<f64> = (0..10)
+///         .map(|seed| {
+///             let exploration = EpsilonGreedy::new(0.2).with_seed(seed);
+///             let trainer = if expected {
+///                 train(&ExpectedSarsa::new(0.1, 0.9, 0.0, &exploration), &exploration)
+///             } else {
+///                 train(&Sarsa::new(0.1, 0.9, 0.0), &exploration)
+///             };
+///             trainer.expected_value(&Corridor(2), &1).unwrap()
+///         })
+///         .collect();
+///     let mean = values.iter().sum::<f64>() / 10.0;
+///     (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 10.0).sqrt()
+/// };
+/// assert!(spread(true) < spread(false) / 10.0);
+/// ```
+pub struct ExpectedSarsa<'a, S: State, V: QValue = f64> {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+    policy: &'a dyn ExplorationStrategy<S, V>,
+}
+
+impl<'a, S: State, V: QValue> ExpectedSarsa<'a, S, V> {
+    /// Constructs the Expected SARSA strategy, with learning rate `alpha`, discount factor
+    /// `gamma`, the initial value for Q `initial_value`, and the exploration strategy whose action
+    /// probabilities are used (`policy`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, or if `gamma` isn't between `0` and `1`.
+    pub fn new(
+        alpha: f64,
+        gamma: f64,
+        initial_value: f64,
+        policy: &'a dyn ExplorationStrategy<S, V>,
+    ) -> ExpectedSarsa<'a, S, V> {
+        check_alpha(alpha);
+        check_gamma(gamma);
+        ExpectedSarsa {
+            alpha,
+            gamma,
+            initial_value,
+            policy,
+        }
+    }
+
+    fn update(&self, old_value: Option<&V>, reward: f64, next_value: f64) -> f64 {
+        old_value.map_or(self.initial_value, |x| {
+            let x = x.to_f64();
+            x + self.alpha * (reward + self.gamma * next_value - x)
+        })
+    }
+
+    fn max_value(&self, action_values: Option<&HashMap<S::A, V>>) -> f64 {
+        max_value(action_values).unwrap_or(self.initial_value)
+    }
+}
+
+impl<S: State, V: QValue> LearningStrategy<S, V> for ExpectedSarsa<'_, S, V> {
+    /// Without knowing the new state, the action probabilities are unknown, so this uses the
+    /// best action in the new state, as in Q learning.
+    fn value(
+        &self,
+        new_action_values: &Option<&HashMap<S::A, V>>,
+        old_value: &Option<&V>,
+        reward_after_action: f64,
+    ) -> f64 {
+        let max_next = self.max_value(*new_action_values);
+        self.update(*old_value, reward_after_action, max_next)
+    }
+
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        let v = {
+            let next_values = q.values(transition.next_state);
+            let next_values = next_values.as_deref();
+            let next_value = match (
+                next_values,
+                self.policy.action_probabilities(transition.next_state, &*q),
+            ) {
+                _ if transition.next_state.is_terminal() => 0.0,
+                (Some(m), Some(probabilities)) => probabilities
+                    .iter()
+                    .map(|(a, p)| p * m.get(a).map_or(self.initial_value, |v| v.to_f64()))
+                    .sum(),
+                _ => self.max_value(next_values),
+            };
+            let old_value = q.get(transition.state, transition.action);
+            self.update(old_value.as_ref(), transition.reward, next_value)
+        };
+        store(q, transition.state, transition.action, v)
+    }
+}
@@ -0,0 +1,283 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for schedules of the learning rate.
+
+use crate::strategy::learn::check_alpha;
+
+/// A schedule which gives the learning rate to make every update with, for example to learn
+/// quickly at first, and to settle on stable values as training goes on.
+///
+/// Schedules are used with
+/// [QLearning::with_schedule()](crate::strategy::learn::QLearning::with_schedule), which advances
+/// the schedule with every update of a learned value.
+///
+/// # Examples
+///
+/// With a constant learning rate, a value keeps following the most recent rewards, while a
+/// learning rate of `1 / (1 + t)` averages all rewards, and converges to their mean:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use rurel::mdp::{State, Transition};
+/// use rurel::strategy::learn::{InverseTimeDecay, LearningStrategy, QLearning};
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Coin;
+///
+/// impl State for Coin {
+///     type A = ();
+///     fn reward(&self) -> f64 {
+///         0.0
+///     }
+///     fn actions(&self) -> Vec<()> {
+///         vec![()]
+///     }
+/// }
+///
+/// fn learn(learning: &QLearning) -> f64 {
+///     let mut q = HashMap::new();
+///     for i in 0..1000 {
+///         // The reward alternates between 0 and 1, so its mean is 0.5
+///         let transition = Transition {
+///             state: &Coin,
+///             action: &(),
+///             reward: (i % 2) as f64,
+///             next_state: &Coin,
+///             next_action: None,
+///         };
+///         learning.learn(&mut q, &transition);
+///     }
+///     q[&Coin][&()]
+/// }
+///
+/// let constant = learn(&QLearning::new(0.5, 0.0, 0.0));
+/// let decaying = learn(&QLearning::with_schedule(InverseTimeDecay::new(1.0, 1.0), 0.0, 0.0));
+/// assert!((constant - 0.5).abs() > 0.1);
+/// assert!((decaying - 0.5).abs() < 0.01);
+/// ```
+pub trait LearningRateSchedule {
+    /// Returns the learning rate for the update with index `update`, counting from `0`.
+    fn alpha(&self, update: usize) -> f64;
+
+    /// Returns the learning rate for the update with index `update`, of a value whose state and
+    /// action were learned from `visits` times before. This is used instead of
+    /// [alpha()](LearningRateSchedule::alpha) when the trainer counts visits, as set with
+    /// [AgentTrainer::with_visit_counts()](crate::AgentTrainer::with_visit_counts). The default
+    /// implementation ignores the visits, and returns `alpha(update)`.
+    fn alpha_with_visits(&self, update: usize, _visits: u64) -> f64 {
+        self.alpha(update)
+    }
+}
+
+/// A [LearningRateSchedule] which always gives the same learning rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstantRate {
+    alpha: f64,
+}
+
+impl ConstantRate {
+    /// Constructs the schedule, which always gives `alpha`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive.
+    pub fn new(alpha: f64) -> ConstantRate {
+        check_alpha(alpha);
+        ConstantRate { alpha }
+    }
+}
+
+impl LearningRateSchedule for ConstantRate {
+    fn alpha(&self, _update: usize) -> f64 {
+        self.alpha
+    }
+}
+
+/// A [LearningRateSchedule] which decays the learning rate inversely with the number of updates,
+/// as `alpha / (1 + k * update)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InverseTimeDecay {
+    alpha: f64,
+    k: f64,
+}
+
+impl InverseTimeDecay {
+    /// Constructs the schedule, which starts at `alpha`, and decays faster for larger `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, or if `k` is negative, which would make the learning rate
+    /// grow, and eventually divide by zero.
+    pub fn new(alpha: f64, k: f64) -> InverseTimeDecay {
+        check_alpha(alpha);
+        assert!(
+            k >= 0.0,
+            "the decay of the learning rate must not be negative, but is {}",
+            k
+        );
+        InverseTimeDecay { alpha, k }
+    }
+}
+
+impl LearningRateSchedule for InverseTimeDecay {
+    fn alpha(&self, update: usize) -> f64 {
+        self.alpha / (1.0 + self.k * update as f64)
+    }
+}
+
+/// A [LearningRateSchedule] which multiplies the learning rate by `factor` after every `every`
+/// updates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepDecay {
+    alpha: f64,
+    factor: f64,
+    every: usize,
+}
+
+impl StepDecay {
+    /// Constructs the schedule, which starts at `alpha`, and is multiplied by `factor` after
+    /// every `every` updates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, if `factor` isn't above `0` and at most `1`, which would
+    /// make the learning rate grow or vanish, or if `every` is `0`.
+    pub fn new(alpha: f64, factor: f64, every: usize) -> StepDecay {
+        check_alpha(alpha);
+        assert!(
+            factor > 0.0 && factor <= 1.0,
+            "the factor of the learning rate must be above 0 and at most 1, but is {}",
+            factor
+        );
+        assert!(
+            every > 0,
+            "the number of updates between decays must be positive"
+        );
+        StepDecay {
+            alpha,
+            factor,
+            every,
+        }
+    }
+}
+
+impl LearningRateSchedule for StepDecay {
+    fn alpha(&self, update: usize) -> f64 {
+        self.alpha * self.factor.powi((update / self.every) as i32)
+    }
+}
+
+/// A [LearningRateSchedule] which decays the learning rate of every state and action with the
+/// number of times they were learned from, as `alpha / (1 + visits)`, so that rarely taken
+/// actions keep learning quickly while frequently taken ones settle. This needs the trainer to
+/// count visits, as set with
+/// [AgentTrainer::with_visit_counts()](crate::AgentTrainer::with_visit_counts); otherwise, the
+/// learning rate is always `alpha`.
+///
+/// The learning rates of every state and action sum up to infinity, while their squares don't,
+/// which are the Robbins-Monro conditions under which Q-learning converges, as long as every
+/// action keeps being taken. With an `alpha` of `1`, the learned value of an action with a
+/// terminal next state is the mean of its rewards.
+///
+/// # Examples
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::{LearningRateSchedule, QLearning, VisitCountDecay};
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// let schedule = VisitCountDecay::new(1.0);
+/// let rates: Vec<f64> = (0..10_000).map(|visits| schedule.alpha_with_visits(0, visits)).collect();
+/// // The sum grows without bound, like the harmonic series, but the sum of squares doesn't
+/// assert!(rates.iter().sum::<f64>() > 9.0);
+/// assert!(rates.iter().map(|alpha| alpha * alpha).sum::<f64>() < 1.65);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// enum Coin {
+///     Ready,
+///     Tossed,
+/// }
+///
+/// impl State for Coin {
+///     type A = ();
+///     fn reward(&self) -> f64 {
+///         0.0
+///     }
+///     fn actions(&self) -> Vec<()> {
+///         vec![()]
+///     }
+///     fn is_terminal(&self) -> bool {
+///         *self == Coin::Tossed
+///     }
+/// }
+///
+/// // Every toss ends an episode, and the tosses pay 0 and 1 in turn
+/// struct Tosser {
+///     coin: Coin,
+///     tosses: u32,
+/// }
+///
+/// impl Agent<Coin> for Tosser {
+///     fn current_state(&self) -> &Coin {
+///         &self.coin
+///     }
+///     fn take_action(&mut self, _: &()) {
+///         self.coin = Coin::Tossed;
+///         self.tosses += 1;
+///     }
+///     fn step_reward(&self, _: &()) -> f64 {
+///         (self.tosses % 2) as f64
+///     }
+/// }
+///
+/// fn learn(learning: &QLearning) -> f64 {
+///     let mut trainer = AgentTrainer::new()
+///         .with_initial_value(0.0)
+///         .with_visit_counts(true);
+///     let mut tosser = Tosser { coin: Coin::Ready, tosses: 0 };
+///     for _ in 0..1000 {
+///         tosser.coin = Coin::Ready;
+///         trainer.train(&mut tosser, learning, &mut FixedIterations::new(1), &RandomExploration);
+///     }
+///     trainer.expected_value(&Coin::Ready, &()).unwrap()
+/// }
+///
+/// // A constant learning rate keeps following the last tosses
+/// let constant = learn(&QLearning::new(0.5, 0.9, 0.0));
+/// assert!((constant - 0.5).abs() > 0.1);
+/// // Decaying with the visits averages all tosses
+/// let decaying = learn(&QLearning::with_schedule(VisitCountDecay::new(1.0), 0.9, 0.0));
+/// assert!((decaying - 0.5).abs() < 1e-9);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VisitCountDecay {
+    alpha: f64,
+}
+
+impl VisitCountDecay {
+    /// Constructs the schedule, which gives `alpha` for the first update of every state and
+    /// action.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive.
+    pub fn new(alpha: f64) -> VisitCountDecay {
+        check_alpha(alpha);
+        VisitCountDecay { alpha }
+    }
+}
+
+impl LearningRateSchedule for VisitCountDecay {
+    fn alpha(&self, _update: usize) -> f64 {
+        self.alpha
+    }
+
+    fn alpha_with_visits(&self, _update: usize, visits: u64) -> f64 {
+        self.alpha / (1.0 + visits as f64)
+    }
+}
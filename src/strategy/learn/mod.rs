@@ -6,22 +6,152 @@
 
 use std::collections::HashMap;
 
+pub use self::discount::{DiscountSchedule, LinearDiscount};
+pub use self::double_q::DoubleQLearning;
+pub use self::expected_sarsa::ExpectedSarsa;
+pub use self::learning_rate::{
+    ConstantRate, InverseTimeDecay, LearningRateSchedule, StepDecay, VisitCountDecay,
+};
+pub use self::monte_carlo::{MonteCarlo, VisitMode};
+pub use self::n_step_q::NStepQLearning;
 pub use self::q::QLearning;
-use crate::mdp::State;
+pub use self::q_lambda::QLambda;
+pub use self::sarsa::Sarsa;
+pub use self::soft_q::SoftQLearning;
+use crate::mdp::{State, Transition};
+use crate::{QValue, RurelError, ValueStore};
 
+pub mod discount;
+pub mod double_q;
+pub mod expected_sarsa;
+pub mod learning_rate;
+pub mod monte_carlo;
+pub mod n_step_q;
 pub mod q;
+pub mod q_lambda;
+pub mod sarsa;
+pub mod soft_q;
 
 /// A learning strategy can calculate a learned value for the action which was taken from the
 /// values for the actions in the new state (`new_action_values`), the current value
 /// (`current_value`), and the reward that was received after taking the action.
-pub trait LearningStrategy<S: State> {
+///
+/// The learned values are stored as `V`, but strategies compute with `f64`: values are converted
+/// into `f64` when they are read, and learned values are converted into `V` when they are stored.
+pub trait LearningStrategy<S: State, V: QValue = f64> {
     /// Calculates a learned value for the action which was taken from the
     /// values for the actions in the new state (`new_action_values`), the current value
     /// (`current_value`), and the reward that was received after taking the action.
     fn value(
         &self,
-        new_action_values: &Option<&HashMap<S::A, f64>>,
-        current_value: &Option<&f64>,
+        new_action_values: &Option<&HashMap<S::A, V>>,
+        current_value: &Option<&V>,
         received_reward: f64,
     ) -> f64;
+
+    /// Learns from the given `transition`, by updating the learned values `q`. The default
+    /// implementation stores the [value()](LearningStrategy::value) for the action which was
+    /// taken.
+    ///
+    /// Strategies that need more than what `value()` receives, such as the action that was taken
+    /// next, can override this method instead.
+    ///
+    /// Returns the largest absolute change of any learned value caused by this transition. A value
+    /// which is learned for the first time counts as an infinite change, since nothing was known
+    /// about it before, so that values still being discovered never look converged.
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        let v = {
+            let old_value = q.get(transition.state, transition.action);
+            let next_values = q.values(transition.next_state);
+            self.value(
+                &next_values.as_deref(),
+                &old_value.as_ref(),
+                transition.reward,
+            )
+        };
+        store(q, transition.state, transition.action, v)
+    }
+
+    /// Learns from `transition` like [learn()](LearningStrategy::learn), where its state and
+    /// action were learned from `visits` times before. The trainer calls this instead of
+    /// `learn()` when it counts visits, as set with
+    /// [AgentTrainer::with_visit_counts()](crate::AgentTrainer::with_visit_counts), so that
+    /// strategies can learn from rarely taken actions faster than from frequently taken ones.
+    /// The default implementation ignores the visits, and calls `learn()`.
+    fn learn_with_visits(
+        &self,
+        q: &mut dyn ValueStore<S, V>,
+        transition: &Transition<S>,
+        _visits: u64,
+    ) -> f64 {
+        self.learn(q, transition)
+    }
+
+    /// Returns whether this strategy keeps state between the transitions of an episode, such as
+    /// the transitions it didn't learn from yet, or eligibility traces. Such a strategy has to
+    /// learn from the transitions of one episode at a time, in the order they happened, so
+    /// [AgentTrainer::train_self_play()](crate::AgentTrainer::train_self_play), which learns
+    /// from the moves of two players in turns, refuses it. The default implementation returns
+    /// `false`.
+    fn keeps_episode_state(&self) -> bool {
+        false
+    }
+
+    /// Returns the state of this strategy which changes as it learns, such as the position of
+    /// its [learning rate schedule](LearningRateSchedule) or of its random generator, so that a
+    /// checkpoint of training can hold it. The default implementation returns no state.
+    fn save_state(&self) -> Vec<u64> {
+        Vec::new()
+    }
+
+    /// Restores the state returned by [save_state()](LearningStrategy::save_state), so that the
+    /// strategy continues where it was. Fails with [RurelError::InvalidFormat] if `state` isn't
+    /// a state of this strategy. The default implementation only accepts no state.
+    fn restore_state(&self, state: &[u64]) -> Result<(), RurelError> {
+        if state.is_empty() {
+            Ok(())
+        } else {
+            Err(RurelError::InvalidFormat(
+                "the learning strategy has no state to restore".to_string(),
+            ))
+        }
+    }
+}
+
+/// Stores the learned `value` for `action` in `state`, returning the absolute change compared to
+/// the previously learned value, or infinity if no value was learned before.
+fn store<S: State, V: QValue>(
+    q: &mut dyn ValueStore<S, V>,
+    state: &S,
+    action: &S::A,
+    value: f64,
+) -> f64 {
+    let value = V::from_f64(value);
+    q.set(state, action, value)
+        .map_or(f64::INFINITY, |old| (value.to_f64() - old.to_f64()).abs())
+}
+
+/// Panics with a message naming `alpha` if it isn't a positive learning rate.
+pub(crate) fn check_alpha(alpha: f64) {
+    assert!(
+        alpha > 0.0,
+        "the learning rate must be positive, but is {}",
+        alpha
+    );
+}
+
+/// Panics with a message naming `gamma` if it isn't a discount factor between `0` and `1`.
+pub(crate) fn check_gamma(gamma: f64) {
+    assert!(
+        (0.0..=1.0).contains(&gamma),
+        "the discount factor must be between 0 and 1, but is {}",
+        gamma
+    );
+}
+
+/// Returns the highest of `action_values`, or `None` if there are none.
+fn max_value<A, V: QValue>(action_values: Option<&HashMap<A, V>>) -> Option<f64> {
+    action_values
+        .and_then(|m| m.values().copied().max_by(|a, b| a.partial_cmp(b).unwrap()))
+        .map(V::to_f64)
 }
@@ -0,0 +1,194 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the Monte Carlo learning strategy.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use crate::mdp::{State, Transition};
+use crate::strategy::learn::{check_alpha, check_gamma, store, LearningStrategy};
+use crate::{QValue, ValueStore};
+
+/// Which visits of a state and action in an episode [MonteCarlo] learns from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitMode {
+    /// Only the first visit of every state and action in an episode is learned from, with the
+    /// return from there to the end of the episode. The returns learned from are independent
+    /// samples, so the values are unbiased estimates.
+    FirstVisit,
+    /// Every visit of a state and action is learned from, with the return from that visit to
+    /// the end of the episode. This learns from more returns, but those of the same episode
+    /// overlap.
+    EveryVisit,
+}
+
+/// The Monte Carlo learning strategy. Where [QLearning](super::QLearning) bootstraps from the
+/// learned value of the next state, this strategy waits until the episode ends, and moves the
+/// value of every action taken towards the discounted return that actually followed it: the
+/// sum of all rewards to the end of the episode, each discounted by `gamma` for every step
+/// before it. Nothing is bootstrapped, so the values aren't biased by wrong estimates of other
+/// values, at the cost of more variance, and of only learning once an episode is over.
+///
+/// The transitions of an episode are kept in a buffer by this strategy until training ends, and
+/// are learned from when
+/// [AgentTrainer::train()](../../../struct.AgentTrainer.html#method.train) returns, so every
+/// call of it should train a whole episode, ideally one that ends in a
+/// [terminal](State::is_terminal) state, for the returns to be those of the task.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::EpsilonGreedy;
+/// use rurel::strategy::learn::{MonteCarlo, VisitMode};
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::AgentTrainer;
+///
+/// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(3)`, and ends at 3 with a
+/// // reward of 1
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         if self.0 == 3 { 1.0 } else { 0.0 }
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 1]
+/// #     }
+/// #     fn is_terminal(&self) -> bool {
+/// #         self.0 == 3
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(0, 3));
+/// #     }
+/// # }
+///
+/// for visits in [VisitMode::FirstVisit, VisitMode::EveryVisit] {
+///     let learning = MonteCarlo::new(0.1, 0.9, 0.0, visits);
+///     let mut trainer = AgentTrainer::new();
+///     for _ in 0..500 {
+///         trainer.train(
+///             &mut Walker(Position(0)),
+///             &learning,
+///             &mut FixedIterations::new(100),
+///             &EpsilonGreedy::new(0.2),
+///         );
+///     }
+///
+///     // Every return after stepping onto the goal is its reward
+///     let value = trainer.expected_value(&Position(2), &1).unwrap();
+///     assert!((value - 1.0).abs() < 1e-6);
+///     for x in 0..3 {
+///         assert_eq!(trainer.best_action(&Position(x)), Some(1));
+///     }
+/// }
+/// ```
+pub struct MonteCarlo<S: State> {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+    visits: VisitMode,
+    episode: RefCell<Vec<(S, S::A, f64)>>,
+}
+
+impl<S: State> MonteCarlo<S> {
+    /// Constructs the Monte Carlo learning strategy, with learning rate `alpha`, discount factor
+    /// `gamma`, the initial value for Q `initial_value`, and which `visits` of every state and
+    /// action in an episode are learned from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, or if `gamma` isn't between `0` and `1`.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64, visits: VisitMode) -> MonteCarlo<S> {
+        check_alpha(alpha);
+        check_gamma(gamma);
+        MonteCarlo {
+            alpha,
+            gamma,
+            initial_value,
+            visits,
+            episode: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns which visits of every state and action in an episode are learned from.
+    pub fn visits(&self) -> VisitMode {
+        self.visits
+    }
+
+    fn update<V: QValue>(&self, old_value: Option<&V>, target: f64) -> f64 {
+        old_value.map_or(self.initial_value, |x| {
+            let x = x.to_f64();
+            x + self.alpha * (target - x)
+        })
+    }
+
+    /// Learns from every step of the finished `episode`, towards the return that followed it.
+    fn learn_episode<V: QValue>(
+        &self,
+        q: &mut dyn ValueStore<S, V>,
+        episode: &[(S, S::A, f64)],
+    ) -> f64 {
+        let mut returns = vec![0.0; episode.len()];
+        let mut g = 0.0;
+        for (i, (_, _, reward)) in episode.iter().enumerate().rev() {
+            g = reward + self.gamma * g;
+            returns[i] = g;
+        }
+        let mut visited = HashSet::new();
+        let mut change = 0.0;
+        for ((state, action, _), g) in episode.iter().zip(returns) {
+            if self.visits == VisitMode::FirstVisit && !visited.insert((state, action)) {
+                continue;
+            }
+            let v = self.update(q.get(state, action).as_ref(), g);
+            change = f64::max(change, store(q, state, action, v));
+        }
+        change
+    }
+}
+
+impl<S: State, V: QValue> LearningStrategy<S, V> for MonteCarlo<S> {
+    /// Without the rest of the episode, the return is the reward alone, as if the episode ended
+    /// after it.
+    fn value(
+        &self,
+        _new_action_values: &Option<&HashMap<S::A, V>>,
+        old_value: &Option<&V>,
+        reward_after_action: f64,
+    ) -> f64 {
+        self.update(*old_value, reward_after_action)
+    }
+
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        let mut episode = self.episode.borrow_mut();
+        episode.push((
+            transition.state.clone(),
+            transition.action.clone(),
+            transition.reward,
+        ));
+        if transition.next_action.is_some() {
+            return 0.0;
+        }
+        let change = self.learn_episode(q, &episode);
+        episode.clear();
+        change
+    }
+
+    /// The transitions of the episode so far.
+    fn keeps_episode_state(&self) -> bool {
+        true
+    }
+}
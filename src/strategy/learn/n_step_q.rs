@@ -0,0 +1,124 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the n-step Q Learning strategy.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use crate::mdp::{State, Transition};
+use crate::strategy::learn::{check_alpha, check_gamma, max_value, store, LearningStrategy};
+use crate::{QValue, ValueStore};
+
+/// The n-step Q Learning strategy. Where [QLearning](../q/struct.QLearning.html) updates the value
+/// of an action using only the reward received directly after it, this strategy waits for the
+/// next `n` rewards and learns from the discounted sum of those, plus the discounted value of the
+/// best action in the state reached after `n` steps. This propagates rewards back much faster in
+/// processes where they are only received after many steps. With `n = 1`, this is equivalent to Q
+/// learning.
+///
+/// The last `n` transitions are kept in a buffer by this strategy. When training ends, the
+/// transitions which are still buffered are learned from with the shorter returns up to the end
+/// of training, so rewards of different calls to
+/// [AgentTrainer::train()](../../../struct.AgentTrainer.html#method.train) are never mixed.
+pub struct NStepQLearning<S: State> {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+    n: usize,
+    window: RefCell<VecDeque<(S, S::A, f64)>>,
+}
+
+impl<S: State> NStepQLearning<S> {
+    /// Constructs the n-step Q Learning strategy, with learning rate `alpha`, discount factor
+    /// `gamma`, the initial value for Q `initial_value` and the number of steps `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, if `gamma` isn't between `0` and `1`, or if `n` is 0.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64, n: usize) -> NStepQLearning<S> {
+        check_alpha(alpha);
+        check_gamma(gamma);
+        assert!(n > 0, "n must be at least 1");
+        NStepQLearning {
+            alpha,
+            gamma,
+            initial_value,
+            n,
+            window: RefCell::new(VecDeque::with_capacity(n)),
+        }
+    }
+
+    fn max_value<V: QValue>(&self, action_values: Option<&HashMap<S::A, V>>) -> f64 {
+        max_value(action_values).unwrap_or(self.initial_value)
+    }
+
+    fn update<V: QValue>(&self, old_value: Option<&V>, target: f64) -> f64 {
+        old_value.map_or(self.initial_value, |x| {
+            let x = x.to_f64();
+            x + self.alpha * (target - x)
+        })
+    }
+
+    /// Learns from the oldest transition in the window, using the rewards of all transitions in
+    /// the window and the value of the best action in `last_state`.
+    fn learn_oldest<V: QValue>(
+        &self,
+        q: &mut dyn ValueStore<S, V>,
+        window: &mut VecDeque<(S, S::A, f64)>,
+        last_state: &S,
+    ) -> f64 {
+        let bootstrap = if last_state.is_terminal() {
+            0.0
+        } else {
+            q.max(last_state).map_or(self.initial_value, V::to_f64)
+        };
+        let target = window
+            .iter()
+            .rev()
+            .fold(bootstrap, |g, (_, _, reward)| reward + self.gamma * g);
+        let (state, action, _) = window.pop_front().unwrap();
+        let v = self.update(q.get(&state, &action).as_ref(), target);
+        store(q, &state, &action, v)
+    }
+}
+
+impl<S: State, V: QValue> LearningStrategy<S, V> for NStepQLearning<S> {
+    /// Without the rest of the window, this behaves like one-step Q learning.
+    fn value(
+        &self,
+        new_action_values: &Option<&HashMap<S::A, V>>,
+        old_value: &Option<&V>,
+        reward_after_action: f64,
+    ) -> f64 {
+        let max_next = self.max_value(*new_action_values);
+        self.update(*old_value, reward_after_action + self.gamma * max_next)
+    }
+
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        let mut window = self.window.borrow_mut();
+        window.push_back((
+            transition.state.clone(),
+            transition.action.clone(),
+            transition.reward,
+        ));
+        let mut change = 0.0;
+        if transition.next_action.is_none() {
+            while !window.is_empty() {
+                change = f64::max(
+                    change,
+                    self.learn_oldest(q, &mut window, transition.next_state),
+                );
+            }
+        } else if window.len() == self.n {
+            change = self.learn_oldest(q, &mut window, transition.next_state);
+        }
+        change
+    }
+
+    /// The last transitions, which weren't learned from yet.
+    fn keeps_episode_state(&self) -> bool {
+        true
+    }
+}
@@ -5,41 +5,210 @@
 //! Module for the Q Learning strategy.
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::mdp::State;
-use crate::strategy::learn::LearningStrategy;
+use crate::mdp::{State, Transition};
+use crate::strategy::learn::{
+    check_gamma, max_value, store, ConstantRate, LearningRateSchedule, LearningStrategy,
+};
+use crate::{QValue, RurelError, ValueStore};
 
 /// The Q Learning strategy
 pub struct QLearning {
-    alpha: f64,
+    alpha: Box<dyn LearningRateSchedule + Send + Sync>,
     gamma: f64,
     initial_value: f64,
+    /// The number of updates made so far, which the learning rate schedule advances with.
+    updates: AtomicUsize,
 }
 
 impl QLearning {
     /// Constructs the Q Learning strategy, with learning rate `alpha`, discount factor `gamma` and
     /// the initial value for Q `initial_value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, or if `gamma` isn't between `0` and `1`, naming the
+    /// value, as either would make the learned values meaningless.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::panic;
+    ///
+    /// use rurel::strategy::learn::QLearning;
+    ///
+    /// QLearning::new(0.1, 0.0, 0.0);
+    /// QLearning::new(1.0, 1.0, 0.0);
+    /// for (alpha, gamma) in [(0.0, 0.9), (-0.1, 0.9), (0.1, 1.5), (0.1, -0.1), (0.1, f64::NAN)] {
+    ///     assert!(panic::catch_unwind(|| QLearning::new(alpha, gamma, 0.0)).is_err());
+    /// }
+    /// ```
     pub fn new(alpha: f64, gamma: f64, initial_value: f64) -> QLearning {
+        QLearning::with_schedule(ConstantRate::new(alpha), gamma, initial_value)
+    }
+
+    /// Constructs the Q Learning strategy like [new()](QLearning::new), with a learning rate
+    /// which follows `schedule`. The schedule advances with every update of a learned value,
+    /// counted over all values. See [LearningRateSchedule] for an example.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` isn't between `0` and `1`.
+    pub fn with_schedule<R>(schedule: R, gamma: f64, initial_value: f64) -> QLearning
+    where
+        R: LearningRateSchedule + Send + Sync + 'static,
+    {
+        check_gamma(gamma);
         QLearning {
-            alpha,
+            alpha: Box::new(schedule),
             gamma,
             initial_value,
+            updates: AtomicUsize::new(0),
         }
     }
+
+    /// Returns the learning rate the next update is made with.
+    pub fn alpha(&self) -> f64 {
+        self.alpha.alpha(self.updates.load(Ordering::Relaxed))
+    }
+
+    /// Returns the discount factor.
+    pub fn gamma(&self) -> f64 {
+        self.gamma
+    }
+
+    /// Sets the discount factor, which is used by all values learned from then on. See
+    /// [DiscountSchedule](crate::strategy::learn::DiscountSchedule) for changing it as training
+    /// goes on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use rurel::mdp::State;
+    /// use rurel::strategy::learn::{LearningStrategy, QLearning};
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Start;
+    ///
+    /// impl State for Start {
+    ///     type A = ();
+    ///     fn reward(&self) -> f64 {
+    ///         0.0
+    ///     }
+    ///     fn actions(&self) -> Vec<()> {
+    ///         vec![()]
+    ///     }
+    /// }
+    ///
+    /// // The next state is worth 1, and the learning rate of 1 replaces the old value entirely
+    /// let next_values = HashMap::from([((), 1.0)]);
+    /// let mut learning = QLearning::new(1.0, 0.9, 0.0);
+    /// let value = |learning: &QLearning| {
+    ///     LearningStrategy::<Start>::value(learning, &Some(&next_values), &Some(&0.0), 0.0)
+    /// };
+    /// assert_eq!(value(&learning), 0.9);
+    ///
+    /// learning.set_gamma(0.5);
+    /// assert_eq!(learning.gamma(), 0.5);
+    /// assert_eq!(value(&learning), 0.5);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` isn't between `0` and `1`.
+    pub fn set_gamma(&mut self, gamma: f64) {
+        check_gamma(gamma);
+        self.gamma = gamma;
+    }
+
+    /// Returns the value learned from `old_value`, when the best action of the next state is
+    /// worth `max_next`, and its state and action were learned from `visits` times before, if
+    /// visits are counted.
+    fn update<V: QValue>(
+        &self,
+        old_value: Option<&V>,
+        reward: f64,
+        max_next: f64,
+        visits: Option<u64>,
+    ) -> f64 {
+        old_value.map_or(self.initial_value, |x| {
+            let x = x.to_f64();
+            let update = self.updates.fetch_add(1, Ordering::Relaxed);
+            let alpha = match visits {
+                Some(visits) => self.alpha.alpha_with_visits(update, visits),
+                None => self.alpha.alpha(update),
+            };
+            x + alpha * (reward + self.gamma * max_next - x)
+        })
+    }
+
+    /// Learns from `transition` like [LearningStrategy::learn()], with the visits of its state
+    /// and action, if they are counted.
+    fn learn_transition<S: State, V: QValue>(
+        &self,
+        q: &mut dyn ValueStore<S, V>,
+        transition: &Transition<S>,
+        visits: Option<u64>,
+    ) -> f64 {
+        let v = {
+            let max_next = if transition.next_state.is_terminal() {
+                0.0
+            } else {
+                q.max(transition.next_state)
+                    .map_or(self.initial_value, V::to_f64)
+            };
+            let old_value = q.get(transition.state, transition.action);
+            self.update(old_value.as_ref(), transition.reward, max_next, visits)
+        };
+        store(q, transition.state, transition.action, v)
+    }
 }
 
-impl<S: State> LearningStrategy<S> for QLearning {
+impl<S: State, V: QValue> LearningStrategy<S, V> for QLearning {
     fn value(
         &self,
-        new_action_values: &Option<&HashMap<S::A, f64>>,
-        old_value: &Option<&f64>,
+        new_action_values: &Option<&HashMap<S::A, V>>,
+        old_value: &Option<&V>,
         reward_after_action: f64,
     ) -> f64 {
-        let max_next = new_action_values
-            .and_then(|m| m.values().max_by(|a, b| a.partial_cmp(b).unwrap()))
-            .unwrap_or(&self.initial_value);
-        old_value.map_or(self.initial_value, |x| {
-            x + self.alpha * (reward_after_action + self.gamma * max_next - x)
-        })
+        let max_next = max_value(*new_action_values).unwrap_or(self.initial_value);
+        self.update(*old_value, reward_after_action, max_next, None)
+    }
+
+    /// Learns like [value()](LearningStrategy::value), except that nothing is bootstrapped from
+    /// a [terminal](State::is_terminal) next state, which is worth `0`.
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        self.learn_transition(q, transition, None)
+    }
+
+    /// Learns like [learn()](LearningStrategy::learn), with the learning rate the schedule gives
+    /// for the visits. See [VisitCountDecay](crate::strategy::learn::VisitCountDecay).
+    fn learn_with_visits(
+        &self,
+        q: &mut dyn ValueStore<S, V>,
+        transition: &Transition<S>,
+        visits: u64,
+    ) -> f64 {
+        self.learn_transition(q, transition, Some(visits))
+    }
+
+    /// The number of updates made so far, which the learning rate schedule advances with.
+    fn save_state(&self) -> Vec<u64> {
+        vec![self.updates.load(Ordering::Relaxed) as u64]
+    }
+
+    fn restore_state(&self, state: &[u64]) -> Result<(), RurelError> {
+        match state {
+            [updates] => {
+                self.updates.store(*updates as usize, Ordering::Relaxed);
+                Ok(())
+            }
+            _ => Err(RurelError::InvalidFormat(
+                "the state is not that of Q learning".to_string(),
+            )),
+        }
     }
 }
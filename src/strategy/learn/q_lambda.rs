@@ -0,0 +1,159 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the Q(λ) strategy.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::mdp::{State, Transition};
+use crate::strategy::learn::{check_alpha, check_gamma, max_value, LearningStrategy};
+use crate::{QValue, ValueStore};
+
+/// The Q(λ) strategy (Watkins's Q(λ)). Like [QLearning](../q/struct.QLearning.html), but instead
+/// of only updating the action which was taken last, every update is also applied to the actions
+/// taken before it, in proportion to their eligibility trace. The trace of an action is increased
+/// by 1 every time it is taken, and decays by `gamma * lambda` every step. This assigns credit for
+/// a reward to all recent actions at once. With `lambda = 0`, this is equivalent to Q learning.
+///
+/// Since the values learned are those of the greedy policy, the traces are cleared whenever an
+/// exploratory (non-greedy) action is taken. They are also cleared at the end of every episode,
+/// when training ends.
+///
+/// Actions without a learned value are assumed to have value `initial_value`.
+///
+/// # Memory
+///
+/// One trace is kept for every state-action pair visited since the traces were last cleared, and
+/// every update touches all of them. To keep this bounded, traces that decay below a threshold are
+/// dropped (`0.0001` by default, see
+/// [with_trace_threshold()](QLambda::with_trace_threshold)). With a higher `gamma * lambda` the
+/// traces live longer, so more of them are kept.
+///
+/// Unlike Q learning, actions taken for the first time are learned from right away, starting from
+/// `initial_value`.
+pub struct QLambda<S: State> {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+    lambda: f64,
+    trace_threshold: f64,
+    traces: RefCell<HashMap<(S, S::A), f64>>,
+}
+
+impl<S: State> QLambda<S> {
+    /// Constructs the Q(λ) strategy, with learning rate `alpha`, discount factor `gamma`, the
+    /// initial value for Q `initial_value` and trace decay `lambda`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, or if `gamma` or `lambda` isn't between `0` and `1`.
+    /// Traces of a `lambda` above `1` would grow rather than decay.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64, lambda: f64) -> QLambda<S> {
+        check_alpha(alpha);
+        check_gamma(gamma);
+        assert!(
+            (0.0..=1.0).contains(&lambda),
+            "the trace decay must be between 0 and 1, but is {}",
+            lambda
+        );
+        QLambda {
+            alpha,
+            gamma,
+            initial_value,
+            lambda,
+            trace_threshold: 0.0001,
+            traces: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the threshold below which traces are dropped.
+    pub fn with_trace_threshold(mut self, trace_threshold: f64) -> QLambda<S> {
+        self.trace_threshold = trace_threshold;
+        self
+    }
+
+    /// Returns the number of traces that are currently kept.
+    pub fn trace_count(&self) -> usize {
+        self.traces.borrow().len()
+    }
+
+    fn max_value<V: QValue>(&self, action_values: Option<&HashMap<S::A, V>>) -> f64 {
+        max_value(action_values).unwrap_or(self.initial_value)
+    }
+}
+
+impl<S: State, V: QValue> LearningStrategy<S, V> for QLambda<S> {
+    /// Without the traces, this behaves like Q learning.
+    fn value(
+        &self,
+        new_action_values: &Option<&HashMap<S::A, V>>,
+        old_value: &Option<&V>,
+        reward_after_action: f64,
+    ) -> f64 {
+        let max_next = self.max_value(*new_action_values);
+        old_value.map_or(self.initial_value, |x| {
+            let x = x.to_f64();
+            x + self.alpha * (reward_after_action + self.gamma * max_next - x)
+        })
+    }
+
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        let max_next = if transition.next_state.is_terminal() {
+            0.0
+        } else {
+            q.max(transition.next_state)
+                .map_or(self.initial_value, V::to_f64)
+        };
+        let mut change: f64 = 0.0;
+        let current = match q.get(transition.state, transition.action) {
+            Some(v) => v.to_f64(),
+            None => {
+                change = f64::INFINITY;
+                q.set(
+                    transition.state,
+                    transition.action,
+                    V::from_f64(self.initial_value),
+                );
+                self.initial_value
+            }
+        };
+        let delta = transition.reward + self.gamma * max_next - current;
+
+        let mut traces = self.traces.borrow_mut();
+        *traces
+            .entry((transition.state.clone(), transition.action.clone()))
+            .or_insert(0.0) += 1.0;
+        for ((state, action), trace) in traces.iter() {
+            if let Some(v) = q.get(state, action) {
+                q.set(
+                    state,
+                    action,
+                    V::from_f64(v.to_f64() + self.alpha * delta * trace),
+                );
+                change = change.max((self.alpha * delta * trace).abs());
+            }
+        }
+
+        let greedy = transition.next_action.is_some_and(|a| {
+            let v = q.get(transition.next_state, a);
+            v.map_or(self.initial_value, V::to_f64) >= max_next
+        });
+        if greedy {
+            let decay = self.gamma * self.lambda;
+            traces.retain(|_, trace| {
+                *trace *= decay;
+                *trace >= self.trace_threshold
+            });
+        } else {
+            traces.clear();
+        }
+        change
+    }
+
+    /// The eligibility traces.
+    fn keeps_episode_state(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,75 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the SARSA strategy.
+
+use std::collections::HashMap;
+
+use crate::mdp::{State, Transition};
+use crate::strategy::learn::{check_alpha, check_gamma, store, LearningStrategy};
+use crate::{QValue, ValueStore};
+
+/// The SARSA strategy. Unlike [QLearning](../q/struct.QLearning.html), which learns from the best
+/// action in the new state, SARSA learns from the action which the exploration strategy actually
+/// took next. This makes it an on-policy strategy: exploratory actions, and whatever they cost,
+/// are taken into account in the learned values.
+pub struct Sarsa {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+}
+
+impl Sarsa {
+    /// Constructs the SARSA strategy, with learning rate `alpha`, discount factor `gamma` and
+    /// the initial value for Q `initial_value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, or if `gamma` isn't between `0` and `1`.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64) -> Sarsa {
+        check_alpha(alpha);
+        check_gamma(gamma);
+        Sarsa {
+            alpha,
+            gamma,
+            initial_value,
+        }
+    }
+
+    fn update<V: QValue>(&self, old_value: Option<&V>, reward: f64, next_value: f64) -> f64 {
+        old_value.map_or(self.initial_value, |x| {
+            let x = x.to_f64();
+            x + self.alpha * (reward + self.gamma * next_value - x)
+        })
+    }
+}
+
+impl<S: State, V: QValue> LearningStrategy<S, V> for Sarsa {
+    /// Without knowing which action is taken next, the value of the next action is assumed to be
+    /// `initial_value`.
+    fn value(
+        &self,
+        _new_action_values: &Option<&HashMap<S::A, V>>,
+        old_value: &Option<&V>,
+        reward_after_action: f64,
+    ) -> f64 {
+        self.update(*old_value, reward_after_action, self.initial_value)
+    }
+
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        let v = {
+            let next_value = if transition.next_state.is_terminal() {
+                0.0
+            } else {
+                transition
+                    .next_action
+                    .and_then(|a| q.get(transition.next_state, a))
+                    .map_or(self.initial_value, V::to_f64)
+            };
+            let old_value = q.get(transition.state, transition.action);
+            self.update(old_value.as_ref(), transition.reward, next_value)
+        };
+        store(q, transition.state, transition.action, v)
+    }
+}
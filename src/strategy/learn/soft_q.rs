@@ -0,0 +1,183 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the soft Q Learning strategy.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::mdp::{State, Transition};
+use crate::strategy::learn::{check_alpha, check_gamma, max_value, store, LearningStrategy};
+use crate::{QValue, ValueStore};
+
+/// The soft Q Learning strategy, an entropy-regularized version of
+/// [QLearning](../q/struct.QLearning.html). Instead of the value of the best action in the new
+/// state, it learns from the soft value of that state, `temperature * ln(sum(exp(q / temperature)))`
+/// over the values `q` of its actions. This is the value of acting with the softmax policy of
+/// [policy()](SoftQLearning::policy), with a bonus for its entropy, so actions that are almost
+/// as good as the best one keep being worth exploring.
+///
+/// The soft value is always at least the value of the best action, and approaches it as the
+/// temperature goes to `0`. With a temperature of `0`, this is the same as Q Learning.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use rurel::mdp::State;
+/// use rurel::strategy::learn::{LearningStrategy, QLearning, SoftQLearning};
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Start;
+///
+/// impl State for Start {
+///     type A = char;
+///     fn reward(&self) -> f64 {
+///         0.0
+///     }
+///     fn actions(&self) -> Vec<char> {
+///         vec!['a', 'b']
+///     }
+/// }
+///
+/// let next_values = HashMap::from([('a', 1.0), ('b', 0.0)]);
+/// let value = |learning: &dyn LearningStrategy<Start>| {
+///     learning.value(&Some(&next_values), &Some(&0.0), 0.0)
+/// };
+///
+/// // The policy is the softmax of the values at the temperature
+/// let soft = SoftQLearning::new(1.0, 1.0, 0.5, 0.0);
+/// let policy = soft.policy(&next_values);
+/// let expected = 2f64.exp() / (2f64.exp() + 1.0);
+/// assert!((policy[&'a'] - expected).abs() < 1e-12);
+/// assert!((policy[&'b'] - (1.0 - expected)).abs() < 1e-12);
+///
+/// // With a learning rate and discount factor of 1, the learned value is the soft value
+/// assert!((value(&soft) - 0.5 * (2f64.exp() + 1.0).ln()).abs() < 1e-12);
+///
+/// // As the temperature goes to 0, soft Q Learning becomes Q Learning
+/// let hard = value(&QLearning::new(1.0, 1.0, 0.0));
+/// assert!(value(&SoftQLearning::new(1.0, 1.0, 0.01, 0.0)) - hard < 1e-6);
+/// assert_eq!(value(&SoftQLearning::new(1.0, 1.0, 0.0, 0.0)), hard);
+/// ```
+pub struct SoftQLearning {
+    alpha: f64,
+    gamma: f64,
+    temperature: f64,
+    initial_value: f64,
+}
+
+impl SoftQLearning {
+    /// Constructs the soft Q Learning strategy, with learning rate `alpha`, discount factor
+    /// `gamma`, the `temperature` of the soft value, and the initial value for Q
+    /// `initial_value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't positive, if `gamma` isn't between `0` and `1`, or if
+    /// `temperature` is negative.
+    pub fn new(alpha: f64, gamma: f64, temperature: f64, initial_value: f64) -> SoftQLearning {
+        check_alpha(alpha);
+        check_gamma(gamma);
+        assert!(temperature >= 0.0, "the temperature must not be negative");
+        SoftQLearning {
+            alpha,
+            gamma,
+            temperature,
+            initial_value,
+        }
+    }
+
+    /// Returns the temperature of the soft value.
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    /// Returns the probability of every action in `action_values` under the softmax policy at
+    /// the temperature of this strategy, whose soft value this strategy learns from. With a
+    /// temperature of `0`, the best actions share all probability.
+    pub fn policy<A: Eq + Hash + Clone, V: QValue>(
+        &self,
+        action_values: &HashMap<A, V>,
+    ) -> HashMap<A, f64> {
+        let soft_value = match self.soft_value(action_values) {
+            Some(soft_value) => soft_value,
+            None => return HashMap::new(),
+        };
+        if self.temperature == 0.0 {
+            let best = action_values
+                .values()
+                .filter(|v| v.to_f64() == soft_value)
+                .count();
+            return action_values
+                .iter()
+                .map(|(a, v)| {
+                    let p = if v.to_f64() == soft_value { 1.0 } else { 0.0 };
+                    (a.clone(), p / best as f64)
+                })
+                .collect();
+        }
+        action_values
+            .iter()
+            .map(|(a, v)| {
+                let p = ((v.to_f64() - soft_value) / self.temperature).exp();
+                (a.clone(), p)
+            })
+            .collect()
+    }
+
+    /// Returns the soft value of a state with the values `action_values`, or `None` if there are
+    /// none.
+    fn soft_value<A, V: QValue>(&self, action_values: &HashMap<A, V>) -> Option<f64> {
+        let max = max_value(Some(action_values))?;
+        if self.temperature == 0.0 || max.is_infinite() {
+            return Some(max);
+        }
+        // subtracting the maximum keeps the exponentials from overflowing
+        let sum: f64 = action_values
+            .values()
+            .map(|v| ((v.to_f64() - max) / self.temperature).exp())
+            .sum();
+        Some(max + self.temperature * sum.ln())
+    }
+
+    fn update<V: QValue>(&self, old_value: Option<&V>, reward: f64, next_value: f64) -> f64 {
+        old_value.map_or(self.initial_value, |x| {
+            let x = x.to_f64();
+            x + self.alpha * (reward + self.gamma * next_value - x)
+        })
+    }
+}
+
+impl<S: State, V: QValue> LearningStrategy<S, V> for SoftQLearning {
+    fn value(
+        &self,
+        new_action_values: &Option<&HashMap<S::A, V>>,
+        old_value: &Option<&V>,
+        reward_after_action: f64,
+    ) -> f64 {
+        let next_value = new_action_values
+            .and_then(|m| self.soft_value(m))
+            .unwrap_or(self.initial_value);
+        self.update(*old_value, reward_after_action, next_value)
+    }
+
+    /// Learns like [value()](LearningStrategy::value), except that nothing is bootstrapped from
+    /// a [terminal](State::is_terminal) next state, which is worth `0`.
+    fn learn(&self, q: &mut dyn ValueStore<S, V>, transition: &Transition<S>) -> f64 {
+        let v = {
+            let next_value = if transition.next_state.is_terminal() {
+                0.0
+            } else {
+                q.values(transition.next_state)
+                    .and_then(|m| self.soft_value(&m))
+                    .unwrap_or(self.initial_value)
+            };
+            let old_value = q.get(transition.state, transition.action);
+            self.update(old_value.as_ref(), transition.reward, next_value)
+        };
+        store(q, transition.state, transition.action, v)
+    }
+}
@@ -0,0 +1,102 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the strategies combining other termination strategies.
+
+use crate::mdp::State;
+use crate::strategy::terminate::TerminationStrategy;
+
+/// The termination strategy that ends once any of the given strategies decides to stop.
+///
+/// All strategies are asked on every step, even if an earlier one already decided to stop, so
+/// strategies which count steps stay consistent.
+///
+/// # Example
+///
+/// Stopping at a sink state, after 500 steps, or after 2 seconds, whichever comes first:
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use rurel::mdp::State;
+/// use rurel::strategy::terminate::{AnyOf, FixedIterations, SinkStates, TimeLimit};
+///
+/// fn termination<S: State>() -> AnyOf<S> {
+///     AnyOf::new(vec![
+///         Box::new(SinkStates),
+///         Box::new(FixedIterations::new(500)),
+///         Box::new(TimeLimit::new(Duration::from_secs(2))),
+///     ])
+/// }
+/// ```
+pub struct AnyOf<S: State> {
+    strategies: Vec<Box<dyn TerminationStrategy<S>>>,
+}
+
+impl<S: State> AnyOf<S> {
+    /// Constructs a new termination strategy which ends when any of `strategies` decides to stop.
+    pub fn new(strategies: Vec<Box<dyn TerminationStrategy<S>>>) -> AnyOf<S> {
+        AnyOf { strategies }
+    }
+}
+
+impl<S: State> TerminationStrategy<S> for AnyOf<S> {
+    fn should_stop(&mut self, state: &S) -> bool {
+        let mut stop = false;
+        for strategy in &mut self.strategies {
+            stop |= strategy.should_stop(state);
+        }
+        stop
+    }
+
+    fn observe_update(&mut self, change: f64) {
+        for strategy in &mut self.strategies {
+            strategy.observe_update(change);
+        }
+    }
+
+    fn start_episode(&mut self) {
+        for strategy in &mut self.strategies {
+            strategy.start_episode();
+        }
+    }
+}
+
+/// The termination strategy that ends once all of the given strategies decide to stop on the same
+/// step.
+///
+/// All strategies are asked on every step, even if an earlier one already decided not to stop, so
+/// strategies which count steps stay consistent.
+pub struct AllOf<S: State> {
+    strategies: Vec<Box<dyn TerminationStrategy<S>>>,
+}
+
+impl<S: State> AllOf<S> {
+    /// Constructs a new termination strategy which ends when all of `strategies` decide to stop.
+    pub fn new(strategies: Vec<Box<dyn TerminationStrategy<S>>>) -> AllOf<S> {
+        AllOf { strategies }
+    }
+}
+
+impl<S: State> TerminationStrategy<S> for AllOf<S> {
+    fn should_stop(&mut self, state: &S) -> bool {
+        let mut stop = true;
+        for strategy in &mut self.strategies {
+            stop &= strategy.should_stop(state);
+        }
+        stop
+    }
+
+    fn observe_update(&mut self, change: f64) {
+        for strategy in &mut self.strategies {
+            strategy.observe_update(change);
+        }
+    }
+
+    fn start_episode(&mut self) {
+        for strategy in &mut self.strategies {
+            strategy.start_episode();
+        }
+    }
+}
@@ -0,0 +1,127 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the convergence strategy.
+
+use crate::mdp::State;
+use crate::strategy::terminate::TerminationStrategy;
+
+/// The termination strategy that ends once the learned values stop changing: when in `patience`
+/// consecutive episodes, no update changed a learned value by more than `threshold`.
+///
+/// The changes are reported by the trainer through
+/// [TerminationStrategy::observe_update()](trait.TerminationStrategy.html#method.observe_update),
+/// and an episode is done when the next one starts. Learning a value for the first time counts
+/// as an infinite change, so no episode which discovers new states or actions is calm, and
+/// episodes without any updates don't count. Once the values converged, every later episode is
+/// stopped right away. To train until they converge, train episodes until
+/// [is_converged()](Converged::is_converged) returns `true`, which also counts the episode that
+/// was trained last.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::SeededRandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::Converged;
+/// use rurel::AgentTrainer;
+///
+/// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(3)`, and ends at 3 with a
+/// // reward of 1
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         if self.0 == 3 { 1.0 } else { 0.0 }
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 1]
+/// #     }
+/// #     fn is_terminal(&self) -> bool {
+/// #         self.0 == 3
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(0, 3));
+/// #     }
+/// # }
+///
+/// let mut trainer = AgentTrainer::new();
+/// let exploration = SeededRandomExploration::new(0);
+/// let mut converged = Converged::new(1e-4, 5);
+/// let mut episodes = 0;
+/// while !converged.is_converged() {
+///     let learning = QLearning::new(0.5, 0.9, 0.0);
+///     trainer.train(&mut Walker(Position(0)), &learning, &mut converged, &exploration);
+///     episodes += 1;
+/// }
+///
+/// // Discovering all values alone takes a few episodes, and every episode after it some more
+/// assert!(episodes > 5);
+/// let value = trainer.expected_value(&Position(2), &1).unwrap();
+/// assert!((value - 1.0).abs() < 1e-3);
+/// ```
+pub struct Converged {
+    threshold: f64,
+    patience: usize,
+    /// The number of consecutive calm episodes, up to the last finished one.
+    calm_episodes: usize,
+    /// The largest change in the current episode, or `None` if nothing was updated in it.
+    episode_change: Option<f64>,
+}
+
+impl Converged {
+    /// Constructs a new termination strategy which ends when in `patience` consecutive episodes,
+    /// no update changed a learned value by more than `threshold`.
+    pub fn new(threshold: f64, patience: usize) -> Converged {
+        Converged {
+            threshold,
+            patience,
+            calm_episodes: 0,
+            episode_change: None,
+        }
+    }
+
+    /// Returns whether the learned values converged, counting the current episode as if it
+    /// ended now.
+    pub fn is_converged(&self) -> bool {
+        self.calm_streak() >= self.patience
+    }
+
+    /// Returns the number of consecutive calm episodes, up to the current one.
+    fn calm_streak(&self) -> usize {
+        match self.episode_change {
+            None => self.calm_episodes,
+            Some(change) if change <= self.threshold => self.calm_episodes + 1,
+            Some(_) => 0,
+        }
+    }
+}
+
+impl<S: State> TerminationStrategy<S> for Converged {
+    /// Only finished episodes count, so that an episode isn't stopped because its first updates
+    /// happened to be small.
+    fn should_stop(&mut self, _: &S) -> bool {
+        self.calm_episodes >= self.patience
+    }
+
+    fn observe_update(&mut self, change: f64) {
+        self.episode_change = Some(self.episode_change.map_or(change, |c| c.max(change)));
+    }
+
+    fn start_episode(&mut self) {
+        self.calm_episodes = self.calm_streak();
+        self.episode_change = None;
+    }
+}
@@ -4,15 +4,35 @@
 
 //! Module containing termination strategies.
 
+pub use self::combine::{AllOf, AnyOf};
+pub use self::converged::Converged;
 pub use self::fixed_iterations::FixedIterations;
+pub use self::reward_threshold::{Comparison, RewardThreshold};
 pub use self::sink_states::SinkStates;
+pub use self::time_limit::TimeLimit;
 use crate::mdp::State;
 
+pub mod combine;
+pub mod converged;
 pub mod fixed_iterations;
+pub mod reward_threshold;
 pub mod sink_states;
+pub mod time_limit;
 
 /// A termination strategy decides when to end training.
 pub trait TerminationStrategy<S: State> {
     /// If `should_stop` returns `true`, training will end.
     fn should_stop(&mut self, state: &S) -> bool;
+
+    /// Called during training after every update of the learned values, with the largest
+    /// absolute change of any learned value caused by the update, which is infinite for values
+    /// learned for the first time. Updates during an episode are observed before the next
+    /// `should_stop`, and those at the end of an episode after the `should_stop` which ended it.
+    /// The default implementation ignores it.
+    fn observe_update(&mut self, _change: f64) {}
+
+    /// Called by the trainers at the start of every episode, before its first step, so that
+    /// strategies which keep track of an episode can start over. The default implementation does
+    /// nothing.
+    fn start_episode(&mut self) {}
 }
@@ -0,0 +1,107 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the reward threshold strategy.
+
+use crate::mdp::State;
+use crate::strategy::terminate::TerminationStrategy;
+
+/// How the cumulative reward is compared to the threshold of a [RewardThreshold].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// Stop once the cumulative reward is greater than the threshold.
+    Above,
+    /// Stop once the cumulative reward is less than the threshold.
+    Below,
+}
+
+/// The termination strategy that ends once the cumulative reward of the visited states exceeds
+/// (or drops below) a threshold. The reward of every `State` passed to `should_stop` is added up,
+/// and the sum is reset when the next episode starts, however the last one ended, so the same
+/// strategy can be used for all episodes.
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::{Comparison, RewardThreshold};
+/// use rurel::AgentTrainer;
+///
+/// // A `Walker` steps from `Position(x)` to `Position(x + 1)` with its only action `()`, and is
+/// // rewarded with 1 for every step, until it ends at 3
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = ();
+/// #     fn reward(&self) -> f64 {
+/// #         1.0
+/// #     }
+/// #     fn actions(&self) -> Vec<()> {
+/// #         vec![()]
+/// #     }
+/// #     fn is_terminal(&self) -> bool {
+/// #         self.0 == 3
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, _: &()) {
+/// #         self.0 = Position(self.0 .0 + 1);
+/// #     }
+/// # }
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut threshold = RewardThreshold::new(4.5, Comparison::Above);
+/// for _ in 0..2 {
+///     // Both episodes end in the terminal state, before the threshold is reached
+///     let mut agent = Walker(Position(0));
+///     trainer.train(&mut agent, &QLearning::new(0.2, 0.9, 0.0), &mut threshold, &RandomExploration);
+///     assert_eq!(agent.current_state().0, 3);
+///     assert_eq!(threshold.total_reward(), 3.0);
+/// }
+/// ```
+pub struct RewardThreshold {
+    threshold: f64,
+    comparison: Comparison,
+    total: f64,
+}
+
+impl RewardThreshold {
+    /// Constructs a new termination strategy which ends when the cumulative reward is above or
+    /// below (depending on `comparison`) `threshold`.
+    pub fn new(threshold: f64, comparison: Comparison) -> RewardThreshold {
+        RewardThreshold {
+            threshold,
+            comparison,
+            total: 0.0,
+        }
+    }
+
+    /// Returns the cumulative reward of the current episode.
+    pub fn total_reward(&self) -> f64 {
+        self.total
+    }
+}
+
+impl<S: State> TerminationStrategy<S> for RewardThreshold {
+    fn should_stop(&mut self, state: &S) -> bool {
+        self.total += state.reward();
+        match self.comparison {
+            Comparison::Above => self.total > self.threshold,
+            Comparison::Below => self.total < self.threshold,
+        }
+    }
+
+    fn start_episode(&mut self) {
+        self.total = 0.0;
+    }
+}
@@ -0,0 +1,37 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the time limit strategy.
+
+use std::time::{Duration, Instant};
+
+use crate::mdp::State;
+use crate::strategy::terminate::TerminationStrategy;
+
+/// The termination strategy that ends once a certain amount of wall-clock time has passed,
+/// regardless of the `State`. The clock starts at the first call to `should_stop`, which normally
+/// happens after the first step of training.
+///
+/// The time is checked on every step, which only costs a call to [Instant::now()]. Unlike
+/// [FixedIterations](../fixed_iterations/struct.FixedIterations.html), the amount of training
+/// depends on the speed of the machine, so this is useful to give different strategies an equal
+/// time budget.
+pub struct TimeLimit {
+    limit: Duration,
+    start: Option<Instant>,
+}
+
+impl TimeLimit {
+    /// Constructs a new termination strategy which ends when `limit` has passed since the first
+    /// step.
+    pub fn new(limit: Duration) -> TimeLimit {
+        TimeLimit { limit, start: None }
+    }
+}
+
+impl<S: State> TerminationStrategy<S> for TimeLimit {
+    fn should_stop(&mut self, _: &S) -> bool {
+        self.start.get_or_insert_with(Instant::now).elapsed() >= self.limit
+    }
+}
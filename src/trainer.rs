@@ -0,0 +1,164 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the interface shared by the tabular trainer and the DQN.
+
+use std::collections::HashMap;
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::learn::LearningStrategy;
+use crate::strategy::terminate::TerminationStrategy;
+use crate::{AgentTrainer, QValue, ValueStore};
+
+/// Something that learns the values of the actions of states from episodes, so that code which
+/// trains or evaluates a trainer, such as a benchmark harness, can be written once for all of
+/// them. It is implemented by the `DQNAgentTrainer` of the `dqn` feature, and by an
+/// [AgentTrainer] together with the learning strategy it learns with, as returned by
+/// [AgentTrainer::as_trainer()].
+///
+/// # Example
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::SeededRandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::SinkStates;
+/// use rurel::{AgentTrainer, Trainer};
+///
+/// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(3)`, and ends at 3 with a
+/// // reward of 1
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         if self.0 == 3 { 1.0 } else { 0.0 }
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 1]
+/// #     }
+/// #     fn is_terminal(&self) -> bool {
+/// #         self.0 == 3
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(0, 3));
+/// #     }
+/// # }
+///
+/// // Counts the steps it takes to reach the goal with the best actions, whatever the trainer
+/// fn steps_to_goal(trainer: &dyn Trainer<Position>) -> Option<usize> {
+///     let mut agent = Walker(Position(0));
+///     for steps in 0..10 {
+///         if agent.current_state().is_terminal() {
+///             return Some(steps);
+///         }
+///         agent.take_action(&trainer.best_action(agent.current_state())?);
+///     }
+///     None
+/// }
+///
+/// let mut trainer = AgentTrainer::new();
+/// let learning = QLearning::new(0.5, 0.9, 0.0);
+/// let mut tabular = trainer.as_trainer(&learning);
+/// let exploration = SeededRandomExploration::new(0);
+/// for _ in 0..50 {
+///     tabular.train(&mut Walker(Position(0)), &mut SinkStates, &exploration);
+/// }
+///
+/// assert_eq!(steps_to_goal(&tabular), Some(3));
+/// assert!(tabular.action_values(&Position(2))[&1] > 0.5);
+/// ```
+pub trait Trainer<S: State> {
+    /// Trains for a single episode with `agent`, which ends when the agent arrives at a
+    /// [terminal](State::is_terminal) state, or when `termination_strategy` decides to stop.
+    fn train(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    );
+
+    /// Returns the best action for `state`, or `None` if there is none.
+    fn best_action(&self, state: &S) -> Option<S::A>;
+
+    /// Returns the value of every action of `state` that the trainer has a value for.
+    fn action_values(&self, state: &S) -> HashMap<S::A, f64>;
+}
+
+/// An [AgentTrainer] together with the learning strategy it learns with, which is a [Trainer],
+/// as returned by [AgentTrainer::as_trainer()].
+pub struct TabularTrainer<'a, S, Q = HashMap<S, HashMap<<S as State>::A, f64>>>
+where
+    S: State,
+    Q: ValueStore<S>,
+{
+    trainer: &'a mut AgentTrainer<S, f64, Q>,
+    learning_strategy: &'a dyn LearningStrategy<S>,
+}
+
+impl<S, Q> AgentTrainer<S, f64, Q>
+where
+    S: State,
+    Q: ValueStore<S>,
+{
+    /// Returns this trainer as a [Trainer], which trains with `learning_strategy`, like
+    /// [train()](AgentTrainer::train).
+    pub fn as_trainer<'a>(
+        &'a mut self,
+        learning_strategy: &'a dyn LearningStrategy<S>,
+    ) -> TabularTrainer<'a, S, Q> {
+        TabularTrainer {
+            trainer: self,
+            learning_strategy,
+        }
+    }
+}
+
+impl<S, Q> Trainer<S> for TabularTrainer<'_, S, Q>
+where
+    S: State,
+    Q: ValueStore<S>,
+{
+    fn train(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        self.trainer.train(
+            agent,
+            self.learning_strategy,
+            termination_strategy,
+            exploration_strategy,
+        );
+    }
+
+    fn best_action(&self, state: &S) -> Option<S::A> {
+        self.trainer.best_action(state)
+    }
+
+    /// Returns the values learned for `state`, which are empty if none were learned.
+    fn action_values(&self, state: &S) -> HashMap<S::A, f64> {
+        self.trainer
+            .store()
+            .values(state)
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|(action, value)| (action.clone(), value.to_f64()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,96 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the types learned values can be stored as.
+
+use std::fmt::Debug;
+
+/// A floating point type which the values learned by an [AgentTrainer](crate::AgentTrainer) are
+/// stored as. This is `f64` by default.
+///
+/// Strategies compute with `f64`, and only convert their results into the value type when
+/// storing them, so storing values as `f32` halves the memory the learned values take, at the
+/// cost of the precision of the stored values.
+///
+/// An `AgentTrainer` storing `f32` values is created with `AgentTrainer::<S, f32>::default()`,
+/// and is trained with the same strategies:
+///
+/// ```
+/// use rurel::mdp::{Agent, State};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::{AgentTrainer, QValue};
+///
+/// // A `Walker` steps by -1 or 1 between `Position(0)` and `Position(4)`, and is rewarded with 1
+/// // at 4
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Position(i32);
+/// #
+/// # impl State for Position {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 {
+/// #         if self.0 == 4 { 1.0 } else { 0.0 }
+/// #     }
+/// #     fn actions(&self) -> Vec<i32> {
+/// #         vec![-1, 1]
+/// #     }
+/// # }
+/// #
+/// # struct Walker(Position);
+/// #
+/// # impl Agent<Position> for Walker {
+/// #     fn current_state(&self) -> &Position {
+/// #         &self.0
+/// #     }
+/// #     fn take_action(&mut self, step: &i32) {
+/// #         self.0 = Position((self.0 .0 + step).clamp(0, 4));
+/// #     }
+/// # }
+///
+/// fn train<V: QValue>(mut trainer: AgentTrainer<Position, V>) -> AgentTrainer<Position, V> {
+///     trainer.train(
+///         &mut Walker(Position(0)),
+///         &QLearning::new(0.2, 0.9, 0.0),
+///         &mut FixedIterations::new(5000),
+///         &RandomExploration,
+///     );
+///     trainer
+/// }
+///
+/// let single = train(AgentTrainer::<Position, f32>::default());
+/// let double = train(AgentTrainer::<Position, f64>::default());
+/// for x in 0..4 {
+///     // Both learn to walk towards the reward
+///     assert_eq!(single.best_action(&Position(x)), Some(1));
+///     assert_eq!(double.best_action(&Position(x)), Some(1));
+/// }
+/// ```
+pub trait QValue: Copy + PartialOrd + Debug {
+    /// Converts `value` into this type, rounding it if needed.
+    fn from_f64(value: f64) -> Self;
+
+    /// Converts this value into an `f64`.
+    fn to_f64(self) -> f64;
+}
+
+impl QValue for f64 {
+    fn from_f64(value: f64) -> f64 {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl QValue for f32 {
+    fn from_f64(value: f64) -> f32 {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}